@@ -0,0 +1,165 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::env_utils::EnvParams;
+
+/// Outcomes of the last `window` executions, giving the rolling failure rate.
+#[derive(Debug)]
+pub struct RecentOutcomes {
+    window: usize,
+    /// `true` for a failed execution, oldest first.
+    outcomes: Mutex<VecDeque<bool>>,
+}
+
+impl RecentOutcomes {
+    /// Creates a record of the last `window` outcomes (at least one).
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            outcomes: Mutex::new(VecDeque::with_capacity(window)),
+        }
+    }
+
+    /// Records the outcome of an execution, forgetting the oldest one past the window.
+    pub fn record(&self, failed: bool) {
+        let mut outcomes = self.outcomes.lock().unwrap();
+        if outcomes.len() == self.window {
+            outcomes.pop_front();
+        }
+        outcomes.push_back(failed);
+    }
+
+    /// Returns the share of failures among the recorded outcomes, 0 before any.
+    pub fn failure_rate(&self) -> f64 {
+        let outcomes = self.outcomes.lock().unwrap();
+        if outcomes.is_empty() {
+            return 0.0;
+        }
+        outcomes.iter().filter(|failed| **failed).count() as f64 / outcomes.len() as f64
+    }
+}
+
+/// Slows the generation down while the rolling failure rate is high, a softer alternative to
+/// stopping the run: once the rate reaches `slow_above`, every transaction is delayed by
+/// `delay` until the rate falls back to `resume_below`.
+#[derive(Debug, Clone)]
+pub struct AdaptiveThrottle {
+    outcomes: Arc<RecentOutcomes>,
+    slow_above: f64,
+    resume_below: f64,
+    delay: Duration,
+    slowed: bool,
+}
+
+impl AdaptiveThrottle {
+    /// Creates a throttle driven by `outcomes`.
+    ///
+    /// # Arguments
+    /// * `outcomes` - The recent outcomes recorded by the limiter.
+    /// * `slow_above` - The failure rate from which the generation slows down.
+    /// * `resume_below` - The failure rate from which it speeds back up.
+    /// * `delay` - The delay added to every transaction while slowed down.
+    pub fn new(
+        outcomes: Arc<RecentOutcomes>,
+        slow_above: f64,
+        resume_below: f64,
+        delay: Duration,
+    ) -> Self {
+        Self {
+            outcomes,
+            slow_above,
+            resume_below: resume_below.min(slow_above),
+            delay,
+            slowed: false,
+        }
+    }
+
+    /// Creates the throttle of `backoff_failure_rate` over the outcomes of the limiter,
+    /// when configured.
+    ///
+    /// # Arguments
+    /// * `params` - The parameters of the run.
+    /// * `outcomes` - The recent outcomes recorded by the limiter (`LimitChecker::recent_outcomes`).
+    pub fn from_params(params: &EnvParams, outcomes: Option<&Arc<RecentOutcomes>>) -> Option<Self> {
+        let slow_above = params.backoff_failure_rate?;
+        Some(Self::new(
+            outcomes?.clone(),
+            slow_above,
+            params.backoff_recovery_rate.unwrap_or(slow_above / 2.0),
+            Duration::from_millis(params.backoff_delay_ms),
+        ))
+    }
+
+    /// Returns the delay to add before the next transaction, updating whether the generation
+    /// is slowed down from the current failure rate.
+    pub fn next_delay(&mut self) -> Duration {
+        let rate = self.outcomes.failure_rate();
+        if !self.slowed && rate >= self.slow_above {
+            warn!(
+                "Recent failure rate {:.0}%: slowing down the generation.",
+                rate * 100.0
+            );
+            self.slowed = true;
+        } else if self.slowed && rate <= self.resume_below {
+            info!(
+                "Recent failure rate {:.0}%: generation back to full speed.",
+                rate * 100.0
+            );
+            self.slowed = false;
+        }
+
+        if self.slowed {
+            self.delay
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// Sleeps for the delay of the next transaction, if any.
+    pub fn wait(&mut self) {
+        let delay = self.next_delay();
+        if !delay.is_zero() {
+            thread::sleep(delay);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::tx_genertor::TransactionGenerator;
+
+    #[test]
+    fn test_failures_slow_the_generation_until_recovery() {
+        let outcomes = Arc::new(RecentOutcomes::new(10));
+        let throttle = AdaptiveThrottle::new(outcomes.clone(), 0.5, 0.2, Duration::from_millis(20));
+        let mut generator = TransactionGenerator::new(Arc::default());
+        generator.backoff = Some(throttle);
+        let mut timed_next = || {
+            let started = Instant::now();
+            generator.next();
+            started.elapsed()
+        };
+
+        (0..10).for_each(|_| outcomes.record(false));
+        assert!(timed_next() < Duration::from_millis(20));
+
+        (0..5).for_each(|_| outcomes.record(true));
+        assert!(timed_next() >= Duration::from_millis(20));
+
+        // Still slowed down between the thresholds.
+        (0..7).for_each(|_| outcomes.record(false));
+        assert_eq!(outcomes.failure_rate(), 0.3);
+        assert!(timed_next() >= Duration::from_millis(20));
+
+        outcomes.record(false);
+        assert!(timed_next() < Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,162 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::{error, warn};
+
+use crate::limits::LimitChecker;
+
+/// Source of the live balance of the paying wallet, e.g. an RPC node.
+pub trait BalanceProvider: Send + Sync {
+    /// Returns the current balance in budget units.
+    fn balance(&self) -> Result<i64, String>;
+}
+
+/// Polls a `BalanceProvider` every `balance_poll_secs` and aborts the run once the balance
+/// falls below `min_wallet_balance`, protecting the reserves kept in the wallet.
+///
+/// The monitor thread only holds a weak reference to the limiter and stops when dropped.
+#[derive(Debug)]
+pub struct BalanceMonitor {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl BalanceMonitor {
+    /// Starts polling `provider` for the run of `limiter`.
+    ///
+    /// # Arguments
+    /// * `limiter` - The limit checker shared with the workers.
+    /// * `provider` - The source of the wallet balance.
+    /// * `floor` - The lowest balance the run may leave in the wallet.
+    /// * `interval` - The time between two polls.
+    pub fn spawn(
+        limiter: &Arc<LimitChecker>,
+        provider: Arc<dyn BalanceProvider>,
+        floor: i64,
+        interval: Duration,
+    ) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let limiter: Weak<LimitChecker> = Arc::downgrade(limiter);
+
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    let Some(limiter) = limiter.upgrade() else {
+                        break;
+                    };
+                    if check_balance(&limiter, provider.as_ref(), floor) {
+                        break;
+                    }
+                    drop(limiter);
+                    thread::sleep(interval);
+                }
+            })
+        };
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    /// Starts polling `provider` every `balance_poll_secs` when `min_wallet_balance` is set.
+    ///
+    /// # Arguments
+    /// * `limiter` - The limit checker shared with the workers.
+    /// * `provider` - The source of the wallet balance.
+    pub fn from_params(
+        limiter: &Arc<LimitChecker>,
+        provider: Arc<dyn BalanceProvider>,
+    ) -> Option<Self> {
+        let params = limiter.params();
+        let interval = Duration::from_secs(params.balance_poll_secs);
+        params
+            .min_wallet_balance
+            .map(|floor| Self::spawn(limiter, provider, floor, interval))
+    }
+}
+
+impl Drop for BalanceMonitor {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Polls the balance once, aborting the run if it is below `floor`.
+/// A failed poll is logged and does not stop the run.
+///
+/// # Arguments
+/// * `limiter` - The limit checker of the run.
+/// * `provider` - The source of the wallet balance.
+/// * `floor` - The lowest balance the run may leave in the wallet.
+///
+/// # Returns
+/// `true` if the run was aborted.
+pub fn check_balance(limiter: &LimitChecker, provider: &dyn BalanceProvider, floor: i64) -> bool {
+    match provider.balance() {
+        Ok(balance) if balance < floor => {
+            error!(
+                "Wallet balance {} fell below the floor of {}. Aborting the run.",
+                balance, floor
+            );
+            limiter.abort();
+            true
+        }
+        Ok(_) => false,
+        Err(err) => {
+            warn!("Failed to poll the wallet balance: {}", err);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicI64;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::runner::{run_transaction_process, unwrap_results};
+    use crate::test_tx::StubTransaction;
+
+    /// Wallet losing 100 on every poll.
+    struct Draining(AtomicI64);
+
+    impl BalanceProvider for Draining {
+        fn balance(&self) -> Result<i64, String> {
+            Ok(self.0.fetch_sub(100, Ordering::SeqCst))
+        }
+    }
+
+    #[test]
+    fn test_run_halts_below_the_balance_floor() {
+        let limiter = Arc::new(LimitChecker::new(&EnvParams {
+            total_amount: 10_000_000,
+            max_transactions: 100_000,
+            max_threads: 1,
+            ..Default::default()
+        }));
+        let provider = Arc::new(Draining(AtomicI64::new(1_000)));
+        let monitor =
+            BalanceMonitor::spawn(&limiter, provider.clone(), 500, Duration::from_millis(5));
+
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator =
+            std::iter::repeat_with(|| StubTransaction::new().with_delay(Duration::from_millis(2)))
+                .take(10_000);
+        run_transaction_process(1, generator, limiter.clone(), &results);
+        drop(monitor);
+
+        assert!(limiter.aborted.load(Ordering::SeqCst));
+        // Polled at 1000, 900, ..., 500 and 400, which breaches the floor.
+        assert_eq!(provider.0.load(Ordering::SeqCst), 300);
+        assert!(unwrap_results(results).len() < 10_000);
+    }
+}
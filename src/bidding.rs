@@ -0,0 +1,236 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::limits::LimitChecker;
+
+/// Snapshot of how far the run has progressed, used to pick a commission.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunProgress {
+    /// Time elapsed since the run started.
+    pub elapsed: Duration,
+    /// Time window the run is expected to complete in.
+    pub window: Duration,
+    /// Budget that is still available.
+    pub remaining_budget: i64,
+    /// Total budget of the run.
+    pub total_budget: i64,
+    /// Internal backpressure in `[0, 1]`, see `LimitChecker::pressure`.
+    pub pressure: f64,
+}
+
+impl RunProgress {
+    /// Returns the urgency of the run in `[0, 1]`: the larger of the elapsed time fraction
+    /// and the spent budget fraction.
+    pub fn urgency(&self) -> f64 {
+        let time = if self.window.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f64() / self.window.as_secs_f64()
+        };
+        let budget = if self.total_budget <= 0 {
+            1.0
+        } else {
+            1.0 - self.remaining_budget as f64 / self.total_budget as f64
+        };
+
+        time.max(budget).clamp(0.0, 1.0)
+    }
+}
+
+/// Strategy deciding the commission of the next transaction based on run progress.
+pub trait BiddingStrategy: Send + Sync {
+    /// Returns the commission to bid, given the base commission, its allowed variation
+    /// and the current run progress.
+    fn bid(&self, commission: i64, commission_change: i64, progress: &RunProgress) -> i64;
+}
+
+/// Bids from `commission - commission_change` up to `commission + commission_change`
+/// proportionally to the urgency of the run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Linear;
+
+impl BiddingStrategy for Linear {
+    fn bid(&self, commission: i64, commission_change: i64, progress: &RunProgress) -> i64 {
+        let span = 2.0 * commission_change as f64 * progress.urgency();
+        commission - commission_change + span.round() as i64
+    }
+}
+
+/// Like `Linear`, but approaches the maximum bid faster (square root of the urgency).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Aggressive;
+
+impl BiddingStrategy for Aggressive {
+    fn bid(&self, commission: i64, commission_change: i64, progress: &RunProgress) -> i64 {
+        let span = 2.0 * commission_change as f64 * progress.urgency().sqrt();
+        commission - commission_change + span.round() as i64
+    }
+}
+
+/// Bids from `commission - commission_change` up to `commission + commission_change`
+/// proportionally to the internal backpressure: the more work is backed up, the higher the bid.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Backpressure;
+
+impl BiddingStrategy for Backpressure {
+    fn bid(&self, commission: i64, commission_change: i64, progress: &RunProgress) -> i64 {
+        let span = 2.0 * commission_change as f64 * progress.pressure.clamp(0.0, 1.0);
+        commission - commission_change + span.round() as i64
+    }
+}
+
+/// Bidding strategy selected through `BIDDING_STRATEGY`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BiddingMode {
+    /// Random commission within `commission ± commission_change`.
+    #[default]
+    None,
+    Linear,
+    Aggressive,
+    Backpressure,
+}
+
+impl BiddingMode {
+    /// Returns the strategy for this mode, if any.
+    pub fn strategy(&self) -> Option<Arc<dyn BiddingStrategy>> {
+        match self {
+            Self::None => None,
+            Self::Linear => Some(Arc::new(Linear)),
+            Self::Aggressive => Some(Arc::new(Aggressive)),
+            Self::Backpressure => Some(Arc::new(Backpressure)),
+        }
+    }
+}
+
+impl FromStr for BiddingMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "linear" => Ok(Self::Linear),
+            "aggressive" => Ok(Self::Aggressive),
+            "backpressure" => Ok(Self::Backpressure),
+            other => Err(format!("unknown bidding strategy: {}", other)),
+        }
+    }
+}
+
+/// Everything the generator needs to bid: the strategy and a view of the run progress.
+#[derive(Clone)]
+pub struct Bidding {
+    pub strategy: Arc<dyn BiddingStrategy>,
+    pub limiter: Arc<LimitChecker>,
+    pub started: Instant,
+    pub window: Duration,
+}
+
+impl Bidding {
+    /// Creates a bidding context whose run starts now.
+    pub fn new(
+        strategy: Arc<dyn BiddingStrategy>,
+        limiter: Arc<LimitChecker>,
+        window: Duration,
+    ) -> Self {
+        Self {
+            strategy,
+            limiter,
+            started: Instant::now(),
+            window,
+        }
+    }
+
+    /// Returns the current progress of the run.
+    pub fn progress(&self) -> RunProgress {
+        let total_budget = self.limiter.total_amount();
+        RunProgress {
+            elapsed: self.started.elapsed(),
+            window: self.window,
+            remaining_budget: total_budget - self.limiter.committed_amount(),
+            total_budget,
+            pressure: self.limiter.pressure(),
+        }
+    }
+
+    /// Returns the commission to bid for the next transaction.
+    pub fn bid(&self) -> i64 {
+        self.strategy.bid(
+            self.limiter.commission(),
+            self.limiter.commission_change(),
+            &self.progress(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    fn at(secs: u64) -> RunProgress {
+        RunProgress {
+            elapsed: Duration::from_secs(secs),
+            window: Duration::from_secs(100),
+            remaining_budget: 1000,
+            total_budget: 1000,
+            pressure: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_bids_escalate_over_fixed_timeline() {
+        let timeline = [0, 25, 50, 75, 100, 150];
+
+        let linear: Vec<_> = timeline
+            .iter()
+            .map(|&t| Linear.bid(100, 10, &at(t)))
+            .collect();
+        assert_eq!(linear, vec![90, 95, 100, 105, 110, 110]);
+
+        let aggressive: Vec<_> = timeline
+            .iter()
+            .map(|&t| Aggressive.bid(100, 10, &at(t)))
+            .collect();
+        assert_eq!(aggressive, vec![90, 100, 104, 107, 110, 110]);
+        assert!(aggressive.iter().zip(&linear).all(|(a, l)| a >= l));
+    }
+
+    #[test]
+    fn test_spent_budget_raises_urgency() {
+        let progress = RunProgress {
+            remaining_budget: 250,
+            ..at(0)
+        };
+        assert_eq!(progress.urgency(), 0.75);
+        assert_eq!(Linear.bid(100, 10, &progress), 105);
+    }
+
+    #[test]
+    fn test_backlog_raises_backpressure_bids() {
+        let params = crate::env_utils::EnvParams {
+            commission: 100,
+            commission_change: 10,
+            max_threads: 4,
+            backpressure_latency_ms: Some(50),
+            ..Default::default()
+        };
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let bidding = Bidding::new(Arc::new(Backpressure), limiter.clone(), Duration::ZERO);
+        assert_eq!(bidding.bid(), 90);
+
+        // Half of the workers are busy.
+        limiter.in_flight.store(2, Ordering::SeqCst);
+        assert_eq!(bidding.bid(), 100);
+
+        // Executions are twice as slow as the latency target.
+        limiter.in_flight.store(0, Ordering::SeqCst);
+        limiter.metrics.record_success(Duration::from_millis(100));
+        assert_eq!(bidding.progress().pressure, 1.0);
+        assert_eq!(bidding.bid(), 110);
+    }
+}
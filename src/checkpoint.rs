@@ -0,0 +1,152 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::env_utils::EnvParams;
+
+/// Progress of a run persisted to `CHECKPOINT_FILE`, which a restarted run resumes from.
+///
+/// The counters include the transactions still executing when it was written: one of them may
+/// have landed before a crash, so resuming counts it rather than risk spending its amount twice.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// Number of admitted transactions.
+    pub transactions_count: usize,
+    /// Amount committed by the admitted transactions.
+    pub committed_amount: i64,
+}
+
+impl Checkpoint {
+    /// Reads the checkpoint at `path`, `None` when there is none yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<Self>, String> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|err| format!("failed to parse {}: {}", path.display(), err))
+    }
+
+    /// Resumes the run from the checkpoint, replacing `initial_count` and `initial_committed`.
+    pub fn apply(&self, params: &mut EnvParams) {
+        params.initial_count = self.transactions_count;
+        params.initial_committed = self.committed_amount;
+    }
+
+    /// Writes the checkpoint to a temporary file renamed over `path`, so a crash leaves
+    /// either the previous checkpoint or this one.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+        let temp = PathBuf::from(temp);
+
+        let json = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        let mut file = fs::File::create(&temp)
+            .map_err(|err| format!("failed to create {}: {}", temp.display(), err))?;
+        file.write_all(json.as_bytes())
+            .and_then(|_| file.sync_all())
+            .map_err(|err| format!("failed to write {}: {}", temp.display(), err))?;
+        fs::rename(&temp, path)
+            .map_err(|err| format!("failed to replace {}: {}", path.display(), err))
+    }
+}
+
+/// The checkpoint file of a `LimitChecker`, rewritten after every committed transaction.
+#[derive(Debug)]
+pub struct CheckpointFile {
+    path: PathBuf,
+    /// Serializes the writes, so a stale checkpoint never replaces a newer one.
+    lock: Mutex<()>,
+}
+
+impl CheckpointFile {
+    /// Creates the checkpoint file at `path`.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Writes the checkpoint returned by `current`, read while holding the lock, with a
+    /// warning when the write fails.
+    pub fn save(&self, current: impl FnOnce() -> Checkpoint) {
+        let _lock = self.lock.lock().unwrap();
+        if let Err(err) = current().save(&self.path) {
+            warn!("Failed to write the checkpoint: {}", err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::limits::{LimitChecker, States};
+    use crate::tx::SomeTransaction;
+
+    #[test]
+    fn test_run_resumes_from_the_checkpoint() {
+        let path = std::env::temp_dir().join(format!("checkpoint-{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(Checkpoint::load(&path), Ok(None));
+        let mut params = EnvParams {
+            price: 100,
+            commission: 50,
+            total_amount: 1_000,
+            max_transactions: 100,
+            failure_rate: Some(0.0),
+            checkpoint_file: Some(path.display().to_string()),
+            ..Default::default()
+        };
+
+        {
+            let params = Arc::new(params.clone());
+            let limiter = LimitChecker::new(&params);
+            for _ in 0..3 {
+                let tx = SomeTransaction::new(&params);
+                assert!(matches!(
+                    limiter.process_transaction(&tx),
+                    Ok(States::Success { .. })
+                ));
+            }
+        }
+        let checkpoint = Checkpoint::load(&path).unwrap().unwrap();
+        assert_eq!(
+            checkpoint,
+            Checkpoint {
+                transactions_count: 3,
+                committed_amount: 450
+            }
+        );
+
+        checkpoint.apply(&mut params);
+        let params = Arc::new(params);
+        let limiter = LimitChecker::new(&params);
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 3);
+        assert_eq!(limiter.committed_amount(), 450);
+        // Only 3 more transactions of 150 fit into the rest of the budget.
+        let states: Vec<_> = (0..4)
+            .map(|_| limiter.process_transaction(&SomeTransaction::new(&params)))
+            .collect();
+        assert!(states[..3]
+            .iter()
+            .all(|state| matches!(state, Ok(States::Success { .. }))));
+        assert_eq!(states[3], Ok(States::Finish));
+        assert_eq!(
+            Checkpoint::load(&path).unwrap().unwrap().committed_amount,
+            900
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::env_utils::EnvParams;
+
+/// Settings loaded from a JSON config file (`CONFIG_PATH`), overriding the environment.
+///
+/// Every field is optional; the numeric ones are signed so that out-of-range values
+/// reach `validate` and are reported against their field instead of failing the parse.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub wallet: Option<String>,
+    pub token: Option<String>,
+    pub total_amount: Option<i64>,
+    pub commission: Option<i64>,
+    pub commission_change: Option<i64>,
+    pub max_transactions: Option<i64>,
+    pub max_threads: Option<i64>,
+    pub price: Option<i64>,
+}
+
+/// A config value outside of its expected range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    /// Name of the offending field.
+    pub field: &'static str,
+    /// What is wrong with its value.
+    pub message: String,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` {}", self.field, self.message)
+    }
+}
+
+/// Errors raised while loading a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The file could not be read.
+    Read(String),
+    /// The file is not valid JSON or does not match the config layout.
+    Parse(String),
+    /// The file parsed, but some values are out of range.
+    Invalid(Vec<FieldError>),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::Read(message) | ConfigError::Parse(message) => f.write_str(message),
+            ConfigError::Invalid(errors) => {
+                f.write_str("invalid config:")?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl FileConfig {
+    /// Reads, parses and validates the config file at `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|err| {
+            ConfigError::Read(format!("failed to read {}: {}", path.display(), err))
+        })?;
+        Self::parse(&json).map_err(|err| match err {
+            ConfigError::Parse(message) => {
+                ConfigError::Parse(format!("failed to parse {}: {}", path.display(), message))
+            }
+            other => other,
+        })
+    }
+
+    /// Parses and validates a config from its JSON text.
+    pub fn parse(json: &str) -> Result<Self, ConfigError> {
+        let config: Self =
+            serde_json::from_str(json).map_err(|err| ConfigError::Parse(err.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Checks the value ranges, reporting every offending field.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        let mut check = |field: &'static str, value: Option<i64>, min: i64| {
+            if let Some(value) = value.filter(|value| *value < min) {
+                let expected = if min == 0 {
+                    "be non-negative".to_string()
+                } else {
+                    format!("be at least {}", min)
+                };
+                errors.push(FieldError {
+                    field,
+                    message: format!("must {}, got {}", expected, value),
+                });
+            }
+        };
+
+        check("total_amount", self.total_amount, 0);
+        check("commission", self.commission, 0);
+        check("commission_change", self.commission_change, 0);
+        check("max_transactions", self.max_transactions, 0);
+        check("max_threads", self.max_threads, 1);
+        check("price", self.price, 0);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    /// Overrides the parameters with the values set in the config.
+    /// Expects a validated config, so the counts are non-negative;
+    /// `max_threads` is capped by the number of CPUs, as when read from the environment.
+    pub fn apply(&self, params: &mut EnvParams) {
+        if let Some(wallet) = &self.wallet {
+            params.wallet = wallet.clone();
+        }
+        if let Some(token) = &self.token {
+            params.token = token.clone();
+        }
+        if let Some(total_amount) = self.total_amount {
+            params.total_amount = total_amount;
+        }
+        if let Some(commission) = self.commission {
+            params.commission = commission;
+        }
+        if let Some(commission_change) = self.commission_change {
+            params.commission_change = commission_change;
+        }
+        if let Some(max_transactions) = self.max_transactions {
+            params.max_transactions = max_transactions as usize;
+        }
+        if let Some(max_threads) = self.max_threads {
+            params.max_threads = std::cmp::min(num_cpus::get(), max_threads as usize);
+        }
+        if let Some(price) = self.price {
+            params.price = price;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negative_total_amount_is_reported_by_field() {
+        let err = FileConfig::parse(r#"{ "total_amount": -5, "max_threads": 2 }"#).unwrap_err();
+
+        assert_eq!(
+            err,
+            ConfigError::Invalid(vec![FieldError {
+                field: "total_amount",
+                message: "must be non-negative, got -5".to_string(),
+            }])
+        );
+        assert_eq!(
+            err.to_string(),
+            "invalid config:\n  `total_amount` must be non-negative, got -5"
+        );
+    }
+
+    #[test]
+    fn test_valid_config_overrides_params() {
+        let config = FileConfig::parse(r#"{ "total_amount": 500, "max_threads": 0 }"#);
+        assert!(
+            matches!(config, Err(ConfigError::Invalid(errors)) if errors[0].field == "max_threads")
+        );
+
+        let config =
+            FileConfig::parse(r#"{ "total_amount": 500, "max_threads": 1, "price": 7 }"#).unwrap();
+        let mut params = EnvParams {
+            total_amount: 100,
+            price: 1,
+            commission: 3,
+            ..Default::default()
+        };
+        config.apply(&mut params);
+        assert_eq!(
+            (
+                params.total_amount,
+                params.max_threads,
+                params.price,
+                params.commission
+            ),
+            (500, 1, 7, 3)
+        );
+    }
+}
@@ -1,30 +1,603 @@
+use clap::{Parser, Subcommand};
 use dotenv::dotenv;
+use rand::Rng;
+use serde::{Serialize, Serializer};
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::bidding::BiddingMode;
+use crate::config::{ConfigError, FieldError, FileConfig};
+use crate::errors::ErrorRule;
+use crate::fees::SharedFeeOracle;
+use crate::output::{LogTarget, OutputFormat, OutputSink, OutputStream};
+use crate::pool::ExecutionBackend;
+use crate::runner::{OnError, ResultSort};
+use crate::signature::SignatureFormat;
+use crate::stats::{Estimate, Rejection};
+use crate::tx::{CommissionDistribution, RngAlgorithm, StandaloneSequence};
+use crate::tx::{CommissionTier, DEFAULT_FAILURE_RATE};
+
+/// Error reading the parameters from the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EnvError {
+    /// A required variable is not set.
+    Missing(String),
+    /// A variable cannot be converted to the expected type.
+    Parse { var: String, expected: &'static str },
+    /// A variable is not a valid value of the expected kind, for `reason`.
+    Invalid {
+        var: String,
+        expected: &'static str,
+        reason: String,
+    },
+    /// `NETWORK` is `mainnet` without `CONFIRM_MAINNET=1`.
+    MainnetNotConfirmed,
+    /// The config file at `CONFIG_PATH` cannot be loaded.
+    Config(ConfigError),
+}
+
+impl fmt::Display for EnvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnvError::Missing(var) => write!(f, "{} not set", var),
+            EnvError::Parse { var, expected } => write!(f, "{} should be {}", var, expected),
+            EnvError::Invalid {
+                var,
+                expected,
+                reason,
+            } => write!(f, "{} should be {}: {}", var, expected, reason),
+            EnvError::MainnetNotConfirmed => {
+                f.write_str("NETWORK is mainnet, set CONFIRM_MAINNET=1 to proceed")
+            }
+            EnvError::Config(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for EnvError {}
+
+impl EnvError {
+    fn parse(var: &str, expected: &'static str) -> Self {
+        EnvError::Parse {
+            var: var.to_string(),
+            expected,
+        }
+    }
+
+    fn invalid(var: &str, expected: &'static str, reason: impl fmt::Display) -> Self {
+        EnvError::Invalid {
+            var: var.to_string(),
+            expected,
+            reason: reason.to_string(),
+        }
+    }
+}
 
 /// Macro to fetch and convert an environment variable to a specified type.
-/// Panics if the variable is not set or cannot be converted to the specified type.
+/// Returns an `EnvError` if the variable is not set or cannot be converted to the specified type.
 macro_rules! get_env {
-    ($var:expr, $typ:ty) => {
-        env::var($var)
-            .unwrap_or_else(|_| panic!("{} not set", $var))
+    ($vars:expr, $var:expr, $typ:ty) => {
+        $vars
+            .var($var)
+            .map_err(|_| EnvError::Missing($var.to_string()))?
             .parse::<$typ>()
-            .unwrap_or_else(|_| panic!("{} should be a {}", $var, stringify!($typ)))
+            .map_err(|_| EnvError::parse($var, concat!("a ", stringify!($typ))))?
+    };
+}
+
+/// Macro to fetch and convert an optional environment variable, falling back to `$default` when unset.
+/// Returns an `EnvError` if the variable is set but cannot be converted to the specified type.
+macro_rules! get_env_or {
+    ($vars:expr, $var:expr, $typ:ty, $default:expr) => {
+        match $vars.var($var) {
+            Ok(value) => value
+                .parse::<$typ>()
+                .map_err(|_| EnvError::parse($var, concat!("a ", stringify!($typ))))?,
+            Err(_) => $default,
+        }
+    };
+}
+
+/// Blockchain network the bot is pointed at.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    /// Production network, spending real funds. Requires `CONFIRM_MAINNET=1`.
+    #[default]
+    Mainnet,
+    Testnet,
+    Devnet,
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet" => Ok(Self::Mainnet),
+            "testnet" => Ok(Self::Testnet),
+            "devnet" => Ok(Self::Devnet),
+            other => Err(format!("unknown network: {}", other)),
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Mainnet => "mainnet",
+            Self::Testnet => "testnet",
+            Self::Devnet => "devnet",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Macro to fetch and convert an optional environment variable into an `Option`.
+/// Returns an `EnvError` if the variable is set but cannot be converted to the specified type.
+macro_rules! get_env_opt {
+    ($vars:expr, $var:expr, $typ:ty) => {
+        match $vars.var($var) {
+            Ok(value) => Some(
+                value
+                    .parse::<$typ>()
+                    .map_err(|_| EnvError::parse($var, concat!("a ", stringify!($typ))))?,
+            ),
+            Err(_) => None,
+        }
+    };
+}
+
+/// Macro to read an optional boolean flag. `1`, `true` and `yes` enable it; unset means disabled.
+/// Returns an `EnvError` if the variable is set to anything else than a recognised flag value.
+macro_rules! get_env_flag {
+    ($vars:expr, $var:expr) => {
+        match $vars.var($var).as_deref() {
+            Ok("1" | "true" | "yes") => true,
+            Ok("0" | "false" | "no" | "") | Err(_) => false,
+            Ok(_) => return Err(EnvError::parse($var, "a flag (1/0)")),
+        }
+    };
+}
+
+/// Macro to fetch an optional comma-separated list, returning an empty `Vec` when unset.
+/// Returns an `EnvError` if any element cannot be converted to the specified type.
+macro_rules! get_env_list {
+    ($vars:expr, $var:expr, $typ:ty) => {
+        $vars
+            .var($var)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| {
+                item.parse::<$typ>().map_err(|_| {
+                    EnvError::parse(
+                        $var,
+                        concat!("a comma-separated list of ", stringify!($typ)),
+                    )
+                })
+            })
+            .collect::<Result<Vec<$typ>, EnvError>>()?
+    };
+}
+
+/// Macro to fetch an optional comma-separated list of `key:value` pairs into a `HashMap`,
+/// returning an empty map when unset.
+/// Returns an `EnvError` if a pair is malformed or a value cannot be converted to the specified type.
+macro_rules! get_env_map {
+    ($vars:expr, $var:expr, $typ:ty) => {
+        get_env_list!($vars, $var, String)
+            .into_iter()
+            .map(|pair| {
+                pair.split_once(':')
+                    .and_then(|(key, value)| {
+                        Some((key.trim().to_string(), value.trim().parse::<$typ>().ok()?))
+                    })
+                    .ok_or_else(|| {
+                        EnvError::parse(
+                            $var,
+                            concat!("a comma-separated list of key:", stringify!($typ), " pairs"),
+                        )
+                    })
+            })
+            .collect::<Result<HashMap<String, $typ>, EnvError>>()?
     };
 }
 
+/// Macro to fetch an amount, accepting `k`/`M`/`G` suffixes and decimal points (see `parse_amount`).
+/// Returns an `EnvError` if the variable is not set and has no `$default`, or is not a valid amount.
+macro_rules! get_env_amount {
+    ($vars:expr, $var:expr, $decimals:expr) => {
+        get_env_amount!(
+            $vars,
+            $var,
+            $decimals,
+            return Err(EnvError::Missing($var.to_string()))
+        )
+    };
+    ($vars:expr, $var:expr, $decimals:expr, $default:expr) => {
+        match $vars.var($var) {
+            Ok(value) => parse_amount(&value, $decimals)
+                .map_err(|err| EnvError::invalid($var, "an amount", err))?,
+            Err(_) => $default,
+        }
+    };
+}
+
+/// Macro to fetch an optional amount into an `Option` (see `get_env_amount`).
+/// Returns an `EnvError` if the variable is set but is not a valid amount.
+macro_rules! get_env_amount_opt {
+    ($vars:expr, $var:expr, $decimals:expr) => {
+        match $vars.var($var) {
+            Ok(value) => Some(
+                parse_amount(&value, $decimals)
+                    .map_err(|err| EnvError::invalid($var, "an amount", err))?,
+            ),
+            Err(_) => None,
+        }
+    };
+}
+
+/// Parses a human-friendly amount such as `1500`, `1.5k`, `10M` or `2G` into base units,
+/// scaling it by `10^decimals`.
+///
+/// The value is parsed exactly, without going through floating point, and must not have
+/// more decimal places than the suffix and `decimals` allow, e.g. `1.5` needs `decimals >= 1`.
+pub fn parse_amount(value: &str, decimals: u32) -> Result<i64, String> {
+    let value = value.trim();
+    let (number, suffix_exponent) = match value.char_indices().last() {
+        Some((index, 'k' | 'K')) => (&value[..index], 3),
+        Some((index, 'M')) => (&value[..index], 6),
+        Some((index, 'G')) => (&value[..index], 9),
+        _ => (value, 0),
+    };
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, number),
+    };
+    let (integer, fraction) = number.split_once('.').unwrap_or((number, ""));
+    let is_digits = |part: &str| part.bytes().all(|byte| byte.is_ascii_digit());
+    if integer.is_empty() || !is_digits(integer) || !is_digits(fraction) {
+        return Err(format!("`{}` is not a number", value));
+    }
+
+    let exponent = suffix_exponent + decimals;
+    let fraction = fraction.trim_end_matches('0');
+    let fraction_exponent = exponent
+        .checked_sub(fraction.len() as u32)
+        .ok_or_else(|| format!("`{}` has too many decimal places", value))?;
+    let overflow = || format!("`{}` is out of range", value);
+    let scale = |digits: &str, exponent: u32| {
+        let digits = if digits.is_empty() {
+            0
+        } else {
+            digits.parse::<i64>().ok()?
+        };
+        digits.checked_mul(10i64.checked_pow(exponent)?)
+    };
+    let amount = scale(integer, exponent)
+        .zip(scale(fraction, fraction_exponent))
+        .and_then(|(integer, fraction)| integer.checked_add(fraction))
+        .ok_or_else(overflow)?;
+
+    Ok(if negative { -amount } else { amount })
+}
+
+/// Placeholder of the secrets in the serialized parameters, e.g. in the run summary.
+pub const REDACTED: &str = "<redacted>";
+
+/// Serializes a secret parameter as `REDACTED`.
+fn redact<S: Serializer>(_: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(REDACTED)
+}
+
+/// Serializes a list of secrets as as many `REDACTED`.
+fn redact_all<S: Serializer>(values: &[String], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(values.iter().map(|_| REDACTED))
+}
+
+/// Parses an inclusive `min:max` range, e.g. `500:2k`, with `parse` parsing each bound.
+pub fn parse_range<T: PartialOrd>(
+    value: &str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<RangeInclusive<T>, String> {
+    let (min, max) = value
+        .split_once(':')
+        .ok_or_else(|| format!("invalid range: {}", value))?;
+    let (min, max) = (parse(min.trim())?, parse(max.trim())?);
+    if min > max {
+        return Err(format!("empty range: {}", value));
+    }
+    Ok(min..=max)
+}
+
+/// Wallet/token pair of a multi-target run (`TARGETS`) with its own budget.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Target {
+    #[serde(serialize_with = "redact")]
+    pub wallet: String,
+    pub token: String,
+    pub total_amount: i64,
+    pub max_transactions: usize,
+}
+
+impl Target {
+    /// Parses `wallet:token:total_amount:max_transactions`, the amount with `decimals`
+    /// decimal places, e.g. `W1:SOL:1.5k:10`.
+    pub fn parse(s: &str, decimals: u32) -> Result<Self, String> {
+        let invalid = || format!("invalid target: {}", s);
+        let [wallet, token, total_amount, max_transactions] = s
+            .split(':')
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| invalid())?;
+        if wallet.is_empty() || token.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            wallet: wallet.to_string(),
+            token: token.to_string(),
+            total_amount: parse_amount(total_amount, decimals)?,
+            max_transactions: max_transactions.parse().map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// Command line of the bot; the parameters override their environment variable.
+///
+/// Only the parameters commonly changed between runs have a flag: the budget and its limits,
+/// the wallet, token and network, the seed, the pacing and the dry run. All the other
+/// parameters are read from their environment variable, or from the `--config` file.
+/// `test-bot --self-test` checks the pipeline on a built-in configuration.
+#[derive(Debug, Clone, Default, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Runs a command instead of the bot.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Check the pipeline on a built-in configuration, printing a pass/fail.
+    #[arg(long)]
+    pub self_test: bool,
+    /// Wallet the transactions are paid from (`WALLET`).
+    #[arg(long)]
+    pub wallet: Option<String>,
+    /// Token bought by the transactions (`TOKEN`).
+    #[arg(long)]
+    pub token: Option<String>,
+    /// Budget of the run, an amount such as `1.5k` (`TOTAL_AMOUNT`).
+    #[arg(long)]
+    pub total_amount: Option<String>,
+    /// Base commission, an amount (`COMMISSION`).
+    #[arg(long)]
+    pub commission: Option<String>,
+    /// Variation of the commission, an amount (`COMMISSION_CHANGE`).
+    #[arg(long)]
+    pub commission_change: Option<String>,
+    /// Price of the token in a transaction, an amount (`PRICE`).
+    #[arg(long)]
+    pub price: Option<String>,
+    /// Maximum number of transactions (`MAX_TRANSACTIONS`).
+    #[arg(long)]
+    pub max_transactions: Option<usize>,
+    /// Maximum number of worker threads (`MAX_THREADS`).
+    #[arg(long)]
+    pub max_threads: Option<usize>,
+    /// Network the transactions are sent to (`NETWORK`).
+    #[arg(long)]
+    pub network: Option<Network>,
+    /// Decimal places of the amounts (`DECIMALS`).
+    #[arg(long)]
+    pub decimals: Option<u32>,
+    /// Seed of the random number generators (`RNG_SEED`).
+    #[arg(long)]
+    pub rng_seed: Option<u64>,
+    /// Maximum number of submissions per second (`MAX_TPS`).
+    #[arg(long)]
+    pub max_tps: Option<f64>,
+    /// Time after which no transaction is admitted any more (`MAX_RUNTIME_SECS`).
+    #[arg(long)]
+    pub max_runtime_secs: Option<f64>,
+    /// Check the limits without executing any transaction (`DRY_RUN`).
+    #[arg(long)]
+    pub dry_run: bool,
+    /// JSON config file, applied over the parameters (`CONFIG_PATH`).
+    #[arg(long = "config")]
+    pub config_path: Option<String>,
+}
+
+/// Commands run instead of the bot.
+#[derive(Debug, Clone, PartialEq, Eq, Subcommand)]
+pub enum Command {
+    /// Compares two run reports.
+    Diff {
+        /// The report of the first run.
+        a: String,
+        /// The report of the second run.
+        b: String,
+    },
+    /// Prints the projected spend of the configuration without executing anything.
+    Estimate,
+}
+
+impl Args {
+    /// Drops the values of `config` that are also given on the command line, which take
+    /// precedence over the file.
+    fn overriding(&self, config: FileConfig) -> FileConfig {
+        FileConfig {
+            wallet: config.wallet.filter(|_| self.wallet.is_none()),
+            token: config.token.filter(|_| self.token.is_none()),
+            total_amount: config.total_amount.filter(|_| self.total_amount.is_none()),
+            commission: config.commission.filter(|_| self.commission.is_none()),
+            commission_change: config
+                .commission_change
+                .filter(|_| self.commission_change.is_none()),
+            max_transactions: config
+                .max_transactions
+                .filter(|_| self.max_transactions.is_none()),
+            max_threads: config.max_threads.filter(|_| self.max_threads.is_none()),
+            price: config.price.filter(|_| self.price.is_none()),
+        }
+    }
+
+    /// Returns the variables set on the command line, in their environment form.
+    fn vars(&self) -> Vars {
+        let mut overrides = HashMap::new();
+        let mut set = |var: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                overrides.insert(var, value);
+            }
+        };
+        set("WALLET", self.wallet.clone());
+        set("TOKEN", self.token.clone());
+        set("TOTAL_AMOUNT", self.total_amount.clone());
+        set("COMMISSION", self.commission.clone());
+        set("COMMISSION_CHANGE", self.commission_change.clone());
+        set("PRICE", self.price.clone());
+        set(
+            "MAX_TRANSACTIONS",
+            self.max_transactions.map(|n| n.to_string()),
+        );
+        set("MAX_THREADS", self.max_threads.map(|n| n.to_string()));
+        set("NETWORK", self.network.map(|network| network.to_string()));
+        set("DECIMALS", self.decimals.map(|n| n.to_string()));
+        set("RNG_SEED", self.rng_seed.map(|seed| seed.to_string()));
+        set("MAX_TPS", self.max_tps.map(|tps| tps.to_string()));
+        set(
+            "MAX_RUNTIME_SECS",
+            self.max_runtime_secs.map(|secs| secs.to_string()),
+        );
+        set("DRY_RUN", self.dry_run.then(|| "1".to_string()));
+        set("CONFIG_PATH", self.config_path.clone());
+        Vars { overrides }
+    }
+}
+
+/// Source of the variables read by the `get_env` macros: the overrides given on the command
+/// line, then the environment.
+#[derive(Debug, Default)]
+struct Vars {
+    overrides: HashMap<&'static str, String>,
+}
+
+impl Vars {
+    fn var(&self, var: &str) -> Result<String, env::VarError> {
+        match self.overrides.get(var) {
+            Some(value) => Ok(value.clone()),
+            None => env::var(var),
+        }
+    }
+}
+
 /// Environmental parameters for configuring the transaction bot.
 ///
 /// # Parameters
 /// * `wallet` - The blockchain wallet address from which transactions will be initiated.
 /// * `token` - The specific token to be purchased in transactions.
-/// * `total_amount` - The target total amount to be spent on token purchases.
-/// * `max_transactions` - The maximum number of transactions to attempt.
+/// * `total_amount` - The target total amount to be spent on token purchases,
+///   drawn from the `min:max` amounts of `TOTAL_AMOUNT_RANGE` when set, to fuzz the limiter.
+/// * `max_transactions` - The maximum number of transactions to attempt,
+///   drawn from the `min:max` of `MAX_TRANSACTIONS_RANGE` when set.
 /// * `commission` - Base commission for transactions, will vary +/- `commission_change`.
 /// * `commission_change` - Allowed variation in commission, to be added or subtracted randomly.
 /// * `max_threads` - The maximum number of concurrent threads for sending transactions.
+/// * `price` - The price of the token in a single transaction (`PRICE`).
+/// * `result_sort` - Order of the displayed results (`RESULT_SORT`: `none`, `amount_asc`, `amount_desc`).
+/// * `network` - The network transactions are sent to (`NETWORK`, defaults to `mainnet`).
+/// * `bidding` - Commission bidding strategy (`BIDDING_STRATEGY`: `none`, `linear`, `aggressive`, `backpressure`).
+/// * `bidding_window_secs` - Time window the bidding strategy escalates over (`BIDDING_WINDOW_SECS`).
+/// * `statsd_addr` - Optional StatsD endpoint (`STATSD_ADDR`, `host:port`) receiving transaction metrics.
+/// * `log_drop_summary` - Log the final counters at `info!` level when the limit checker is dropped (`LOG_DROP_SUMMARY`).
+/// * `deferred_retry_limit` - Number of passes re-attempting failed transactions after the main stream (`DEFERRED_RETRY_LIMIT`).
+/// * `validate_amounts` - Check that each transaction amount equals price + commission before committing (`VALIDATE_AMOUNTS`).
+/// * `dust_threshold` - Transactions with an amount below this value are skipped (`DUST_THRESHOLD`).
+/// * `tx_sla_ms` - Transactions taking longer than this to process are counted as SLA violations (`TX_SLA_MS`).
+/// * `trace_tx` - Emit `trace!` lines for every processing stage of each transaction (`TRACE_TX`).
+/// * `report_path` - File the JSON run summary is written to at the end of the run (`REPORT_PATH`).
+/// * `force_fail_indices` - Indices of generated transactions that always fail (`FORCE_FAIL_INDICES`, comma-separated).
+/// * `window_amount_limit` - Maximum amount committed within any `window_secs` (`WINDOW_AMOUNT_LIMIT`).
+/// * `window_secs` - Length of the rolling spending window in seconds (`WINDOW_SECS`, defaults to 60).
+/// * `result_chunk_size` - Number of results buffered per thread before moving them to the shared list (`RESULT_CHUNK_SIZE`).
+/// * `fee_to_budget_rate` - Converts the token-denominated commission into budget units (`FEE_TO_BUDGET_RATE`, defaults to 1.0).
+/// * `initial_committed` - Amount already committed elsewhere when the run starts (`INITIAL_COMMITTED`).
+/// * `initial_count` - Number of transactions already committed when the run starts (`INITIAL_COUNT`).
+/// * `config_path` - Optional JSON config file overriding the core parameters (`CONFIG_PATH`).
+/// * `log_sample_rate` - Only 1 in N processed transactions logs its detail lines, 0 or 1 logs all (`LOG_SAMPLE_RATE`).
+/// * `output_format` - Format the results are printed in (`OUTPUT_FORMAT`: `text`, `json`, `ndjson`, `csv`).
+/// * `allow_zero_commission` - Accept `commission_change > commission`, clamping the adjusted commission at 0 (`ALLOW_ZERO_COMMISSION`).
+/// * `tokens` - Token campaigns interleaved round-robin by the generator, `token` alone when empty (`TOKENS`).
+/// * `fair_tokens` - Limit each of `tokens` to its share of the concurrent execution slots (`FAIR_TOKENS`).
+/// * `backpressure_latency_ms` - Average execution latency at which the backpressure is full (`BACKPRESSURE_LATENCY_MS`).
+/// * `report_compress` - Gzip the run summary even when `report_path` does not end in `.gz` (`REPORT_COMPRESS`).
+/// * `assert_min_committed_pct` - Post-condition: at least this percentage of `total_amount` is committed (`ASSERT_MIN_COMMITTED_PCT`).
+/// * `assert_max_failure_pct` - Post-condition: less than this percentage of the executions failed (`ASSERT_MAX_FAILURE_PCT`).
+/// * `deterministic` - Process single-threaded and in order regardless of `max_threads`, for reproducible output (`DETERMINISTIC`).
+/// * `token_failure_rates` - Simulated failure probability of each token's executions (`TOKEN_FAILURE_RATES`, e.g. `A:0.1,B:0.3`).
+/// * `histogram_path` - Optional path the full latency histogram is written to as JSON at the end of the run (`HISTOGRAM_PATH`).
+/// * `output_stream` - Where the results are written (`OUTPUT_STREAM`: `stdout`, `stderr` to merge them with the logs, or a file path).
+/// * `log_target` - Stream the logs are written to (`LOG_TARGET`: `stderr`, `stdout`).
+/// * `rng_algorithm` - Random number generator drawing the commissions (`RNG_ALGORITHM`: `std`, `chacha8`).
+/// * `rng_seed` - Seed of the generator's random number generator for reproducible runs, entropy when unset (`RNG_SEED`).
+/// * `max_tokens` - Maximum number of tokens bought over the run, unlimited when unset (`MAX_TOKENS`).
+/// * `max_generation_rate` - Maximum number of transactions generated per second, unthrottled when unset (`MAX_GENERATION_RATE`).
+/// * `otel_endpoint` - OTLP/HTTP endpoint receiving the transaction spans, with the `otel` feature (`OTEL_ENDPOINT`).
+/// * `min_duration_secs` - Minimum duration of the run, idling after an early finish (`MIN_DURATION_SECS`).
+/// * `decimals` - Decimal places of the amounts: `TOTAL_AMOUNT`, `COMMISSION`, `COMMISSION_CHANGE`, `PRICE`,
+///   `DUST_THRESHOLD`, `INITIAL_COMMITTED` and `WINDOW_AMOUNT_LIMIT` accept `k`/`M`/`G` suffixes and
+///   decimal points, scaled to base units by `10^decimals` (`DECIMALS`, defaults to 0).
+/// * `error_categories` - Ordered `category:substring` rules grouping the execution errors in the stats (`ERROR_CATEGORIES`).
+/// * `wallets` - Comma-separated wallets the generated transactions rotate through, `wallet` alone when unset (`WALLETS`).
+/// * `per_wallet_amount_limit` - Maximum amount committed by each wallet, unlimited when unset (`PER_WALLET_AMOUNT_LIMIT`).
+/// * `dedupe_by_token` - Whether only the first success of each token is kept in the results (`DEDUPE_BY_TOKEN`).
+/// * `replay_path` - JSON file of transactions replayed instead of generating new ones (`REPLAY_PATH`).
+/// * `commission_ema_alpha` - Weight in `(0, 1]` of each new commission draw in the exponential moving average of the generated commissions (`COMMISSION_EMA_ALPHA`).
+/// * `pause_every_n` - Number of successes after which the run pauses until resumed (`PAUSE_EVERY_N`).
+/// * `pause_resume_file` - File to touch to resume a pause, Enter on stdin when unset (`PAUSE_RESUME_FILE`).
+/// * `stall_timeout_secs` - Longest time without a successful transaction before the run is reported as stalled (`STALL_TIMEOUT_SECS`).
+/// * `stall_abort` - Whether a stalled run is also finished (`STALL_ABORT`).
+/// * `output_sinks` - Destinations all the results are written to, replacing `output_format` and `output_stream` when set (`OUTPUT_SINKS`: comma-separated `format:stream`, e.g. `text:stdout,csv:results.csv`).
+/// * `commission_tiers` - Base commission per price tier, replacing `commission` from the lowest threshold up (`COMMISSION_TIERS`: comma-separated `price_threshold:commission` amounts).
+/// * `min_wallet_balance` - Live wallet balance below which the run is aborted (`MIN_WALLET_BALANCE`),
+///   rejected by `validate` until the binary has a `BalanceProvider` to poll.
+/// * `balance_poll_secs` - Interval between two polls of the wallet balance (`BALANCE_POLL_SECS`, defaults to 5).
+/// * `signature_format` - Canonical encoding the signatures are normalized to (`SIGNATURE_FORMAT`: `raw`, `base58`, `hex`, `base64`).
+/// * `fee_oracle` - Time-varying base commission replacing `commission` and the `commission_tiers` (`FEE_ORACLE`: `sine:amplitude:period_secs` around `commission`, or `csv:path` of `seconds,fee` lines).
+/// * `max_execute_calls` - Hard bound on the number of `execute()` calls, retries included, whatever the other limits (`MAX_EXECUTE_CALLS`).
+/// * `on_error` - Whether a transaction that fails to be processed stops the run or is skipped (`ON_ERROR`: `stop`, `continue`).
+/// * `commission_failure_scale` - Commission over which the default failure probability falls e-fold, making the failure model commission-aware (`COMMISSION_FAILURE_SCALE`).
+/// * `target_success_rate` - Success rate whose break-even commission is logged at start (`TARGET_SUCCESS_RATE`).
+/// * `memo_template` - Memo attached to every transaction, with `{id}` and `{token}` replaced by those of the transaction (`MEMO_TEMPLATE`).
+/// * `backoff_failure_rate` - Recent failure rate from which the generation slows down (`BACKOFF_FAILURE_RATE`).
+/// * `backoff_recovery_rate` - Recent failure rate from which it speeds back up (`BACKOFF_RECOVERY_RATE`, defaults to half the `backoff_failure_rate`).
+/// * `backoff_window` - Number of recent executions the failure rate is computed over (`BACKOFF_WINDOW`, defaults to 20).
+/// * `backoff_delay_ms` - Delay added to every generated transaction while slowed down (`BACKOFF_DELAY_MS`, defaults to 100).
+/// * `db_path` - SQLite database every execution attempt is recorded to, with the `sqlite` feature (`DB_PATH`).
+/// * `flush_batch_size` - Number of records buffered before a sink writes them (`FLUSH_BATCH_SIZE`, defaults to 100).
+/// * `flush_interval_ms` - Longest time a buffered record waits to be written (`FLUSH_INTERVAL_MS`, defaults to 1000).
+/// * `min_successes` - Fewest successful transactions for the run to exit with success (`MIN_SUCCESSES`).
+/// * `retry_attempts` - Number of times a failed execution is retried before the transaction is rolled back (`RETRY_ATTEMPTS`).
+/// * `retry_backoff_ms` - Delay before the first retry, doubled on every further one (`RETRY_BACKOFF_MS`, defaults to 100).
+/// * `failure_rate` - Simulated failure probability of the executions, from 0 to 1 (`FAILURE_RATE`, defaults to `DEFAULT_FAILURE_RATE`).
+/// * `max_tps` - Maximum number of submissions per second across all threads, retries included, unpaced when unset (`MAX_TPS`).
+/// * `max_runtime_secs` - Time after which no transaction is admitted any more, counted from the creation of the limiter (`MAX_RUNTIME_SECS`).
+/// * `dry_run` - Run the limit checks and counters without executing any transaction; each admitted one succeeds with a `dry-run-<id>` signature (`DRY_RUN`).
+/// * `commission_distribution` - Distribution of the commissions, `commission_change` being the half-width of the uniform one and the standard deviation of the normal one (`COMMISSION_DISTRIBUTION`: `uniform`, `normal`).
+/// * `checkpoint_file` - File the count and amount of the run are saved to after every committed transaction, and resumed from at startup (`CHECKPOINT_FILE`).
+/// * `tx_timeout_ms` - Time after which an execution still running is failed with `timeout` (`TX_TIMEOUT_MS`).
+/// * `metrics_addr` - Optional address serving the live counters on `/metrics` in the Prometheus text format (`METRICS_ADDR`, `host:port`).
+/// * `backend` - Path the transactions are executed through (`BACKEND`: `sim`, `pool` for a `ConnectionPool` created once for the run).
+/// * `standalone_sequence` - Number of transactions created from the parameters outside of a generator, seeding their draws under `rng_seed` (not read from the environment).
+/// * `targets` - Wallet/token pairs run at once, each with its own budget, in place of `wallet` and `token`; the options of a single stream, such as `REPLAY_PATH` or `CHECKPOINT_FILE`, are rejected with them (`TARGETS`: comma-separated `wallet:token:total_amount:max_transactions`).
 ///
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct EnvParams {
+    #[serde(serialize_with = "redact")]
     pub wallet: String,
     pub token: String,
     pub total_amount: i64,
@@ -33,28 +606,564 @@ pub struct EnvParams {
     pub max_transactions: usize,
     pub max_threads: usize,
     pub price: i64,
+    pub result_sort: ResultSort,
+    pub network: Network,
+    pub bidding: BiddingMode,
+    pub bidding_window_secs: u64,
+    pub statsd_addr: Option<String>,
+    pub log_drop_summary: bool,
+    pub deferred_retry_limit: usize,
+    pub validate_amounts: bool,
+    pub dust_threshold: i64,
+    pub tx_sla_ms: Option<u64>,
+    pub trace_tx: bool,
+    pub report_path: Option<String>,
+    pub force_fail_indices: Arc<HashSet<u64>>,
+    pub window_amount_limit: Option<i64>,
+    pub window_secs: u64,
+    pub result_chunk_size: usize,
+    pub fee_to_budget_rate: Option<f64>,
+    pub initial_committed: i64,
+    pub initial_count: usize,
+    pub config_path: Option<String>,
+    pub log_sample_rate: usize,
+    pub output_format: OutputFormat,
+    pub allow_zero_commission: bool,
+    pub tokens: Vec<String>,
+    pub fair_tokens: bool,
+    pub backpressure_latency_ms: Option<u64>,
+    pub report_compress: bool,
+    pub assert_min_committed_pct: Option<f64>,
+    pub assert_max_failure_pct: Option<f64>,
+    pub deterministic: bool,
+    pub token_failure_rates: Arc<HashMap<String, f64>>,
+    pub histogram_path: Option<String>,
+    pub output_stream: OutputStream,
+    pub log_target: LogTarget,
+    pub rng_algorithm: RngAlgorithm,
+    pub rng_seed: Option<u64>,
+    pub max_tokens: Option<i64>,
+    pub max_generation_rate: Option<f64>,
+    pub otel_endpoint: Option<String>,
+    pub min_duration_secs: Option<u64>,
+    pub decimals: u32,
+    pub error_categories: Vec<ErrorRule>,
+    #[serde(serialize_with = "redact_all")]
+    pub wallets: Vec<String>,
+    pub per_wallet_amount_limit: Option<i64>,
+    pub dedupe_by_token: bool,
+    pub replay_path: Option<String>,
+    pub commission_ema_alpha: Option<f64>,
+    pub pause_every_n: Option<usize>,
+    pub pause_resume_file: Option<String>,
+    pub stall_timeout_secs: Option<u64>,
+    pub stall_abort: bool,
+    pub output_sinks: Vec<OutputSink>,
+    pub commission_tiers: Vec<CommissionTier>,
+    pub min_wallet_balance: Option<i64>,
+    pub balance_poll_secs: u64,
+    pub signature_format: SignatureFormat,
+    #[serde(skip)]
+    pub fee_oracle: Option<SharedFeeOracle>,
+    pub max_execute_calls: Option<usize>,
+    pub on_error: OnError,
+    pub commission_failure_scale: Option<i64>,
+    pub target_success_rate: Option<f64>,
+    pub memo_template: Option<Arc<str>>,
+    pub backoff_failure_rate: Option<f64>,
+    pub backoff_recovery_rate: Option<f64>,
+    pub backoff_window: usize,
+    pub backoff_delay_ms: u64,
+    pub db_path: Option<String>,
+    pub flush_batch_size: usize,
+    pub flush_interval_ms: u64,
+    pub min_successes: Option<u64>,
+    pub retry_attempts: usize,
+    pub retry_backoff_ms: u64,
+    pub failure_rate: Option<f64>,
+    pub max_tps: Option<f64>,
+    pub max_runtime_secs: Option<f64>,
+    pub dry_run: bool,
+    pub commission_distribution: CommissionDistribution,
+    pub checkpoint_file: Option<String>,
+    pub tx_timeout_ms: Option<u64>,
+    pub metrics_addr: Option<String>,
+    pub backend: ExecutionBackend,
+    #[serde(skip)]
+    pub standalone_sequence: StandaloneSequence,
+    pub targets: Vec<Target>,
 }
 
 impl EnvParams {
     /// Reads and parses environment variables, creating a new instance of `EnvParams`.
     ///
-    /// # Panics
-    /// Panics if any environment variable is not set or cannot be parsed into the expected type.
-    pub fn read_env() -> Self {
+    /// # Errors
+    /// Returns an `EnvError` if any environment variable is not set or cannot be parsed into the
+    /// expected type, or if `NETWORK` is `mainnet` and `CONFIRM_MAINNET=1` is not set.
+    pub fn read_env() -> Result<Self, EnvError> {
+        Self::read_vars(&Vars::default())
+    }
+
+    /// Reads the parameters like `read_env`, then applies the config file at `config_path`
+    /// when set; the values given on the command line take precedence over both, and the
+    /// file over the environment.
+    ///
+    /// # Errors
+    /// Returns an `EnvError` if a parameter is set nowhere, is set but invalid, or if the
+    /// config file cannot be loaded.
+    pub fn read_with_args(args: &Args) -> Result<Self, EnvError> {
+        let mut params = Self::read_vars(&args.vars())?;
+        if let Some(path) = params.config_path.clone() {
+            let config = FileConfig::load(&path).map_err(EnvError::Config)?;
+            args.overriding(config).apply(&mut params);
+        }
+        Ok(params)
+    }
+
+    fn read_vars(vars: &Vars) -> Result<Self, EnvError> {
         dotenv().ok();
 
-        Self {
-            wallet: get_env!("WALLET", String),
-            token: get_env!("TOKEN", String),
-            total_amount: get_env!("TOTAL_AMOUNT", i64),
-            commission: get_env!("COMMISSION", i64),
-            commission_change: get_env!("COMMISSION_CHANGE", i64),
-            max_transactions: get_env!("MAX_TRANSACTIONS", usize),
-            price: get_env!("PRICE", i64),
+        let network = get_env_or!(vars, "NETWORK", Network, Network::Mainnet);
+        if network == Network::Mainnet
+            && get_env_or!(vars, "CONFIRM_MAINNET", String, String::new()) != "1"
+        {
+            return Err(EnvError::MainnetNotConfirmed);
+        }
+        let decimals = get_env_or!(vars, "DECIMALS", u32, 0);
+        let rng_algorithm = get_env_or!(vars, "RNG_ALGORITHM", RngAlgorithm, RngAlgorithm::Std);
+        let rng_seed = get_env_opt!(vars, "RNG_SEED", u64);
+
+        // Randomized budgets are drawn with their own generator, reproducible under `RNG_SEED`.
+        let mut range_rng = rng_algorithm.build(rng_seed);
+        let commission = get_env_amount!(vars, "COMMISSION", decimals);
+        let total_amount = match vars.var("TOTAL_AMOUNT_RANGE") {
+            Ok(range) => range_rng.gen_range(
+                parse_range(&range, |bound| parse_amount(bound, decimals)).map_err(|err| {
+                    EnvError::invalid("TOTAL_AMOUNT_RANGE", "a min:max range of amounts", err)
+                })?,
+            ),
+            Err(_) => get_env_amount!(vars, "TOTAL_AMOUNT", decimals),
+        };
+        let max_transactions = match vars.var("MAX_TRANSACTIONS_RANGE") {
+            Ok(range) => range_rng.gen_range(
+                parse_range(&range, |bound| {
+                    bound.parse::<usize>().map_err(|err| err.to_string())
+                })
+                .map_err(|err| {
+                    EnvError::invalid("MAX_TRANSACTIONS_RANGE", "a min:max range of counts", err)
+                })?,
+            ),
+            Err(_) => get_env!(vars, "MAX_TRANSACTIONS", usize),
+        };
+
+        Ok(Self {
+            wallet: get_env!(vars, "WALLET", String),
+            token: get_env!(vars, "TOKEN", String),
+            total_amount,
+            commission,
+            commission_change: get_env_amount!(vars, "COMMISSION_CHANGE", decimals),
+            max_transactions,
+            price: get_env_amount!(vars, "PRICE", decimals),
+            result_sort: get_env_or!(vars, "RESULT_SORT", ResultSort, ResultSort::None),
+            network,
+            bidding: get_env_or!(vars, "BIDDING_STRATEGY", BiddingMode, BiddingMode::None),
+            bidding_window_secs: get_env_or!(vars, "BIDDING_WINDOW_SECS", u64, 60),
+            statsd_addr: get_env_opt!(vars, "STATSD_ADDR", String),
+            log_drop_summary: get_env_flag!(vars, "LOG_DROP_SUMMARY"),
+            deferred_retry_limit: get_env_or!(vars, "DEFERRED_RETRY_LIMIT", usize, 0),
+            validate_amounts: get_env_flag!(vars, "VALIDATE_AMOUNTS"),
+            dust_threshold: get_env_amount!(vars, "DUST_THRESHOLD", decimals, 0),
+            tx_sla_ms: get_env_opt!(vars, "TX_SLA_MS", u64),
+            trace_tx: get_env_flag!(vars, "TRACE_TX"),
+            report_path: get_env_opt!(vars, "REPORT_PATH", String),
+            result_chunk_size: get_env_or!(vars, "RESULT_CHUNK_SIZE", usize, 64),
+            fee_to_budget_rate: get_env_opt!(vars, "FEE_TO_BUDGET_RATE", f64),
+            initial_committed: get_env_amount!(vars, "INITIAL_COMMITTED", decimals, 0),
+            initial_count: get_env_or!(vars, "INITIAL_COUNT", usize, 0),
+            config_path: get_env_opt!(vars, "CONFIG_PATH", String),
+            log_sample_rate: get_env_or!(vars, "LOG_SAMPLE_RATE", usize, 1),
+            output_format: get_env_or!(vars, "OUTPUT_FORMAT", OutputFormat, OutputFormat::Text),
+            allow_zero_commission: get_env_flag!(vars, "ALLOW_ZERO_COMMISSION"),
+            tokens: get_env_list!(vars, "TOKENS", String),
+            fair_tokens: get_env_flag!(vars, "FAIR_TOKENS"),
+            backpressure_latency_ms: get_env_opt!(vars, "BACKPRESSURE_LATENCY_MS", u64),
+            report_compress: get_env_flag!(vars, "REPORT_COMPRESS"),
+            assert_min_committed_pct: get_env_opt!(vars, "ASSERT_MIN_COMMITTED_PCT", f64),
+            assert_max_failure_pct: get_env_opt!(vars, "ASSERT_MAX_FAILURE_PCT", f64),
+            deterministic: get_env_flag!(vars, "DETERMINISTIC"),
+            token_failure_rates: Arc::new(get_env_map!(vars, "TOKEN_FAILURE_RATES", f64)),
+            histogram_path: get_env_opt!(vars, "HISTOGRAM_PATH", String),
+            output_stream: get_env_or!(vars, "OUTPUT_STREAM", OutputStream, OutputStream::Stdout),
+            log_target: get_env_or!(vars, "LOG_TARGET", LogTarget, LogTarget::Stderr),
+            rng_algorithm,
+            rng_seed,
+            max_tokens: get_env_opt!(vars, "MAX_TOKENS", i64),
+            max_generation_rate: get_env_opt!(vars, "MAX_GENERATION_RATE", f64),
+            otel_endpoint: get_env_opt!(vars, "OTEL_ENDPOINT", String),
+            min_duration_secs: get_env_opt!(vars, "MIN_DURATION_SECS", u64),
+            decimals,
+            error_categories: get_env_list!(vars, "ERROR_CATEGORIES", ErrorRule),
+            wallets: get_env_list!(vars, "WALLETS", String),
+            per_wallet_amount_limit: get_env_amount_opt!(vars, "PER_WALLET_AMOUNT_LIMIT", decimals),
+            dedupe_by_token: get_env_flag!(vars, "DEDUPE_BY_TOKEN"),
+            replay_path: get_env_opt!(vars, "REPLAY_PATH", String),
+            commission_ema_alpha: get_env_opt!(vars, "COMMISSION_EMA_ALPHA", f64),
+            pause_every_n: get_env_opt!(vars, "PAUSE_EVERY_N", usize),
+            pause_resume_file: get_env_opt!(vars, "PAUSE_RESUME_FILE", String),
+            stall_timeout_secs: get_env_opt!(vars, "STALL_TIMEOUT_SECS", u64),
+            stall_abort: get_env_flag!(vars, "STALL_ABORT"),
+            output_sinks: get_env_list!(vars, "OUTPUT_SINKS", OutputSink),
+            commission_tiers: get_env_list!(vars, "COMMISSION_TIERS", String)
+                .iter()
+                .map(|tier| {
+                    CommissionTier::parse(tier, decimals).map_err(|err| {
+                        EnvError::invalid(
+                            "COMMISSION_TIERS",
+                            "price_threshold:commission pairs",
+                            err,
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            min_wallet_balance: get_env_amount_opt!(vars, "MIN_WALLET_BALANCE", decimals),
+            balance_poll_secs: get_env_or!(vars, "BALANCE_POLL_SECS", u64, 5),
+            signature_format: get_env_or!(
+                vars,
+                "SIGNATURE_FORMAT",
+                SignatureFormat,
+                SignatureFormat::Raw
+            ),
+            fee_oracle: get_env_opt!(vars, "FEE_ORACLE", String)
+                .map(|spec| {
+                    SharedFeeOracle::parse(&spec, commission, decimals)
+                        .map_err(|err| EnvError::invalid("FEE_ORACLE", "a fee oracle", err))
+                })
+                .transpose()?,
+            max_execute_calls: get_env_opt!(vars, "MAX_EXECUTE_CALLS", usize),
+            on_error: get_env_or!(vars, "ON_ERROR", OnError, OnError::Stop),
+            commission_failure_scale: get_env_amount_opt!(
+                vars,
+                "COMMISSION_FAILURE_SCALE",
+                decimals
+            ),
+            target_success_rate: get_env_opt!(vars, "TARGET_SUCCESS_RATE", f64),
+            memo_template: get_env_opt!(vars, "MEMO_TEMPLATE", String).map(Arc::from),
+            backoff_failure_rate: get_env_opt!(vars, "BACKOFF_FAILURE_RATE", f64),
+            backoff_recovery_rate: get_env_opt!(vars, "BACKOFF_RECOVERY_RATE", f64),
+            backoff_window: get_env_or!(vars, "BACKOFF_WINDOW", usize, 20),
+            backoff_delay_ms: get_env_or!(vars, "BACKOFF_DELAY_MS", u64, 100),
+            db_path: get_env_opt!(vars, "DB_PATH", String),
+            flush_batch_size: get_env_or!(vars, "FLUSH_BATCH_SIZE", usize, 100),
+            flush_interval_ms: get_env_or!(vars, "FLUSH_INTERVAL_MS", u64, 1000),
+            min_successes: get_env_opt!(vars, "MIN_SUCCESSES", u64),
+            retry_attempts: get_env_or!(vars, "RETRY_ATTEMPTS", usize, 0),
+            retry_backoff_ms: get_env_or!(vars, "RETRY_BACKOFF_MS", u64, 100),
+            failure_rate: get_env_opt!(vars, "FAILURE_RATE", f64),
+            max_tps: get_env_opt!(vars, "MAX_TPS", f64),
+            max_runtime_secs: get_env_opt!(vars, "MAX_RUNTIME_SECS", f64),
+            dry_run: get_env_flag!(vars, "DRY_RUN"),
+            commission_distribution: get_env_or!(
+                vars,
+                "COMMISSION_DISTRIBUTION",
+                CommissionDistribution,
+                CommissionDistribution::Uniform
+            ),
+            checkpoint_file: get_env_opt!(vars, "CHECKPOINT_FILE", String),
+            tx_timeout_ms: get_env_opt!(vars, "TX_TIMEOUT_MS", u64),
+            metrics_addr: get_env_opt!(vars, "METRICS_ADDR", String),
+            backend: get_env_or!(vars, "BACKEND", ExecutionBackend, ExecutionBackend::Sim),
+            standalone_sequence: StandaloneSequence::default(),
+            targets: get_env_list!(vars, "TARGETS", String)
+                .iter()
+                .map(|target| {
+                    Target::parse(target, decimals).map_err(|err| {
+                        EnvError::invalid(
+                            "TARGETS",
+                            "wallet:token:total_amount:max_transactions entries",
+                            err,
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            window_amount_limit: get_env_amount_opt!(vars, "WINDOW_AMOUNT_LIMIT", decimals),
+            window_secs: get_env_or!(vars, "WINDOW_SECS", u64, 60),
+            force_fail_indices: Arc::new(
+                get_env_list!(vars, "FORCE_FAIL_INDICES", u64)
+                    .into_iter()
+                    .collect(),
+            ),
             max_threads: {
-                let max_threads_env: usize = get_env!("MAX_THREADS", usize);
+                let max_threads_env: usize = get_env!(vars, "MAX_THREADS", usize);
                 std::cmp::min(num_cpus::get(), max_threads_env)
             },
+        })
+    }
+
+    /// Returns the parameters of the run of `target` within a multi-target run: its wallet,
+    /// token and budget in place of the ones of the run.
+    pub fn for_target(&self, target: &Target) -> Self {
+        Self {
+            wallet: target.wallet.clone(),
+            token: target.token.clone(),
+            total_amount: target.total_amount,
+            max_transactions: target.max_transactions,
+            targets: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the average amount of a transaction: the price plus the mean commission.
+    pub fn average_amount(&self) -> f64 {
+        (self.price + self.base_commission(self.price)) as f64
+    }
+
+    /// Returns the base commission of a transaction priced `price`: the current fee of the
+    /// `fee_oracle` if set, otherwise the commission of the highest `commission_tiers`
+    /// threshold it reaches, `commission` below the lowest one or without tiers.
+    pub fn base_commission(&self, price: i64) -> i64 {
+        if let Some(oracle) = &self.fee_oracle {
+            return oracle.current();
+        }
+        self.commission_tiers
+            .iter()
+            .filter(|tier| tier.price_threshold <= price)
+            .max_by_key(|tier| tier.price_threshold)
+            .map_or(self.commission, |tier| tier.commission)
+    }
+
+    /// Returns the failure probability of the executions before any commission discount:
+    /// `failure_rate`, or `DEFAULT_FAILURE_RATE` when unset.
+    pub fn base_failure_rate(&self) -> f64 {
+        self.failure_rate
+            .unwrap_or(DEFAULT_FAILURE_RATE)
+            .clamp(0.0, 1.0)
+    }
+
+    /// Returns the simulated failure probability of a transaction paying `commission`:
+    /// the `base_failure_rate`, falling e-fold every `commission_failure_scale` when set.
+    pub fn failure_probability(&self, commission: i64) -> f64 {
+        let base = self.base_failure_rate();
+        match self.commission_failure_scale {
+            Some(scale) if scale > 0 => base * (-(commission.max(0) as f64) / scale as f64).exp(),
+            _ => base,
+        }
+    }
+
+    /// Returns the lowest commission whose success probability, per `failure_probability`,
+    /// reaches `target_success_rate`, or `i64::MAX` when no commission does, e.g. without
+    /// `commission_failure_scale`.
+    pub fn break_even_commission(&self, target_success_rate: f64) -> i64 {
+        let base = self.base_failure_rate();
+        let target_failure = 1.0 - target_success_rate;
+        if target_failure >= base {
+            return 0;
+        }
+        match self.commission_failure_scale {
+            Some(scale) if scale > 0 && target_failure > 0.0 => {
+                let commission = scale as f64 * (base / target_failure).ln();
+                commission.ceil() as i64
+            }
+            _ => i64::MAX,
+        }
+    }
+
+    /// Checks the relations between parameters that would distort the budget math.
+    ///
+    /// A `commission_change` larger than `commission` lets the adjusted commission go negative;
+    /// it is only accepted with `allow_zero_commission`, the commission being clamped at 0.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut errors = Vec::new();
+        if self.commission_change > self.commission && !self.allow_zero_commission {
+            errors.push(FieldError {
+                field: "commission_change",
+                message: format!(
+                    "must not exceed commission ({}), got {}; set ALLOW_ZERO_COMMISSION=1 to clamp the commission at 0",
+                    self.commission, self.commission_change
+                ),
+            });
+        }
+        let rates = [
+            ("failure_rate", self.failure_rate),
+            ("target_success_rate", self.target_success_rate),
+            ("backoff_failure_rate", self.backoff_failure_rate),
+            ("backoff_recovery_rate", self.backoff_recovery_rate),
+        ];
+        for (field, rate) in rates {
+            if let Some(rate) = rate.filter(|rate| !(0.0..=1.0).contains(rate)) {
+                errors.push(FieldError {
+                    field,
+                    message: format!("must be between 0 and 1, got {}", rate),
+                });
+            }
+        }
+        let mut token_failure_rates: Vec<_> = self
+            .token_failure_rates
+            .iter()
+            .filter(|(_, rate)| !(0.0..=1.0).contains(*rate))
+            .collect();
+        token_failure_rates.sort_by(|a, b| a.0.cmp(b.0));
+        for (token, rate) in token_failure_rates {
+            errors.push(FieldError {
+                field: "token_failure_rates",
+                message: format!("must be between 0 and 1, got {} for {}", rate, token),
+            });
+        }
+        let percentages = [
+            ("assert_min_committed_pct", self.assert_min_committed_pct),
+            ("assert_max_failure_pct", self.assert_max_failure_pct),
+        ];
+        for (field, pct) in percentages {
+            if let Some(pct) = pct.filter(|pct| !(0.0..=100.0).contains(pct)) {
+                errors.push(FieldError {
+                    field,
+                    message: format!("must be between 0 and 100, got {}", pct),
+                });
+            }
+        }
+        if let Some(alpha) = self
+            .commission_ema_alpha
+            .filter(|alpha| !(*alpha > 0.0 && *alpha <= 1.0))
+        {
+            errors.push(FieldError {
+                field: "commission_ema_alpha",
+                message: format!("must be above 0 and at most 1, got {}", alpha),
+            });
+        }
+        if let Some(rate) = self
+            .fee_to_budget_rate
+            .filter(|rate| !(rate.is_finite() && *rate > 0.0))
+        {
+            errors.push(FieldError {
+                field: "fee_to_budget_rate",
+                message: format!("must be a finite number above 0, got {}", rate),
+            });
+        }
+        if !self.targets.is_empty() {
+            // A multi-target run has no single stream, budget or report these would apply to.
+            let unsupported = [
+                ("wallets", !self.wallets.is_empty()),
+                ("tokens", !self.tokens.is_empty()),
+                ("replay_path", self.replay_path.is_some()),
+                ("checkpoint_file", self.checkpoint_file.is_some()),
+                ("initial_committed", self.initial_committed != 0),
+                ("initial_count", self.initial_count != 0),
+                ("report_path", self.report_path.is_some()),
+                ("histogram_path", self.histogram_path.is_some()),
+                ("metrics_addr", self.metrics_addr.is_some()),
+                ("bidding", self.bidding.strategy().is_some()),
+                ("backoff_failure_rate", self.backoff_failure_rate.is_some()),
+                ("db_path", self.db_path.is_some()),
+                ("otel_endpoint", self.otel_endpoint.is_some()),
+                ("backend", self.backend != ExecutionBackend::Sim),
+            ];
+            errors.extend(
+                unsupported
+                    .into_iter()
+                    .filter(|(_, set)| *set)
+                    .map(|(field, _)| FieldError {
+                        field,
+                        message: "is not supported together with TARGETS".to_string(),
+                    }),
+            );
+        }
+        // The `BalanceMonitor` has no provider to poll yet, the floor would silently not apply.
+        if self.min_wallet_balance.is_some() {
+            errors.push(FieldError {
+                field: "min_wallet_balance",
+                message: "is not supported: no wallet balance provider is available".to_string(),
+            });
+        }
+        if let Some(tps) = self
+            .max_tps
+            .filter(|tps| !tps.is_finite() || Duration::try_from_secs_f64(1.0 / tps).is_err())
+        {
+            errors.push(FieldError {
+                field: "max_tps",
+                message: format!("must be a finite number above 0, got {}", tps),
+            });
+        }
+        if let Some(rate) = self
+            .max_generation_rate
+            .filter(|rate| !rate.is_finite() || Duration::try_from_secs_f64(1.0 / rate).is_err())
+        {
+            errors.push(FieldError {
+                field: "max_generation_rate",
+                message: format!("must be a finite number above 0, got {}", rate),
+            });
+        }
+        if let Some(secs) = self
+            .max_runtime_secs
+            .filter(|secs| *secs <= 0.0 || Duration::try_from_secs_f64(*secs).is_err())
+        {
+            errors.push(FieldError {
+                field: "max_runtime_secs",
+                message: format!("must be a finite number of seconds above 0, got {}", secs),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(errors))
+        }
+    }
+
+    /// Returns the destinations of the results: the `output_sinks`, or the single
+    /// `output_format` and `output_stream` pair when none is configured.
+    pub fn output_sinks(&self) -> Vec<OutputSink> {
+        if self.output_sinks.is_empty() {
+            vec![OutputSink {
+                format: self.output_format,
+                stream: self.output_stream.clone(),
+            }]
+        } else {
+            self.output_sinks.clone()
+        }
+    }
+
+    /// Returns the transactions-per-second rate needed to spend `target_amount` within `window_secs`,
+    /// given the average transaction amount.
+    ///
+    /// Returns `f64::INFINITY` when no rate can achieve the target: the window is empty, the
+    /// target exceeds `total_amount`, or it needs more than `max_transactions` transactions.
+    pub fn recommended_rate(&self, target_amount: i64, window_secs: u64) -> f64 {
+        let average = self.average_amount();
+        if window_secs == 0 || average <= 0.0 || target_amount > self.total_amount {
+            return f64::INFINITY;
+        }
+
+        let transactions = (target_amount as f64 / average).ceil();
+        if transactions > self.max_transactions as f64 {
+            return f64::INFINITY;
+        }
+
+        transactions / window_secs as f64
+    }
+
+    /// Projects the transactions and spend of a run until a cap binds, assuming every
+    /// transaction costs the average amount and succeeds. Purely analytical, nothing is executed.
+    /// The budget and count already used (`initial_committed`, `initial_count`) are deducted.
+    pub fn estimate(&self) -> Estimate {
+        let average = self.average_amount();
+        let remaining_count = self.max_transactions.saturating_sub(self.initial_count);
+        let remaining_amount = (self.total_amount - self.initial_committed).max(0) as f64;
+
+        let by_amount = if average > 0.0 {
+            (remaining_amount / average).floor() as usize
+        } else {
+            usize::MAX
+        };
+
+        let (transactions, binding) = if remaining_count <= by_amount {
+            (remaining_count, Rejection::CountCap)
+        } else {
+            (by_amount, Rejection::AmountCap)
+        };
+
+        Estimate {
+            transactions,
+            spend: transactions as f64 * average,
+            binding,
         }
     }
 }
@@ -75,6 +1184,8 @@ mod tests {
         env::set_var("COMMISSION_CHANGE", "10");
         env::set_var("MAX_TRANSACTIONS", "50");
         env::set_var("MAX_THREADS", "4");
+        env::set_var("PRICE", "150");
+        env::set_var("NETWORK", "devnet");
     }
 
     fn cleanup_env() {
@@ -85,6 +1196,15 @@ mod tests {
         env::remove_var("COMMISSION_CHANGE");
         env::remove_var("MAX_TRANSACTIONS");
         env::remove_var("MAX_THREADS");
+        env::remove_var("PRICE");
+        env::remove_var("NETWORK");
+        env::remove_var("CONFIRM_MAINNET");
+        env::remove_var("ALLOW_ZERO_COMMISSION");
+        env::remove_var("DECIMALS");
+        env::remove_var("TOTAL_AMOUNT_RANGE");
+        env::remove_var("MAX_TRANSACTIONS_RANGE");
+        env::remove_var("RNG_SEED");
+        env::remove_var("PER_WALLET_AMOUNT_LIMIT");
     }
 
     // Ensures cleanup after test completion (in case of panic)
@@ -101,33 +1221,414 @@ mod tests {
     fn test_read_env_correctly() {
         let _env_guard = EnvironmentGuard;
         setup_env();
-        let params = EnvParams::read_env();
+        let params = EnvParams::read_env().unwrap();
         assert_eq!(params.wallet, "TestWallet");
         assert_eq!(params.token, "TestToken");
         assert_eq!(params.total_amount, 1000);
         assert_eq!(params.commission, 100);
         assert_eq!(params.commission_change, 10);
         assert_eq!(params.max_transactions, 50);
-        assert_eq!(params.max_threads, 4);
+        assert_eq!(params.max_threads, std::cmp::min(num_cpus::get(), 4));
+        assert_eq!(params.price, 150);
+        assert_eq!(params.network, Network::Devnet);
+    }
+
+    #[test]
+    fn test_parse_human_friendly_amounts() {
+        assert_eq!(parse_amount("1.5k", 0), Ok(1_500));
+        assert_eq!(parse_amount("2M", 0), Ok(2_000_000));
+        assert_eq!(parse_amount("3G", 0), Ok(3_000_000_000));
+        assert_eq!(parse_amount("1500", 0), Ok(1_500));
+        assert_eq!(parse_amount("-5", 0), Ok(-5));
+        assert_eq!(parse_amount("1.5", 2), Ok(150));
+        assert_eq!(parse_amount("0.25k", 2), Ok(25_000));
+        assert!(parse_amount("1.5", 0).is_err());
+        assert!(parse_amount("1.2345k", 0).is_err());
+        assert!(parse_amount("12x", 0).is_err());
+        assert!(parse_amount("k", 0).is_err());
+        assert!(parse_amount("10G", 10).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_env_scales_amounts() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
+        env::set_var("TOTAL_AMOUNT", "1.5k");
+        env::set_var("COMMISSION", "2.5");
+        env::set_var("DECIMALS", "1");
+        env::set_var("PER_WALLET_AMOUNT_LIMIT", "1.5M");
+        let params = EnvParams::read_env().unwrap();
+        assert_eq!(params.total_amount, 15_000);
+        assert_eq!(params.commission, 25);
+        assert_eq!(params.per_wallet_amount_limit, Some(15_000_000));
+        assert_eq!(params.commission_change, 100);
+        assert_eq!(params.price, 1_500);
+    }
+
+    #[test]
+    #[serial]
+    fn test_read_env_draws_budget_from_ranges() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
+        env::set_var("TOTAL_AMOUNT_RANGE", "500:2k");
+        env::set_var("MAX_TRANSACTIONS_RANGE", "10:20");
+        for seed in 0..20 {
+            env::set_var("RNG_SEED", seed.to_string());
+            let params = EnvParams::read_env().unwrap();
+            assert!((500..=2_000).contains(&params.total_amount));
+            assert!((10..=20).contains(&params.max_transactions));
+            // The same seed draws the same budget.
+            assert_eq!(
+                EnvParams::read_env().unwrap().total_amount,
+                params.total_amount
+            );
+        }
+
+        assert_eq!(
+            parse_range("7:7", |v| v.parse::<i64>().map_err(|e| e.to_string())),
+            Ok(7..=7)
+        );
+        assert!(parse_range("9:3", |v| v.parse::<i64>().map_err(|e| e.to_string())).is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_mainnet_requires_confirmation() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
+        env::set_var("NETWORK", "mainnet");
+        env::set_var("CONFIRM_MAINNET", "0");
+        let err = EnvParams::read_env().unwrap_err();
+        assert_eq!(err, EnvError::MainnetNotConfirmed);
+        assert!(err.to_string().contains("set CONFIRM_MAINNET=1"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_mainnet_with_confirmation() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
+        env::set_var("NETWORK", "mainnet");
+        env::set_var("CONFIRM_MAINNET", "1");
+        let params = EnvParams::read_env().unwrap();
+        assert_eq!(params.network, Network::Mainnet);
+    }
+
+    #[test]
+    fn test_recommended_rate() {
+        let params = EnvParams {
+            total_amount: 10_000,
+            max_transactions: 100,
+            commission: 20,
+            price: 80,
+            ..Default::default()
+        };
+
+        // 5_000 / 100 = 50 transactions in 10 seconds.
+        assert_eq!(params.recommended_rate(5_000, 10), 5.0);
+        // 150 needs 2 transactions.
+        assert_eq!(params.recommended_rate(150, 4), 0.5);
+    }
+
+    #[test]
+    #[serial]
+    fn test_commission_change_exceeding_commission() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
+        env::set_var("COMMISSION", "5");
+        env::set_var("COMMISSION_CHANGE", "50");
+
+        let err = EnvParams::read_env().unwrap().validate().unwrap_err();
+        assert!(
+            matches!(&err, ConfigError::Invalid(errors) if errors[0].field == "commission_change")
+        );
+
+        env::set_var("ALLOW_ZERO_COMMISSION", "1");
+        let params = Arc::new(EnvParams::read_env().unwrap());
+        assert_eq!(params.validate(), Ok(()));
+        assert!((0..200).all(|_| crate::tx::SomeTransaction::new(&params).adjusted_commission >= 0));
+    }
+
+    #[test]
+    fn test_pacing_and_runtime_limits_must_be_positive() {
+        for (max_tps, max_runtime_secs) in [(0.0, 1.0), (1e-300, 1.0), (f64::NAN, 1.0)]
+            .into_iter()
+            .chain([(1.0, -5.0), (1.0, 0.0), (1.0, f64::INFINITY)])
+        {
+            let params = EnvParams {
+                max_tps: Some(max_tps),
+                max_runtime_secs: Some(max_runtime_secs),
+                ..Default::default()
+            };
+            let Err(ConfigError::Invalid(errors)) = params.validate() else {
+                panic!("{} / {} passed the validation", max_tps, max_runtime_secs);
+            };
+            assert_eq!(errors.len(), 1);
+        }
+
+        let params = EnvParams {
+            max_tps: Some(0.5),
+            max_runtime_secs: Some(0.5),
+            ..Default::default()
+        };
+        assert_eq!(params.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_rates_and_percentages_must_be_in_range() {
+        let params = EnvParams {
+            commission_ema_alpha: Some(0.0),
+            target_success_rate: Some(1.5),
+            backoff_failure_rate: Some(-0.1),
+            backoff_recovery_rate: Some(f64::NAN),
+            token_failure_rates: Arc::new(HashMap::from([
+                ("A".to_string(), 0.5),
+                ("B".to_string(), 2.0),
+            ])),
+            assert_min_committed_pct: Some(150.0),
+            assert_max_failure_pct: Some(50.0),
+            fee_to_budget_rate: Some(0.0),
+            ..Default::default()
+        };
+        let Err(ConfigError::Invalid(errors)) = params.validate() else {
+            panic!("out of range values passed the validation");
+        };
+        let fields: Vec<_> = errors.iter().map(|error| error.field).collect();
+        assert_eq!(
+            fields,
+            [
+                "target_success_rate",
+                "backoff_failure_rate",
+                "backoff_recovery_rate",
+                "token_failure_rates",
+                "assert_min_committed_pct",
+                "commission_ema_alpha",
+                "fee_to_budget_rate",
+            ]
+        );
+        assert_eq!(errors[3].message, "must be between 0 and 1, got 2 for B");
+
+        let params = EnvParams {
+            commission_ema_alpha: Some(1.0),
+            target_success_rate: Some(0.9),
+            backoff_failure_rate: Some(0.3),
+            backoff_recovery_rate: Some(0.1),
+            token_failure_rates: Arc::new(HashMap::from([("A".to_string(), 0.5)])),
+            assert_min_committed_pct: Some(100.0),
+            fee_to_budget_rate: Some(0.5),
+            ..params
+        };
+        assert_eq!(params.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_generation_rate_must_be_positive() {
+        for rate in [0.0, -1.0, 1e-300, f64::NAN, f64::INFINITY] {
+            let params = EnvParams {
+                max_generation_rate: Some(rate),
+                ..Default::default()
+            };
+            assert!(
+                matches!(params.validate(), Err(ConfigError::Invalid(errors)) if errors[0].field == "max_generation_rate"),
+                "{} passed the validation",
+                rate
+            );
+        }
+        let params = EnvParams {
+            max_generation_rate: Some(0.5),
+            ..Default::default()
+        };
+        assert_eq!(params.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_balance_floor_is_rejected_without_a_provider() {
+        let params = EnvParams {
+            min_wallet_balance: Some(500),
+            ..Default::default()
+        };
+        assert!(
+            matches!(params.validate(), Err(ConfigError::Invalid(errors)) if errors[0].field == "min_wallet_balance")
+        );
+    }
+
+    #[test]
+    fn test_estimate_reports_binding_cap() {
+        let params = EnvParams {
+            total_amount: 1_000,
+            max_transactions: 100,
+            commission: 20,
+            price: 80,
+            ..Default::default()
+        };
+        let estimate = params.estimate();
+        assert_eq!(estimate.transactions, 10);
+        assert_eq!(estimate.spend, 1_000.0);
+        assert_eq!(estimate.binding, Rejection::AmountCap);
+
+        let params = EnvParams {
+            max_transactions: 4,
+            initial_count: 1,
+            ..params
+        };
+        let estimate = params.estimate();
+        assert_eq!(estimate.transactions, 3);
+        assert_eq!(estimate.binding, Rejection::CountCap);
+    }
+
+    #[test]
+    fn test_recommended_rate_unachievable() {
+        let params = EnvParams {
+            total_amount: 10_000,
+            max_transactions: 10,
+            commission: 20,
+            price: 80,
+            ..Default::default()
+        };
+
+        // Needs 50 transactions while only 10 are allowed.
+        assert_eq!(params.recommended_rate(5_000, 10), f64::INFINITY);
+        // More than the whole budget.
+        assert_eq!(params.recommended_rate(20_000, 10), f64::INFINITY);
+        assert_eq!(params.recommended_rate(500, 0), f64::INFINITY);
     }
 
     #[test]
     #[serial]
-    #[should_panic(expected = "WALLET not set")]
     fn test_missing_wallet() {
         let _env_guard = EnvironmentGuard;
         cleanup_env();
         env::remove_var("WALLET");
-        let _ = get_env!("WALLET", String);
+        // Read with the macro alone, `read_env` would pick the wallet up from `.env`.
+        let read_wallet =
+            || -> Result<String, EnvError> { Ok(get_env!(Vars::default(), "WALLET", String)) };
+        let err = read_wallet().unwrap_err();
+        assert_eq!(err, EnvError::Missing("WALLET".to_string()));
+        assert_eq!(err.to_string(), "WALLET not set");
     }
 
     #[test]
     #[serial]
-    #[should_panic(expected = "TOTAL_AMOUNT should be a i64")]
-    fn test_invalid_total_amount() {
+    fn test_cli_args_take_precedence_over_the_environment() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
+        let args = Args::try_parse_from([
+            "test-bot",
+            "--max-transactions",
+            "7",
+            "--total-amount",
+            "2k",
+            "--rng-seed",
+            "5",
+            "estimate",
+        ])
+        .unwrap();
+        assert_eq!(args.command, Some(Command::Estimate));
+        assert!(Args::try_parse_from(["test-bot", "estimat"]).is_err());
+        assert_eq!(
+            Args::try_parse_from(["test-bot", "diff", "a.json", "b.json"])
+                .unwrap()
+                .command,
+            Some(Command::Diff {
+                a: "a.json".to_string(),
+                b: "b.json".to_string()
+            })
+        );
+
+        let params = EnvParams::read_with_args(&args).unwrap();
+        // Set in both, the command line wins.
+        assert_eq!(params.max_transactions, 7);
+        assert_eq!(params.total_amount, 2_000);
+        // Only in the environment.
+        assert_eq!(params.commission, 100);
+        // Only on the command line.
+        assert_eq!(params.rng_seed, Some(5));
+        assert_eq!(EnvParams::read_env().unwrap().max_transactions, 50);
+    }
+
+    #[test]
+    #[serial]
+    fn test_config_file_sits_between_the_environment_and_the_cli() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
+        let path = env::temp_dir().join(format!("precedence-{}.json", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"{ "total_amount": 3000, "max_transactions": 9, "commission": 70 }"#,
+        )
+        .unwrap();
+        let args = Args::try_parse_from([
+            "test-bot",
+            "--total-amount",
+            "2k",
+            "--config",
+            path.to_str().unwrap(),
+        ])
+        .unwrap();
+
+        let params = EnvParams::read_with_args(&args).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        // Set on the command line, in the file and in the environment.
+        assert_eq!(params.total_amount, 2_000);
+        // Set in the file and in the environment.
+        assert_eq!(params.max_transactions, 9);
+        assert_eq!(params.commission, 70);
+        // Only in the environment.
+        assert_eq!(params.price, 150);
+
+        let args = Args {
+            config_path: Some("missing.json".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            EnvParams::read_with_args(&args),
+            Err(EnvError::Config(ConfigError::Read(_)))
+        ));
+    }
+
+    #[test]
+    #[serial]
+    fn test_variable_missing_from_args_and_environment() {
         let _env_guard = EnvironmentGuard;
         cleanup_env();
+        env::remove_var("WALLET");
+        // Read with the macro alone, `read_with_args` would pick the wallet up from `.env`.
+        let read_wallet = |args: Args| -> Result<String, EnvError> {
+            Ok(get_env!(args.vars(), "WALLET", String))
+        };
+        assert_eq!(
+            read_wallet(Args::default()),
+            Err(EnvError::Missing("WALLET".to_string()))
+        );
+        let args = Args {
+            wallet: Some("CliWallet".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(read_wallet(args), Ok("CliWallet".to_string()));
+    }
+
+    #[test]
+    #[serial]
+    fn test_invalid_total_amount() {
+        let _env_guard = EnvironmentGuard;
+        setup_env();
         env::set_var("TOTAL_AMOUNT", "not_a_number");
-        let _ = get_env!("TOTAL_AMOUNT", i64);
+        assert!(matches!(
+            EnvParams::read_env(),
+            Err(EnvError::Invalid { var, expected: "an amount", .. }) if var == "TOTAL_AMOUNT"
+        ));
+
+        env::set_var("TOTAL_AMOUNT", "1000");
+        env::set_var("MAX_TRANSACTIONS", "many");
+        let err = EnvParams::read_env().unwrap_err();
+        assert_eq!(
+            err,
+            EnvError::Parse {
+                var: "MAX_TRANSACTIONS".to_string(),
+                expected: "a usize"
+            }
+        );
+        assert_eq!(err.to_string(), "MAX_TRANSACTIONS should be a usize");
     }
 }
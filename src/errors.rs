@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+/// Category of the execution errors matching no rule.
+pub const OTHER_CATEGORY: &str = "other";
+
+/// Rule assigning the execution errors containing `pattern` (case-insensitively) to `category`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ErrorRule {
+    pub category: String,
+    pub pattern: String,
+}
+
+impl FromStr for ErrorRule {
+    type Err = String;
+
+    /// Parses a `category:pattern` rule, e.g. `nonce:nonce too low`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            Some((category, pattern)) if !category.trim().is_empty() && !pattern.is_empty() => {
+                Ok(Self {
+                    category: category.trim().to_string(),
+                    pattern: pattern.trim().to_lowercase(),
+                })
+            }
+            _ => Err(format!("invalid error rule: {}", s)),
+        }
+    }
+}
+
+/// Groups the error messages returned by `execute()` into categories (`ERROR_CATEGORIES`)
+/// and counts the failures of each one.
+#[derive(Debug, Default)]
+pub struct ErrorClassifier {
+    rules: Vec<ErrorRule>,
+    counts: Mutex<BTreeMap<String, usize>>,
+}
+
+impl ErrorClassifier {
+    /// Creates a classifier trying `rules` in order.
+    pub fn new(rules: Vec<ErrorRule>) -> Self {
+        Self {
+            rules,
+            counts: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Returns the category of the first rule matching `message`, `other` when none does.
+    pub fn classify(&self, message: &str) -> &str {
+        let message = message.to_lowercase();
+        self.rules
+            .iter()
+            .find(|rule| message.contains(&rule.pattern))
+            .map_or(OTHER_CATEGORY, |rule| &rule.category)
+    }
+
+    /// Counts a failure with `message` in its category.
+    pub fn record(&self, message: &str) {
+        let category = self.classify(message).to_string();
+        *self.counts.lock().unwrap().entry(category).or_insert(0) += 1;
+    }
+
+    /// Returns the number of failures per category.
+    pub fn counts(&self) -> BTreeMap<String, usize> {
+        self.counts.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,210 @@
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::tx::Transaction;
+
+/// Backend submitting the transactions admitted by the `LimitChecker`, e.g. an RPC client.
+pub trait Executor: Send + Sync {
+    /// Submits `tx`. Returns its signature as `Ok(String)` if successful, or an error message
+    /// as `Err(String)` if the submission fails.
+    fn submit(&self, tx: &dyn Transaction) -> Result<String, String>;
+}
+
+/// Default executor, running the simulated `execute()` of the transaction itself.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimExecutor;
+
+impl Executor for SimExecutor {
+    fn submit(&self, tx: &dyn Transaction) -> Result<String, String> {
+        tx.execute()
+    }
+}
+
+/// Executor of the dry runs (`DRY_RUN`): accepts every transaction without executing it,
+/// with the signature `dry-run-<id>`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DryRunExecutor;
+
+impl Executor for DryRunExecutor {
+    fn submit(&self, tx: &dyn Transaction) -> Result<String, String> {
+        Ok(format!("dry-run-{}", tx.id()))
+    }
+}
+
+/// Message of a detached submission to the thread waiting for it.
+#[derive(Debug)]
+enum Detached {
+    /// The executor runs the simulated `execute()` of the transaction.
+    Execute,
+    /// The submission returned.
+    Submitted(Result<String, String>),
+}
+
+/// Owned copy of a transaction that cannot `detach` itself, submitted on a thread of the
+/// `SubmitPool` by `submit_detached`.
+///
+/// The simulated `execute()` of the original transaction is run by the waiting thread,
+/// since the original one is borrowed and cannot leave it.
+#[derive(Debug)]
+struct DetachedTransaction {
+    id: u64,
+    token: String,
+    wallet: String,
+    amount: i64,
+    price: i64,
+    commission: i64,
+    tokens: i64,
+    depends_on: Option<u64>,
+    memo: Option<String>,
+    info: String,
+    requests: Sender<Detached>,
+    outcome: Receiver<Result<String, String>>,
+}
+
+impl Transaction for DetachedTransaction {
+    fn amount(&self) -> i64 {
+        self.amount
+    }
+
+    fn price(&self) -> i64 {
+        self.price
+    }
+
+    fn commission(&self) -> i64 {
+        self.commission
+    }
+
+    fn execute(&self) -> Result<String, String> {
+        // Both ends are dropped once the waiting thread gave up on the submission.
+        let abandoned = || "timeout".to_string();
+        self.requests
+            .send(Detached::Execute)
+            .map_err(|_| abandoned())?;
+        self.outcome.recv().map_err(|_| abandoned())?
+    }
+
+    fn info(&self) -> String {
+        self.info.clone()
+    }
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn token(&self) -> &str {
+        &self.token
+    }
+
+    fn depends_on(&self) -> Option<u64> {
+        self.depends_on
+    }
+
+    fn tokens(&self) -> i64 {
+        self.tokens
+    }
+
+    fn wallet(&self) -> &str {
+        &self.wallet
+    }
+
+    fn memo(&self) -> Option<String> {
+        self.memo.clone()
+    }
+}
+
+/// Submission run by a thread of the `SubmitPool`.
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Fixed set of threads running the submissions of `submit_detached`. Hung submissions hold
+/// at most `size` threads, the later ones wait for a free thread or time out, instead of
+/// leaving one more thread behind each.
+#[derive(Debug)]
+pub(crate) struct SubmitPool {
+    jobs: Sender<Job>,
+}
+
+impl SubmitPool {
+    /// Starts `size` threads (at least one), exiting once the pool is dropped and their
+    /// current submission returned.
+    pub(crate) fn new(size: usize) -> Self {
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let queue = Arc::new(Mutex::new(queue));
+        for _ in 0..size.max(1) {
+            let queue = queue.clone();
+            thread::spawn(move || loop {
+                let job = queue.lock().unwrap().recv();
+                match job {
+                    Ok(job) => job(),
+                    Err(_) => break,
+                }
+            });
+        }
+        Self { jobs }
+    }
+
+    /// Queues `job` for the next free thread.
+    fn run(&self, job: Job) {
+        let _ = self.jobs.send(job);
+    }
+}
+
+/// Submits `tx` to `executor` on a thread of `pool` and waits up to `timeout` for the result,
+/// so a hung submission is left behind instead of blocking the caller.
+///
+/// A transaction returning its owned copy from `detach` executes on the pool thread as well.
+/// Otherwise the copy submitted is a `DetachedTransaction`, whose `execute()` runs on the
+/// calling thread: it cannot be interrupted, and its time is not charged to `timeout`.
+///
+/// # Returns
+///
+/// The result of the submission, `None` when it did not return within `timeout`.
+pub(crate) fn submit_detached(
+    pool: &SubmitPool,
+    executor: Arc<dyn Executor>,
+    tx: &dyn Transaction,
+    timeout: Duration,
+) -> Option<Result<String, String>> {
+    if let Some(owned) = tx.detach() {
+        let (outcomes, outcome) = mpsc::channel();
+        pool.run(Box::new(move || {
+            let _ = outcomes.send(executor.submit(&*owned));
+        }));
+        return outcome.recv_timeout(timeout).ok();
+    }
+
+    let (requests, received) = mpsc::channel();
+    let (outcomes, outcome) = mpsc::channel();
+    let detached = DetachedTransaction {
+        id: tx.id(),
+        token: tx.token().to_string(),
+        wallet: tx.wallet().to_string(),
+        amount: tx.amount(),
+        price: tx.price(),
+        commission: tx.commission(),
+        tokens: tx.tokens(),
+        depends_on: tx.depends_on(),
+        memo: tx.memo(),
+        info: tx.info(),
+        requests: requests.clone(),
+        outcome,
+    };
+    pool.run(Box::new(move || {
+        let result = executor.submit(&detached);
+        let _ = requests.send(Detached::Submitted(result));
+    }));
+
+    let mut waited = Duration::ZERO;
+    loop {
+        let started = Instant::now();
+        match received.recv_timeout(timeout.saturating_sub(waited)) {
+            Ok(Detached::Execute) => {
+                waited += started.elapsed();
+                let _ = outcomes.send(tx.execute());
+            }
+            Ok(Detached::Submitted(result)) => return Some(result),
+            Err(_) => return None,
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::warn;
+
+use crate::limits::LimitChecker;
+
+/// Interval the server checks for new connections and for its shutdown at.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Serves the live counters of a run as a Prometheus text exposition on `/metrics`
+/// (`METRICS_ADDR`).
+///
+/// The counters are read from the atomics of the limiter, so scraping takes no lock on the
+/// hot path. Like the `Watchdog`, the server thread only holds a weak reference to the
+/// limiter and stops when dropped.
+#[derive(Debug)]
+pub struct MetricsServer {
+    addr: SocketAddr,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MetricsServer {
+    /// Starts serving the counters of `limiter` on `addr`.
+    ///
+    /// # Arguments
+    /// * `limiter` - The limit checker shared with the workers.
+    /// * `addr` - The address to listen on, e.g. `0.0.0.0:9100`.
+    pub fn spawn(limiter: &Arc<LimitChecker>, addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let limiter: Weak<LimitChecker> = Arc::downgrade(limiter);
+
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            let Some(limiter) = limiter.upgrade() else {
+                                break;
+                            };
+                            if let Err(err) = respond(stream, &limiter) {
+                                warn!("Failed to serve the metrics: {}", err);
+                            }
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(POLL_INTERVAL)
+                        }
+                        Err(err) => warn!("Failed to accept a metrics connection: {}", err),
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Returns the address the server listens on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MetricsServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Answers a single request: the exposition for `GET /metrics`, 404 otherwise.
+fn respond(stream: TcpStream, limiter: &LimitChecker) -> io::Result<()> {
+    stream.set_nonblocking(false)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Skip the headers up to the blank line ending them.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+
+    let mut stream = reader.into_inner();
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let (status, body) = if path == "/metrics" {
+        ("200 OK", render(limiter))
+    } else {
+        ("404 Not Found", "not found\n".to_string())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+/// Returns the counters of `limiter` in the Prometheus text exposition format.
+pub fn render(limiter: &LimitChecker) -> String {
+    let metrics = [
+        (
+            "tx_bot_transactions",
+            "gauge",
+            "Number of admitted transactions.",
+            limiter.transactions_count.load(Ordering::SeqCst) as i64,
+        ),
+        (
+            "tx_bot_committed_amount",
+            "gauge",
+            "Amount committed by the admitted transactions.",
+            limiter.committed_amount(),
+        ),
+        (
+            "tx_bot_commission_paid",
+            "gauge",
+            "Commission of the successfully executed transactions.",
+            limiter.paid_commission.load(Ordering::SeqCst),
+        ),
+        (
+            "tx_bot_successes_total",
+            "counter",
+            "Number of successful executions.",
+            limiter.metrics.success.load(Ordering::Relaxed) as i64,
+        ),
+        (
+            "tx_bot_failures_total",
+            "counter",
+            "Number of failed executions.",
+            limiter.metrics.fail.load(Ordering::Relaxed) as i64,
+        ),
+    ];
+
+    let mut out = String::new();
+    for (name, kind, help, value) in metrics {
+        let _ = write!(
+            out,
+            "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::io::Read;
+
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::tx::SomeTransaction;
+
+    fn scrape(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_metrics_endpoint_reports_the_live_counters() {
+        let params = Arc::new(EnvParams {
+            price: 100,
+            commission: 50,
+            total_amount: 10_000,
+            max_transactions: 100,
+            failure_rate: Some(0.0),
+            force_fail_indices: Arc::new([2].into()),
+            ..Default::default()
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let server = MetricsServer::spawn(&limiter, "127.0.0.1:0").unwrap();
+        for id in 0..4 {
+            let mut tx = SomeTransaction::new(&params);
+            tx.id = id;
+            limiter.process_transaction(&tx).unwrap();
+        }
+
+        let response = scrape(server.local_addr(), "/metrics");
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("HTTP/1.1 200 OK"));
+        let gauges: HashMap<_, _> = body
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (name, value) = line.split_once(' ').unwrap();
+                (name, value.parse::<i64>().unwrap())
+            })
+            .collect();
+        assert_eq!(gauges["tx_bot_transactions"], 3);
+        assert_eq!(gauges["tx_bot_committed_amount"], 450);
+        assert_eq!(gauges["tx_bot_commission_paid"], 150);
+        assert_eq!(gauges["tx_bot_successes_total"], 3);
+        assert_eq!(gauges["tx_bot_failures_total"], 1);
+
+        assert!(scrape(server.local_addr(), "/").starts_with("HTTP/1.1 404"));
+        let addr = server.local_addr();
+        drop(server);
+        assert!(TcpStream::connect(addr).is_err());
+    }
+}
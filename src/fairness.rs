@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::{Condvar, Mutex};
+
+/// Limits the number of concurrently executing transactions per token,
+/// so that one token campaign cannot occupy every thread while another starves.
+#[derive(Debug)]
+pub struct TokenSlots {
+    share: usize,
+    in_flight: Mutex<HashMap<String, usize>>,
+    freed: Condvar,
+}
+
+impl TokenSlots {
+    /// Splits `max_threads` evenly between `tokens`, every token getting at least one slot.
+    pub fn new(max_threads: usize, tokens: usize) -> Self {
+        Self {
+            share: max_threads.div_ceil(tokens.max(1)).max(1),
+            in_flight: Mutex::new(HashMap::new()),
+            freed: Condvar::new(),
+        }
+    }
+
+    /// Returns the number of concurrent slots of each token.
+    pub fn share(&self) -> usize {
+        self.share
+    }
+
+    /// Blocks until `token` has a free slot and occupies it until the guard is dropped.
+    pub fn acquire(&self, token: &str) -> SlotGuard<'_> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        while in_flight.get(token).copied().unwrap_or(0) >= self.share {
+            in_flight = self.freed.wait(in_flight).unwrap();
+        }
+        *in_flight.entry(token.to_string()).or_insert(0) += 1;
+
+        SlotGuard {
+            slots: self,
+            token: token.to_string(),
+        }
+    }
+
+    fn release(&self, token: &str) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(token) {
+            *count -= 1;
+        }
+        self.freed.notify_all();
+    }
+}
+
+/// A slot occupied by a transaction of `token`, released on drop.
+pub struct SlotGuard<'a> {
+    slots: &'a TokenSlots,
+    token: String,
+}
+
+impl Drop for SlotGuard<'_> {
+    fn drop(&mut self) {
+        self.slots.release(&self.token);
+    }
+}
@@ -0,0 +1,188 @@
+use std::f64::consts::TAU;
+use std::fmt;
+use std::fs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::env_utils::parse_amount;
+
+/// Source of a time-varying base fee, simulating the volatility of a fee market.
+pub trait FeeOracle: Send + Sync {
+    /// Returns the base fee `elapsed` after the start of the run.
+    fn base_fee(&self, elapsed: Duration) -> i64;
+}
+
+/// Base fee oscillating around `mean` by `amplitude` over `period`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SineFee {
+    pub mean: i64,
+    pub amplitude: i64,
+    pub period: Duration,
+}
+
+impl FeeOracle for SineFee {
+    fn base_fee(&self, elapsed: Duration) -> i64 {
+        if self.period.is_zero() {
+            return self.mean;
+        }
+        let phase = elapsed.as_secs_f64() / self.period.as_secs_f64() * TAU;
+        (self.mean as f64 + self.amplitude as f64 * phase.sin())
+            .round()
+            .max(0.0) as i64
+    }
+}
+
+/// Base fee scripted as steps: each fee applies from its time until the next one,
+/// the first one also before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeTimeline {
+    steps: Vec<(Duration, i64)>,
+}
+
+impl FeeTimeline {
+    /// Creates a timeline from `(time, fee)` steps, in any order.
+    pub fn new(mut steps: Vec<(Duration, i64)>) -> Self {
+        steps.sort_by_key(|(at, _)| *at);
+        Self { steps }
+    }
+
+    /// Parses `seconds,fee` lines, skipping blank lines and a header line; the fees are amounts
+    /// with `decimals` decimal places.
+    pub fn parse(csv: &str, decimals: u32) -> Result<Self, String> {
+        let mut steps = Vec::new();
+        for (index, line) in csv.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || index == 0 && line.starts_with(|c: char| c.is_alphabetic()) {
+                continue;
+            }
+            let invalid = || format!("invalid fee timeline line {}: {}", index + 1, line);
+            let (at, fee) = line.split_once(',').ok_or_else(invalid)?;
+            let at = at.trim().parse::<f64>().map_err(|_| invalid())?;
+            let at = Duration::try_from_secs_f64(at).map_err(|_| invalid())?;
+            let fee =
+                parse_amount(fee, decimals).map_err(|err| format!("{}: {}", invalid(), err))?;
+            steps.push((at, fee));
+        }
+        if steps.is_empty() {
+            return Err("empty fee timeline".to_string());
+        }
+        Ok(Self::new(steps))
+    }
+}
+
+impl FeeOracle for FeeTimeline {
+    fn base_fee(&self, elapsed: Duration) -> i64 {
+        self.steps
+            .iter()
+            .take_while(|(at, _)| *at <= elapsed)
+            .last()
+            .or(self.steps.first())
+            .map_or(0, |(_, fee)| *fee)
+    }
+}
+
+/// A fee oracle shared by the transactions of a run, with the time the run started at
+/// (`FEE_ORACLE`).
+#[derive(Clone)]
+pub struct SharedFeeOracle {
+    oracle: Arc<dyn FeeOracle>,
+    started: Instant,
+}
+
+impl fmt::Debug for SharedFeeOracle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedFeeOracle")
+            .field("started", &self.started)
+            .finish()
+    }
+}
+
+impl SharedFeeOracle {
+    /// Shares `oracle`, starting its timeline now.
+    pub fn new(oracle: impl FeeOracle + 'static) -> Self {
+        Self::started_at(oracle, Instant::now())
+    }
+
+    /// Shares `oracle` with its timeline starting at `started`.
+    pub fn started_at(oracle: impl FeeOracle + 'static, started: Instant) -> Self {
+        Self {
+            oracle: Arc::new(oracle),
+            started,
+        }
+    }
+
+    /// Parses `sine:amplitude:period_secs`, oscillating around `commission`, or `csv:path`,
+    /// a `FeeTimeline` file; amounts have `decimals` decimal places.
+    pub fn parse(spec: &str, commission: i64, decimals: u32) -> Result<Self, String> {
+        match spec.split_once(':') {
+            Some(("sine", args)) => {
+                let (amplitude, period) = args
+                    .split_once(':')
+                    .ok_or_else(|| format!("invalid sine fee oracle: {}", spec))?;
+                let period = period
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                    .ok_or_else(|| format!("invalid sine fee period: {}", period))?;
+                Ok(Self::new(SineFee {
+                    mean: commission,
+                    amplitude: parse_amount(amplitude, decimals)?,
+                    period,
+                }))
+            }
+            Some(("csv", path)) => {
+                let csv = fs::read_to_string(path)
+                    .map_err(|err| format!("failed to read {}: {}", path, err))?;
+                Ok(Self::new(FeeTimeline::parse(&csv, decimals)?))
+            }
+            _ => Err(format!("unknown fee oracle: {}", spec)),
+        }
+    }
+
+    /// Returns the current base fee.
+    pub fn current(&self) -> i64 {
+        self.oracle.base_fee(self.started.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::tx::{SomeTransaction, Transaction};
+
+    #[test]
+    fn test_transactions_pick_up_the_scripted_fee() {
+        let timeline = FeeTimeline::parse("seconds,fee\n0,100\n20,50\n10,200\n", 0).unwrap();
+        let commission_at = |secs| {
+            let started = Instant::now() - Duration::from_secs(secs);
+            let params = Arc::new(EnvParams {
+                price: 1_000,
+                commission: 10,
+                fee_oracle: Some(SharedFeeOracle::started_at(timeline.clone(), started)),
+                ..Default::default()
+            });
+            SomeTransaction::new(&params).commission()
+        };
+
+        assert_eq!(commission_at(0), 100);
+        assert_eq!(commission_at(5), 100);
+        assert_eq!(commission_at(15), 200);
+        assert_eq!(commission_at(25), 50);
+        assert!(FeeTimeline::parse("0;100", 0).is_err());
+    }
+
+    #[test]
+    fn test_sine_fee_oscillates_around_the_mean() {
+        let sine = SineFee {
+            mean: 100,
+            amplitude: 40,
+            period: Duration::from_secs(4),
+        };
+        let fees: Vec<_> = (0..5)
+            .map(|secs| sine.base_fee(Duration::from_secs(secs)))
+            .collect();
+        assert_eq!(fees, vec![100, 140, 100, 60, 100]);
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::LatencyPercentiles;
+
+/// Full distribution of the execution latencies, exported for offline analysis (`HISTOGRAM_PATH`).
+///
+/// Every distinct latency, in microseconds, is its own bucket, so the percentiles computed
+/// from the histogram match the ones of the run summary exactly.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    /// Number of executions per latency, in microseconds.
+    pub buckets: BTreeMap<u64, u64>,
+}
+
+impl LatencyHistogram {
+    /// Builds the histogram of unordered latency samples, in microseconds.
+    pub fn from_samples(samples: &[u64]) -> Self {
+        let mut buckets = BTreeMap::new();
+        for sample in samples {
+            *buckets.entry(*sample).or_insert(0) += 1;
+        }
+        Self { buckets }
+    }
+
+    /// Returns the number of recorded executions.
+    pub fn count(&self) -> u64 {
+        self.buckets.values().sum()
+    }
+
+    /// Computes the percentiles of the histogram (nearest-rank method).
+    pub fn percentiles(&self) -> LatencyPercentiles {
+        let count = self.count();
+        let percentile = |p: f64| {
+            if count == 0 {
+                return 0;
+            }
+            let rank = ((p * count as f64).ceil() as u64).clamp(1, count);
+            let mut seen = 0;
+            for (latency, samples) in &self.buckets {
+                seen += samples;
+                if seen >= rank {
+                    return *latency;
+                }
+            }
+            0
+        };
+
+        LatencyPercentiles {
+            p50_us: percentile(0.50),
+            p90_us: percentile(0.90),
+            p99_us: percentile(0.99),
+        }
+    }
+
+    /// Writes the histogram as JSON to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, json).map_err(|err| err.to_string())
+    }
+
+    /// Loads a histogram previously written with `save`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {}: {}", path.display(), err))?;
+        serde_json::from_str(&json)
+            .map_err(|err| format!("failed to parse {}: {}", path.display(), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::metrics::Metrics;
+
+    #[test]
+    fn test_histogram_round_trips_to_the_same_percentiles() {
+        let metrics = Metrics::default();
+        for millis in [5, 1, 3, 3, 8, 2, 3, 20, 1, 4] {
+            metrics.record_success(Duration::from_millis(millis));
+        }
+        let histogram = metrics.latency_histogram();
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(histogram.buckets[&3_000], 3);
+
+        let path = std::env::temp_dir().join(format!("histogram-{}.json", std::process::id()));
+        histogram.save(&path).unwrap();
+        let loaded = LatencyHistogram::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, histogram);
+        assert_eq!(loaded.percentiles(), metrics.latency_percentiles());
+        assert_eq!(
+            loaded.percentiles(),
+            LatencyPercentiles {
+                p50_us: 3_000,
+                p90_us: 8_000,
+                p99_us: 20_000,
+            }
+        );
+    }
+}
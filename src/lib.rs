@@ -1,3 +1,36 @@
+pub mod backoff;
+pub mod balance;
+pub mod bidding;
+pub mod checkpoint;
+pub mod config;
 pub mod env_utils;
+pub mod errors;
+pub mod executor;
+pub mod exporter;
+pub mod fairness;
+pub mod fees;
+pub mod histogram;
 pub mod limits;
+pub mod metrics;
+pub mod output;
+pub mod pacing;
+pub mod pause;
+pub mod pool;
+pub mod runner;
+pub mod signals;
+pub mod signature;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+pub mod summary;
+#[cfg(feature = "otel")]
+pub mod telemetry;
 pub mod tx;
+pub mod tx_genertor;
+pub mod watchdog;
+pub mod window;
+
+#[cfg(test)]
+mod test_log;
+#[cfg(test)]
+mod test_tx;
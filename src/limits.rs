@@ -1,18 +1,106 @@
-use std::fmt::Debug;
-use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Debug};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 
-use log::{debug, info};
+use log::{debug, info, log, warn, Level};
 
+use crate::backoff::RecentOutcomes;
+use crate::checkpoint::{Checkpoint, CheckpointFile};
 use crate::env_utils::EnvParams;
-use crate::tx::Transaction;
+use crate::errors::ErrorClassifier;
+use crate::executor::{submit_detached, DryRunExecutor, Executor, SimExecutor, SubmitPool};
+use crate::fairness::TokenSlots;
+use crate::metrics::Metrics;
+use crate::pacing::TpsLimiter;
+use crate::pause::PauseGate;
+#[cfg(feature = "sqlite")]
+use crate::sqlite::{AttemptRecord, SqliteRecorder};
+use crate::stats::{Rejection, RejectionCounters, RunStats, StopReason, ThreadUtilization};
+#[cfg(feature = "otel")]
+use crate::telemetry::Telemetry;
+use crate::tx::{trace_tx, Transaction};
+use crate::window::{Reservation, SpendWindow};
 
 /// Enum representing the possible states of a transaction process.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub enum States {
     /// Indicates that the transaction process has finished.
     Finish,
-    /// Indicates that the transaction is in progress, with a message (signature or error).
-    InProgres(String),
+    /// Indicates that the transaction was refused without finishing the process.
+    Skipped(Rejection),
+    /// Indicates that the transaction was executed, with its signature and amount.
+    Success { signature: String, amount: i64 },
+    /// Indicates that the execution of the transaction failed, with the error and the amount
+    /// of the transaction.
+    Failed { error: String, amount: i64 },
+}
+
+/// Errors that prevent a transaction from being processed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessError {
+    /// The transaction is malformed, e.g. its amount does not match its components.
+    Invalid(String),
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Invalid(reason) => write!(f, "invalid transaction: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+/// Point-in-time view of the limiter progress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    pub transactions_count: usize,
+    pub committed_amount: i64,
+    pub remaining_budget: i64,
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "transactions processed: {}, amount committed: {}, remaining budget: {}",
+            self.transactions_count, self.committed_amount, self.remaining_budget
+        )
+    }
+}
+
+/// Closure invoked with the final statistics when the `LimitChecker` is dropped,
+/// e.g. to flush an audit log, write a checkpoint or close metrics.
+pub struct Finalizer(Box<dyn FnOnce(&RunStats) + Send + Sync>);
+
+impl Debug for Finalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Finalizer")
+    }
+}
+
+/// User-supplied check run right before `execute()`, vetoing the transaction with an `Err`,
+/// e.g. when the price has moved since the transaction was generated.
+pub struct PreExecute(Box<PreExecuteFn>);
+
+type PreExecuteFn = dyn Fn(&dyn Transaction) -> Result<(), String> + Send + Sync;
+
+impl Debug for PreExecute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PreExecute")
+    }
+}
+
+/// Backend the admitted transactions are submitted to, `SimExecutor` unless replaced.
+pub struct BoxedExecutor(Arc<dyn Executor>);
+
+impl Debug for BoxedExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Executor")
+    }
 }
 
 /// Struct responsible for checking transaction limits and managing transaction counts and amounts.
@@ -20,14 +108,97 @@ pub enum States {
 pub struct LimitChecker {
     /// Tracks the number of transactions processed.
     pub transactions_count: AtomicUsize,
-    /// Tracks the current total amount processed in transactions.
-    pub current_amount: AtomicI64,
+    /// Amount reserved by admitted transactions whose execution has not finished yet.
+    pub reserved_amount: AtomicI64,
+    /// Amount of the successfully executed transactions.
+    pub settled_amount: AtomicI64,
+    /// Commission of the successfully executed transactions.
+    pub paid_commission: AtomicI64,
+    /// Tokens bought by the admitted transactions, checked against `max_tokens`.
+    pub tokens_count: AtomicI64,
     /// Stores the environment parameters for the transaction process.
-    pub params: EnvParams,
+    params: EnvParams,
+    /// Success/failure counters and latencies of executed transactions.
+    pub metrics: Metrics,
+    /// Whether the final counters are logged at `info!` level on drop (otherwise `debug!`).
+    pub log_summary: bool,
+    /// Counts the refused transactions by reason.
+    pub rejections: RejectionCounters,
+    /// Counts the `process_transaction` calls that took longer than `tx_sla_ms`.
+    pub sla_violations: AtomicUsize,
+    /// Optional finalizer run on drop, so the run is finalized even on unexpected teardown.
+    pub finalizer: Option<Finalizer>,
+    /// Optional hook run after the limit check and before `execute()`, able to veto the transaction.
+    pub pre_execute: Option<PreExecute>,
+    /// Submits the admitted transactions.
+    pub executor: BoxedExecutor,
+    /// Executed transactions per rayon worker thread index (modulo `max_threads`).
+    /// Calls made outside of a rayon pool are attributed to thread 0.
+    pub thread_counts: Vec<AtomicUsize>,
+    /// Rolling per-window spending limit, when `window_amount_limit` is set.
+    pub spend_window: Option<SpendWindow>,
+    /// Paces the submissions, when `max_tps` is set.
+    pub tps_limiter: Option<TpsLimiter>,
+    /// Time from which no transaction is admitted, `max_runtime_secs` after the creation.
+    pub deadline: Option<Instant>,
+    /// Signatures returned by successful executions, used to detect duplicates.
+    pub signatures: Mutex<HashSet<String>>,
+    /// Counts the successful executions that returned an already seen signature.
+    pub duplicate_signatures: AtomicUsize,
+    /// Set once the process has finished, shared with generators so they stop early.
+    pub completed: Arc<AtomicBool>,
+    /// Counts the processed transactions to pick the ones logged under `log_sample_rate`.
+    pub log_counter: AtomicUsize,
+    /// Per-token concurrent execution slots, when `fair_tokens` is set for several `tokens`.
+    pub token_slots: Option<TokenSlots>,
+    /// Number of admitted transactions whose execution has not finished yet.
+    pub in_flight: AtomicUsize,
+    /// Ids of the successfully executed transactions, used to resolve `depends_on`.
+    pub succeeded: Mutex<HashSet<u64>>,
+    /// Counts the admitted transactions rolled back after a failure, a veto or the execute call cap.
+    pub rollback_count: AtomicUsize,
+    /// Amount returned to the budget by the rollbacks.
+    pub rolled_back_amount: AtomicI64,
+    /// Counts the execution errors per category of `error_categories`.
+    pub error_classifier: ErrorClassifier,
+    /// Amount committed (settled plus reserved) per wallet, checked against `per_wallet_amount_limit`.
+    pub wallet_amounts: Mutex<HashMap<String, i64>>,
+    /// Pause after every `pause_every_n` successes, resumed by touching `pause_resume_file` or pressing Enter.
+    pub pause_gate: Option<PauseGate>,
+    /// Time of the last successful execution (the creation of the checker before the first one),
+    /// monitored by the `Watchdog` against `stall_timeout_secs`.
+    pub last_success: Mutex<Instant>,
+    /// Set once the run was aborted, e.g. by the stall watchdog or the balance monitor,
+    /// finishing the later transactions.
+    pub aborted: AtomicBool,
+    /// Wakes the retry backoffs once the run is aborted, paired with `abort_lock`.
+    abort_wake: Condvar,
+    abort_lock: Mutex<()>,
+    /// The limit that finished the process first, if any.
+    finished_by: Mutex<Option<Rejection>>,
+    /// Counts the `execute()` calls, retries included, bounded by `max_execute_calls`.
+    pub execute_calls: AtomicUsize,
+    /// Outcomes of the last `backoff_window` executions, when `backoff_failure_rate` is set,
+    /// shared with the `AdaptiveThrottle` of the generator.
+    pub recent_outcomes: Option<Arc<RecentOutcomes>>,
+    /// Counts the re-executions of failed transactions under `retry_attempts`.
+    pub retries: AtomicUsize,
+    /// Threads running the submissions under `tx_timeout_ms`, one per worker thread.
+    submit_pool: Option<SubmitPool>,
+    /// Optional checkpoint of the counters, saved after every committed transaction.
+    pub checkpoint_file: Option<CheckpointFile>,
+    /// Optional OpenTelemetry tracing of every `process_transaction` call.
+    #[cfg(feature = "otel")]
+    pub telemetry: Option<Telemetry>,
+    /// Optional SQLite recording of every execution attempt.
+    #[cfg(feature = "sqlite")]
+    pub recorder: Option<Arc<SqliteRecorder>>,
 }
 
 impl LimitChecker {
-    /// Creates a new `LimitChecker` instance.
+    /// Creates a new `LimitChecker` instance, starting from `initial_committed` and
+    /// `initial_count` when part of the budget is already spent. The transactions are
+    /// submitted to the `SimExecutor` until `with_executor` sets another backend.
     ///
     /// # Arguments
     ///
@@ -37,10 +208,268 @@ impl LimitChecker {
     ///
     /// A new instance of `LimitChecker`.
     pub fn new(params: &EnvParams) -> Self {
+        let completed = Arc::new(AtomicBool::new(false));
         Self {
-            transactions_count: AtomicUsize::new(0),
-            current_amount: AtomicI64::new(0),
+            transactions_count: AtomicUsize::new(params.initial_count),
+            reserved_amount: AtomicI64::new(0),
+            settled_amount: AtomicI64::new(params.initial_committed),
+            paid_commission: AtomicI64::new(0),
+            tokens_count: AtomicI64::new(0),
             params: params.clone(),
+            metrics: Metrics::new(params.statsd_addr.as_deref()),
+            log_summary: params.log_drop_summary,
+            rejections: RejectionCounters::default(),
+            sla_violations: AtomicUsize::new(0),
+            finalizer: None,
+            pre_execute: None,
+            executor: BoxedExecutor(Arc::new(SimExecutor)),
+            thread_counts: (0..params.max_threads.max(1))
+                .map(|_| AtomicUsize::new(0))
+                .collect(),
+            spend_window: params
+                .window_amount_limit
+                .map(|limit| SpendWindow::new(limit, Duration::from_secs(params.window_secs))),
+            tps_limiter: params.max_tps.and_then(TpsLimiter::new),
+            deadline: params
+                .max_runtime_secs
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+                .and_then(|runtime| Instant::now().checked_add(runtime)),
+            signatures: Mutex::new(HashSet::new()),
+            duplicate_signatures: AtomicUsize::new(0),
+            completed: completed.clone(),
+            log_counter: AtomicUsize::new(0),
+            token_slots: (params.fair_tokens && params.tokens.len() > 1)
+                .then(|| TokenSlots::new(params.max_threads, params.tokens.len())),
+            in_flight: AtomicUsize::new(0),
+            succeeded: Mutex::new(HashSet::new()),
+            rollback_count: AtomicUsize::new(0),
+            rolled_back_amount: AtomicI64::new(0),
+            error_classifier: ErrorClassifier::new(params.error_categories.clone()),
+            wallet_amounts: Mutex::new(HashMap::new()),
+            pause_gate: params
+                .pause_every_n
+                .map(|every| match &params.pause_resume_file {
+                    Some(path) => PauseGate::new(
+                        every,
+                        PauseGate::resume_on_file(path.clone(), completed.clone()),
+                    ),
+                    None => PauseGate::new(every, PauseGate::resume_on_enter()),
+                }),
+            last_success: Mutex::new(Instant::now()),
+            aborted: AtomicBool::new(false),
+            abort_wake: Condvar::new(),
+            abort_lock: Mutex::new(()),
+            finished_by: Mutex::new(None),
+            execute_calls: AtomicUsize::new(0),
+            recent_outcomes: params
+                .backoff_failure_rate
+                .map(|_| Arc::new(RecentOutcomes::new(params.backoff_window))),
+            retries: AtomicUsize::new(0),
+            submit_pool: params
+                .tx_timeout_ms
+                .map(|_| SubmitPool::new(params.max_threads)),
+            // A dry run spends nothing, so it leaves the checkpoint of the real runs alone.
+            checkpoint_file: params
+                .checkpoint_file
+                .as_deref()
+                .filter(|_| !params.dry_run)
+                .map(CheckpointFile::new),
+            #[cfg(feature = "otel")]
+            telemetry: None,
+            #[cfg(feature = "sqlite")]
+            recorder: None,
+        }
+    }
+
+    /// Sets the finalizer invoked with the final statistics when the checker is dropped.
+    ///
+    /// # Arguments
+    ///
+    /// * `finalizer` - The closure to run on drop.
+    pub fn with_finalizer(
+        mut self,
+        finalizer: impl FnOnce(&RunStats) + Send + Sync + 'static,
+    ) -> Self {
+        self.finalizer = Some(Finalizer(Box::new(finalizer)));
+        self
+    }
+
+    /// Sets the hook run before every execution; an `Err` vetoes the transaction,
+    /// which is then reported as `Skipped(Rejection::Vetoed)` with its counters rolled back.
+    ///
+    /// # Arguments
+    ///
+    /// * `pre_execute` - The check to run.
+    pub fn with_pre_execute(
+        mut self,
+        pre_execute: impl Fn(&dyn Transaction) -> Result<(), String> + Send + Sync + 'static,
+    ) -> Self {
+        self.pre_execute = Some(PreExecute(Box::new(pre_execute)));
+        self
+    }
+
+    /// Sets the backend the admitted transactions are submitted to, replacing the simulated
+    /// `execute()` of the `SimExecutor`, e.g. with an RPC client.
+    ///
+    /// # Arguments
+    ///
+    /// * `executor` - The backend to submit to.
+    pub fn with_executor(mut self, executor: impl Executor + 'static) -> Self {
+        self.executor = BoxedExecutor(Arc::new(executor));
+        self
+    }
+
+    /// Sets the gate pausing the run after every n successes, replacing the one of `pause_every_n`.
+    ///
+    /// # Arguments
+    ///
+    /// * `pause_gate` - The gate to pause on.
+    pub fn with_pause_gate(mut self, pause_gate: PauseGate) -> Self {
+        self.pause_gate = Some(pause_gate);
+        self
+    }
+
+    /// Sets the OpenTelemetry tracing of the processed transactions.
+    ///
+    /// # Arguments
+    ///
+    /// * `telemetry` - The tracing to record the spans with.
+    #[cfg(feature = "otel")]
+    pub fn with_telemetry(mut self, telemetry: Telemetry) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Sets the SQLite recording of the execution attempts.
+    ///
+    /// # Arguments
+    ///
+    /// * `recorder` - The recorder to send the attempts to.
+    #[cfg(feature = "sqlite")]
+    pub fn with_recorder(mut self, recorder: Arc<SqliteRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Aborts the run: the generators stop, the transactions waiting for the spending window
+    /// and the later ones finish the process.
+    pub fn abort(&self) {
+        self.aborted.store(true, Ordering::SeqCst);
+        self.completed.store(true, Ordering::SeqCst);
+        // Taking the lock orders the wake-up after the check of a backoff about to wait.
+        drop(self.abort_lock.lock().unwrap());
+        self.abort_wake.notify_all();
+        if let Some(window) = &self.spend_window {
+            window.abort();
+        }
+    }
+
+    /// Finishes the process because of `reason`, which is kept as the stop reason of the run
+    /// unless another limit finished it first.
+    fn finish(&self, reason: Rejection) {
+        self.finished_by.lock().unwrap().get_or_insert(reason);
+        self.completed.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns the parameters the checker was created with.
+    pub fn params(&self) -> &EnvParams {
+        &self.params
+    }
+
+    /// Returns the total budget of the run.
+    pub fn total_amount(&self) -> i64 {
+        self.params.total_amount
+    }
+
+    /// Returns the maximum number of committed transactions.
+    pub fn max_transactions(&self) -> usize {
+        self.params.max_transactions
+    }
+
+    /// Returns the base commission.
+    pub fn commission(&self) -> i64 {
+        self.params.commission
+    }
+
+    /// Returns the allowed variation of the commission.
+    pub fn commission_change(&self) -> i64 {
+        self.params.commission_change
+    }
+
+    /// Returns the maximum number of worker threads.
+    pub fn max_threads(&self) -> usize {
+        self.params.max_threads
+    }
+
+    /// Returns whether the transaction `tx` depends on, if any, has succeeded.
+    pub fn dependency_met(&self, tx: &impl Transaction) -> bool {
+        tx.depends_on()
+            .is_none_or(|id| self.succeeded.lock().unwrap().contains(&id))
+    }
+
+    /// Returns the amount committed so far: settled plus currently reserved.
+    pub fn committed_amount(&self) -> i64 {
+        // Settling adds to `settled_amount` before removing from `reserved_amount`,
+        // so reading the reservations first never under-counts.
+        let reserved = self.reserved_amount.load(Ordering::SeqCst);
+        self.settled_amount.load(Ordering::SeqCst) + reserved
+    }
+
+    /// Returns the current progress without touching the other statistics.
+    pub fn snapshot(&self) -> Snapshot {
+        let committed_amount = self.committed_amount();
+        Snapshot {
+            transactions_count: self.transactions_count.load(Ordering::SeqCst),
+            committed_amount,
+            remaining_budget: self.params.total_amount - committed_amount,
+        }
+    }
+
+    /// Returns the internal backpressure in `[0, 1]`: the larger of the share of busy workers
+    /// and the average latency relative to `backpressure_latency_ms`, if set.
+    pub fn pressure(&self) -> f64 {
+        let workers = self.params.max_threads.max(1) as f64;
+        let queue = self.in_flight.load(Ordering::SeqCst) as f64 / workers;
+        let latency = self
+            .params
+            .backpressure_latency_ms
+            .filter(|target| *target > 0)
+            .map_or(0.0, |target| {
+                self.metrics.average_latency().as_secs_f64() * 1000.0 / target as f64
+            });
+
+        queue.max(latency).clamp(0.0, 1.0)
+    }
+
+    /// Returns the statistics accumulated so far.
+    pub fn stats(&self) -> RunStats {
+        RunStats {
+            transactions_count: self.transactions_count.load(Ordering::SeqCst),
+            committed_amount: self.committed_amount(),
+            commission_paid: self.paid_commission.load(Ordering::SeqCst),
+            stop_reason: if self.aborted.load(Ordering::SeqCst) {
+                StopReason::Aborted
+            } else {
+                self.finished_by
+                    .lock()
+                    .unwrap()
+                    .map_or(StopReason::Exhausted, StopReason::Limit)
+            },
+            successes: self.metrics.success.load(Ordering::SeqCst),
+            failures: self.metrics.fail.load(Ordering::SeqCst),
+            rejections: self.rejections.counts(),
+            sla_violations: self.sla_violations.load(Ordering::SeqCst),
+            duplicate_signatures: self.duplicate_signatures.load(Ordering::SeqCst),
+            rollback_count: self.rollback_count.load(Ordering::SeqCst),
+            rolled_back_amount: self.rolled_back_amount.load(Ordering::SeqCst),
+            error_categories: self.error_classifier.counts(),
+            latency: self.metrics.latency_percentiles(),
+            thread_utilization: ThreadUtilization::from_counts(
+                self.thread_counts
+                    .iter()
+                    .map(|count| count.load(Ordering::SeqCst))
+                    .collect(),
+            ),
         }
     }
 
@@ -52,75 +481,784 @@ impl LimitChecker {
     ///
     /// # Returns
     ///
-    /// `Result<States, ()>` indicating the state after processing the transaction.
-    pub fn process_transaction(&self, tx: &(impl Transaction + Debug)) -> Result<States, ()> {
+    /// `Result<States, ProcessError>` indicating the state after processing the transaction.
+    pub fn process_transaction(
+        &self,
+        tx: &(impl Transaction + Debug),
+    ) -> Result<States, ProcessError> {
+        let started = Instant::now();
+        #[cfg(feature = "otel")]
+        let result = match &self.telemetry {
+            Some(telemetry) => telemetry.trace(tx, self.budget_amount(tx), || self.process(tx)),
+            None => self.process(tx),
+        };
+        #[cfg(not(feature = "otel"))]
+        let result = self.process(tx);
+        self.track_sla(started.elapsed());
+        result
+    }
+
+    /// Counts an SLA violation if the end-to-end processing time exceeded `tx_sla_ms`.
+    ///
+    /// # Arguments
+    ///
+    /// * `elapsed` - The time spent in `process_transaction`.
+    fn track_sla(&self, elapsed: Duration) {
+        if let Some(sla_ms) = self.params.tx_sla_ms {
+            if elapsed > Duration::from_millis(sla_ms) {
+                info!(
+                    "Transaction took {:?}, exceeding the SLA of {}ms.",
+                    elapsed, sla_ms
+                );
+                self.sla_violations.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Checks limits and executes the transaction if within bounds.
+    fn process(&self, tx: &(impl Transaction + Debug)) -> Result<States, ProcessError> {
         debug!("{}", tx.info());
+        let sampled = self.sample_log();
+
+        let tx_amount = self.budget_amount(tx);
+
+        if self.aborted.load(Ordering::SeqCst) {
+            info!("Run aborted. Finishing process.");
+            return Ok(States::Finish);
+        }
+
+        // Past the deadline, the in-flight transactions complete but no new one is admitted.
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            info!("Time limit reached. Finishing process.");
+            trace_tx!(self.params, tx.id(), "rejected: {}", Rejection::TimeLimit);
+            self.rejections.record(Rejection::TimeLimit);
+            self.finish(Rejection::TimeLimit);
+            return Ok(States::Finish);
+        }
+
+        if self.params.validate_amounts {
+            Self::validate_amount(tx).inspect_err(|err| {
+                trace_tx!(self.params, tx.id(), "validation failed: {}", err);
+            })?;
+            trace_tx!(self.params, tx.id(), "validated: amount = {}", tx_amount);
+        }
+
+        // Dependent transactions only execute once their dependency succeeded.
+        if !self.dependency_met(tx) {
+            info!("Transaction skipped: its dependency has not succeeded.");
+            trace_tx!(
+                self.params,
+                tx.id(),
+                "rejected: {}",
+                Rejection::UnmetDependency
+            );
+            self.rejections.record(Rejection::UnmetDependency);
+            return Ok(States::Skipped(Rejection::UnmetDependency));
+        }
+
+        // Dust transactions are skipped without finishing the process.
+        if tx_amount < self.params.dust_threshold {
+            if sampled {
+                info!("Transaction skipped: amount below dust threshold.");
+            }
+            trace_tx!(self.params, tx.id(), "rejected: {}", Rejection::Dust);
+            self.rejections.record(Rejection::Dust);
+            return Ok(States::Skipped(Rejection::Dust));
+        }
 
-        let tx_amount = tx.amount();
+        // Transactions larger than the per-window limit can never be sent.
+        if let Some(window) = &self.spend_window {
+            if !window.fits(tx_amount) {
+                if sampled {
+                    info!("Transaction skipped: amount exceeds the per-window limit.");
+                }
+                trace_tx!(self.params, tx.id(), "rejected: {}", Rejection::WindowCap);
+                self.rejections.record(Rejection::WindowCap);
+                return Ok(States::Skipped(Rejection::WindowCap));
+            }
+        }
 
         // Check if there are sufficient funds for the transaction.
         if self.params.total_amount < tx_amount {
             info!("Insufficient funds for this transaction. Finishing process.");
+            trace_tx!(
+                self.params,
+                tx.id(),
+                "rejected: {}",
+                Rejection::InsufficientFunds
+            );
+            self.rejections.record(Rejection::InsufficientFunds);
+            self.finish(Rejection::InsufficientFunds);
             return Ok(States::Finish);
         }
 
+        // Delay the transaction until the per-window limit has room for it.
+        let reservation = match &self.spend_window {
+            Some(window) => match window.wait_and_commit(tx_amount) {
+                Some(reservation) => Some(reservation),
+                None => {
+                    info!("Run aborted. Finishing process.");
+                    return Ok(States::Finish);
+                }
+            },
+            None => None,
+        };
+
         // Check if the transaction exceeds limits.
-        if self.check(tx) {
-            info!("Transaction within limits. Proceeding with execution.");
+        if let Err(reason) = self.check(tx, sampled) {
+            self.release_window(reservation);
+            // A capped wallet is skipped while the other wallets still have room.
+            if reason == Rejection::WalletCap && !self.wallets_exhausted(tx_amount) {
+                if sampled {
+                    info!("Transaction skipped: its wallet reached its amount cap.");
+                }
+                trace_tx!(self.params, tx.id(), "rejected: {}", reason);
+                self.rejections.record(reason);
+                return Ok(States::Skipped(reason));
+            }
+            info!("Transaction skipped: exceeds limits ({}).", reason);
+            trace_tx!(self.params, tx.id(), "rejected: {}", reason);
+            self.rejections.record(reason);
+            self.finish(reason);
+            Ok(States::Finish)
+        } else {
+            if sampled {
+                info!("Transaction within limits. Proceeding with execution.");
+            }
 
-            self.transactions_count.fetch_add(1, Ordering::SeqCst);
-            self.current_amount.fetch_add(tx_amount, Ordering::SeqCst);
+            // Let the pre-execute hook veto the transaction it has been reserved for.
+            if let Some(PreExecute(pre_execute)) = &self.pre_execute {
+                if let Err(reason) = pre_execute(tx) {
+                    info!("Transaction vetoed before execution: {}", reason);
+                    self.roll_back(tx, tx_amount, reservation);
+                    trace_tx!(self.params, tx.id(), "rejected: {}", Rejection::Vetoed);
+                    self.rejections.record(Rejection::Vetoed);
+                    return Ok(States::Skipped(Rejection::Vetoed));
+                }
+            }
+
+            // Hold the execution while the operator inspects a pause.
+            if let Some(gate) = &self.pause_gate {
+                gate.wait();
+            }
 
-            match tx.execute() {
-                // Rollback counters if transaction execution fails.
+            // Retry a failed execution up to `retry_attempts` times, keeping the reservation.
+            let mut attempt = 0;
+            let (result, latency) = loop {
+                attempt += 1;
+                // Stop at the hard bound on executions, whatever the other limits allow.
+                if !self.reserve_execute_call() {
+                    info!("Execute call cap reached. Finishing process.");
+                    self.roll_back(tx, tx_amount, reservation);
+                    trace_tx!(self.params, tx.id(), "rejected: {}", Rejection::ExecuteCap);
+                    self.rejections.record(Rejection::ExecuteCap);
+                    self.finish(Rejection::ExecuteCap);
+                    return Ok(States::Finish);
+                }
+
+                let (result, latency) = self.execute_once(tx);
+                #[cfg(feature = "sqlite")]
+                if let Some(recorder) = &self.recorder {
+                    recorder.record(AttemptRecord::new(
+                        tx.id(),
+                        tx.token(),
+                        tx_amount,
+                        result.is_err(),
+                        latency,
+                    ));
+                }
+                match (result, latency) {
+                    // The failure is counted once, by the attempt ending the retries.
+                    (Err(err_mess), latency) if attempt <= self.params.retry_attempts => {
+                        let backoff = self.retry_backoff(attempt);
+                        if sampled {
+                            info!(
+                                "Transaction failed ({}), retrying in {:?}.",
+                                err_mess, backoff
+                            );
+                        }
+                        trace_tx!(self.params, tx.id(), "retry {} in {:?}", attempt, backoff);
+                        if !self.sleep_unless_aborted(backoff) {
+                            break (Err(err_mess), latency);
+                        }
+                        self.record_outcome(true);
+                        self.error_classifier.record(&err_mess);
+                        self.retries.fetch_add(1, Ordering::SeqCst);
+                    }
+                    outcome => break outcome,
+                }
+            };
+
+            match result {
+                // Release the reservation and roll back the count if transaction execution fails.
                 Err(err_mess) => {
-                    info!("Transaction failed - rolling back counters.");
+                    if sampled {
+                        info!("Transaction failed - rolling back counters.");
+                    }
+                    self.metrics.record_failure(latency);
+                    self.record_outcome(true);
+                    self.error_classifier.record(&err_mess);
 
-                    self.transactions_count.fetch_sub(1, Ordering::SeqCst);
-                    self.current_amount.fetch_sub(tx_amount, Ordering::SeqCst);
-                    Ok(States::InProgres(err_mess))
+                    self.roll_back(tx, tx_amount, reservation);
+                    trace_tx!(self.params, tx.id(), "rolled back: amount = {}", tx_amount);
+                    Ok(States::Failed {
+                        error: err_mess,
+                        amount: tx_amount,
+                    })
+                }
+                // Settle the reservation and return the success message if execution succeeds.
+                Ok(mess) => {
+                    let mess = if self.params.dry_run {
+                        mess
+                    } else {
+                        self.params.signature_format.normalize(&mess)
+                    };
+                    self.settled_amount.fetch_add(tx_amount, Ordering::SeqCst);
+                    self.paid_commission
+                        .fetch_add(tx.commission(), Ordering::SeqCst);
+                    self.reserved_amount.fetch_sub(tx_amount, Ordering::SeqCst);
+                    self.metrics.record_success(latency);
+                    self.record_outcome(false);
+                    self.track_signature(&mess);
+                    self.succeeded.lock().unwrap().insert(tx.id());
+                    *self.last_success.lock().unwrap() = Instant::now();
+                    trace_tx!(self.params, tx.id(), "committed: amount = {}", tx_amount);
+                    if let Some(memo) = tx.memo() {
+                        trace_tx!(self.params, tx.id(), "memo: {}", memo);
+                    }
+                    self.save_checkpoint();
+                    if let Some(gate) = &self.pause_gate {
+                        gate.on_success();
+                    }
+                    Ok(States::Success {
+                        signature: mess,
+                        amount: tx_amount,
+                    })
                 }
-                // Return success message if transaction execution succeeds.
-                Ok(mess) => Ok(States::InProgres(mess)),
             }
+        }
+    }
+
+    /// Submits the transaction once to the executor, attributing it to the current worker thread.
+    ///
+    /// # Returns
+    ///
+    /// The result of the submission and its latency.
+    fn execute_once(&self, tx: &impl Transaction) -> (Result<String, String>, Duration) {
+        let thread = rayon::current_thread_index().unwrap_or(0) % self.thread_counts.len();
+        self.thread_counts[thread].fetch_add(1, Ordering::SeqCst);
+
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        // Wait for a slot of the token so that the other campaigns keep progressing.
+        let slot = self
+            .token_slots
+            .as_ref()
+            .map(|slots| slots.acquire(tx.token()));
+        if let Some(tps_limiter) = &self.tps_limiter {
+            tps_limiter.wait();
+        }
+        let started = Instant::now();
+        let result = match self.params.tx_timeout_ms {
+            Some(timeout) => self.submit_with_timeout(tx, Duration::from_millis(timeout)),
+            None => self.submit(tx),
+        };
+        let latency = started.elapsed();
+        drop(slot);
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+        trace_tx!(
+            self.params,
+            tx.id(),
+            "executed in {:?}: {}",
+            latency,
+            if result.is_ok() { "ok" } else { "error" }
+        );
+        (result, latency)
+    }
+
+    /// Submits the transaction to the executor, the `DryRunExecutor` in a dry run.
+    fn submit(&self, tx: &impl Transaction) -> Result<String, String> {
+        if self.params.dry_run {
+            DryRunExecutor.submit(tx)
         } else {
-            info!("Transaction skipped: exceeds limits.");
-            Ok(States::Finish)
+            self.executor.0.submit(tx)
         }
     }
 
-    /// Checks if the transaction can be processed without exceeding limits.
+    /// Submits the transaction on a thread of the `submit_pool`, failing it with `timeout` when
+    /// no result arrives within `timeout` (`tx_timeout_ms`). The worker moves on right away, the
+    /// hung submission finishes on its pool thread and its late result is discarded.
+    fn submit_with_timeout(
+        &self,
+        tx: &impl Transaction,
+        timeout: Duration,
+    ) -> Result<String, String> {
+        let executor: Arc<dyn Executor> = if self.params.dry_run {
+            Arc::new(DryRunExecutor)
+        } else {
+            self.executor.0.clone()
+        };
+        let pool = self
+            .submit_pool
+            .as_ref()
+            .expect("submit pool without tx_timeout_ms");
+        submit_detached(pool, executor, tx, timeout).unwrap_or_else(|| {
+            warn!("Transaction {} timed out after {:?}.", tx.id(), timeout);
+            Err("timeout".to_string())
+        })
+    }
+
+    /// Returns the delay before the `attempt`-th retry: `retry_backoff_ms`, doubled on every
+    /// further retry.
+    fn retry_backoff(&self, attempt: usize) -> Duration {
+        let factor = 1u64.checked_shl(attempt as u32 - 1).unwrap_or(u64::MAX);
+        Duration::from_millis(self.params.retry_backoff_ms.saturating_mul(factor))
+    }
+
+    /// Sleeps for `duration`, waking up early when the run is aborted. The other limits
+    /// finishing the process let the pending retries run.
+    ///
+    /// # Returns
+    ///
+    /// `false` when the run was aborted before the end of the sleep.
+    fn sleep_unless_aborted(&self, duration: Duration) -> bool {
+        let guard = self.abort_lock.lock().unwrap();
+        let _ = self
+            .abort_wake
+            .wait_timeout_while(guard, duration, |_| !self.aborted.load(Ordering::SeqCst))
+            .unwrap();
+        !self.aborted.load(Ordering::SeqCst)
+    }
+
+    /// Counts an `execute()` call, unless `max_execute_calls` have already been made.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the cap is reached.
+    fn reserve_execute_call(&self) -> bool {
+        let cap = self.params.max_execute_calls.unwrap_or(usize::MAX);
+        self.execute_calls
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |calls| {
+                (calls < cap).then_some(calls + 1)
+            })
+            .is_ok()
+    }
+
+    /// Records the outcome of an execution in the `recent_outcomes`, if tracked.
+    fn record_outcome(&self, failed: bool) {
+        if let Some(outcomes) = &self.recent_outcomes {
+            outcomes.record(failed);
+        }
+    }
+
+    /// Decides whether the current transaction logs its per-transaction detail lines:
+    /// 1 in `log_sample_rate` does, milestones such as the end of the run always log.
+    fn sample_log(&self) -> bool {
+        let rate = self.params.log_sample_rate.max(1);
+        self.log_counter
+            .fetch_add(1, Ordering::Relaxed)
+            .is_multiple_of(rate)
+    }
+
+    /// Returns the amount the transaction costs in budget units: its price plus its commission
+    /// converted with `fee_to_budget_rate` (1.0 when unset).
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - A reference to the transaction.
+    pub fn budget_amount(&self, tx: &impl Transaction) -> i64 {
+        match self.params.fee_to_budget_rate {
+            Some(rate) => tx.price() + (tx.commission() as f64 * rate).round() as i64,
+            None => tx.amount(),
+        }
+    }
+
+    /// Records the signature of a successful execution and counts it if it was already seen,
+    /// which means the backend returned the same signature for two transactions.
+    ///
+    /// # Arguments
+    ///
+    /// * `signature` - The signature returned by `execute()`.
+    fn track_signature(&self, signature: &str) {
+        let mut signatures = self.signatures.lock().unwrap();
+        if !signatures.insert(signature.to_string()) {
+            warn!("Duplicate transaction signature returned: {}", signature);
+            self.duplicate_signatures.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Saves the current count and committed amount to the checkpoint file, if configured.
+    fn save_checkpoint(&self) {
+        if let Some(file) = &self.checkpoint_file {
+            file.save(|| Checkpoint {
+                transactions_count: self.transactions_count.load(Ordering::SeqCst),
+                committed_amount: self.committed_amount(),
+            });
+        }
+    }
+
+    /// Undoes the admission of a transaction that did not settle: its count, tokens,
+    /// reservation, window share and wallet amount are released and the rollback is recorded.
+    fn roll_back(&self, tx: &impl Transaction, amount: i64, reservation: Option<Reservation>) {
+        self.transactions_count.fetch_sub(1, Ordering::SeqCst);
+        self.tokens_count.fetch_sub(tx.tokens(), Ordering::SeqCst);
+        self.reserved_amount.fetch_sub(amount, Ordering::SeqCst);
+        self.release_window(reservation);
+        self.release_wallet(tx.wallet(), amount);
+        self.rollback_count.fetch_add(1, Ordering::SeqCst);
+        self.rolled_back_amount.fetch_add(amount, Ordering::SeqCst);
+    }
+
+    /// Returns the amount committed under `reservation` to the per-window limit, if configured.
+    fn release_window(&self, reservation: Option<Reservation>) {
+        if let (Some(window), Some(reservation)) = (&self.spend_window, reservation) {
+            window.release(reservation);
+        }
+    }
+
+    /// Verifies that the transaction amount equals the sum of its price and commission.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - A reference to the transaction to be validated.
+    fn validate_amount(tx: &impl Transaction) -> Result<(), ProcessError> {
+        let expected = tx.price() + tx.commission();
+        if tx.amount() != expected {
+            return Err(ProcessError::Invalid(format!(
+                "amount {} does not match price {} + commission {}",
+                tx.amount(),
+                tx.price(),
+                tx.commission()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks if the transaction can be processed without exceeding limits,
+    /// reserving its tokens, wallet amount and amount when it can.
     ///
     /// # Arguments
     ///
     /// * `tx` - A reference to the transaction to be checked.
+    /// * `sampled` - Whether the transaction logs its detail line.
     ///
     /// # Returns
     ///
-    /// `bool` indicating whether the transaction can be processed.
-    fn check(&self, tx: &impl Transaction) -> bool {
-        let tx_amount = tx.amount();
+    /// `Ok(())` if the count and the amount are reserved for the transaction, otherwise the limit it would exceed.
+    fn check(&self, tx: &impl Transaction, sampled: bool) -> Result<(), Rejection> {
+        let tx_amount = self.budget_amount(tx);
         let transactions_count = self.transactions_count.load(Ordering::SeqCst);
-        let current_amount = self.current_amount.load(Ordering::SeqCst);
+        let committed_amount = self.committed_amount();
 
-        info!(
-            "Checking transaction: transactions_count = {}, current_amount + tx_amount = {} (limit = {})",
-            transactions_count, current_amount + tx_amount, self.params.total_amount
+        if sampled {
+            info!(
+                "Checking transaction: transactions_count = {}, current_amount + tx_amount = {} (limit = {})",
+                transactions_count, committed_amount + tx_amount, self.params.total_amount
+            );
+        }
+        trace_tx!(
+            self.params,
+            tx.id(),
+            "limit-checked: count = {}/{}, amount = {} + {} (limit = {})",
+            transactions_count,
+            self.params.max_transactions,
+            committed_amount,
+            tx_amount,
+            self.params.total_amount
         );
 
-        transactions_count < self.params.max_transactions
-            && current_amount + tx_amount <= self.params.total_amount
+        // Every cap is reserved atomically and released if a later one refuses the transaction,
+        // so concurrent threads cannot all pass against the same remaining room.
+        if !self.try_reserve_count() {
+            Err(Rejection::CountCap)
+        } else if !self.try_reserve_tokens(tx.tokens()) {
+            self.transactions_count.fetch_sub(1, Ordering::SeqCst);
+            Err(Rejection::TokenCap)
+        } else if !self.try_reserve_wallet(tx.wallet(), tx_amount) {
+            self.transactions_count.fetch_sub(1, Ordering::SeqCst);
+            self.tokens_count.fetch_sub(tx.tokens(), Ordering::SeqCst);
+            Err(Rejection::WalletCap)
+        } else if !self.try_reserve(tx_amount) {
+            self.transactions_count.fetch_sub(1, Ordering::SeqCst);
+            self.tokens_count.fetch_sub(tx.tokens(), Ordering::SeqCst);
+            self.release_wallet(tx.wallet(), tx_amount);
+            Err(Rejection::AmountCap)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Atomically counts a transaction if the count stays within `max_transactions`.
+    fn try_reserve_count(&self) -> bool {
+        self.transactions_count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < self.params.max_transactions).then_some(count + 1)
+            })
+            .is_ok()
+    }
+
+    /// Adds `amount` to the committed amount of `wallet` if it stays within
+    /// `per_wallet_amount_limit`, always when the limit is unset.
+    fn try_reserve_wallet(&self, wallet: &str, amount: i64) -> bool {
+        let mut wallet_amounts = self.wallet_amounts.lock().unwrap();
+        let committed = wallet_amounts.entry(wallet.to_string()).or_insert(0);
+        if self
+            .params
+            .per_wallet_amount_limit
+            .is_some_and(|limit| *committed + amount > limit)
+        {
+            return false;
+        }
+        *committed += amount;
+        true
+    }
+
+    /// Returns `amount` to the committed amount of `wallet`.
+    fn release_wallet(&self, wallet: &str, amount: i64) {
+        if let Some(committed) = self.wallet_amounts.lock().unwrap().get_mut(wallet) {
+            *committed -= amount;
+        }
+    }
+
+    /// Returns whether none of the configured wallets has room for `amount` anymore.
+    fn wallets_exhausted(&self, amount: i64) -> bool {
+        let Some(limit) = self.params.per_wallet_amount_limit else {
+            return false;
+        };
+        let wallet_amounts = self.wallet_amounts.lock().unwrap();
+        let committed = |wallet: &str| wallet_amounts.get(wallet).copied().unwrap_or(0);
+        if self.params.wallets.is_empty() {
+            committed(&self.params.wallet) + amount > limit
+        } else {
+            self.params
+                .wallets
+                .iter()
+                .all(|wallet| committed(wallet) + amount > limit)
+        }
+    }
+
+    /// Atomically counts `tokens` if they fit under `max_tokens`, always when it is unset.
+    fn try_reserve_tokens(&self, tokens: i64) -> bool {
+        let Some(max_tokens) = self.params.max_tokens else {
+            self.tokens_count.fetch_add(tokens, Ordering::SeqCst);
+            return true;
+        };
+        self.tokens_count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count + tokens <= max_tokens).then_some(count + tokens)
+            })
+            .is_ok()
+    }
+
+    /// Atomically reserves `amount` if the settled and reserved amounts leave room for it,
+    /// so that concurrent transactions cannot over-commit the budget.
+    fn try_reserve(&self, amount: i64) -> bool {
+        let mut reserved = self.reserved_amount.load(Ordering::SeqCst);
+        loop {
+            // Read after `reserved`, see `committed_amount`.
+            let settled = self.settled_amount.load(Ordering::SeqCst);
+            if settled + reserved + amount > self.params.total_amount {
+                return false;
+            }
+            match self.reserved_amount.compare_exchange_weak(
+                reserved,
+                reserved + amount,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => reserved = actual,
+            }
+        }
     }
 }
 
 // Implementation of the Drop trait for `LimitChecker`.
+// The summary is only logged at `info!` level when `log_summary` is enabled,
+// the finalizer, if any, runs afterwards.
 impl Drop for LimitChecker {
     fn drop(&mut self) {
         let final_count = self.transactions_count.load(Ordering::SeqCst);
-        let final_amount = self.current_amount.load(Ordering::SeqCst);
-        info!(
-            "LimitChecker is being dropped. Final transaction count: {}, Final total amount: {}",
-            final_count, final_amount
+        let final_amount = self.committed_amount();
+        let final_commission = self.paid_commission.load(Ordering::SeqCst);
+        let level = if self.log_summary {
+            Level::Info
+        } else {
+            Level::Debug
+        };
+        log!(
+            level,
+            "LimitChecker is being dropped. Final transaction count: {}, Final total amount: {}, \
+             Final commission paid: {}",
+            final_count,
+            final_amount,
+            final_commission
+        );
+
+        // No transaction is executing any more, so the final checkpoint holds no reservation.
+        self.save_checkpoint();
+        if let Some(Finalizer(finalizer)) = self.finalizer.take() {
+            finalizer(&self.stats());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_log;
+    use crate::test_tx::StubTransaction;
+    use crate::tx::SomeTransaction;
+
+    fn traced() -> StubTransaction {
+        StubTransaction::new().with_price(100, 50).with_id(7)
+    }
+
+    #[test]
+    fn test_paid_commission_sums_the_successful_transactions() {
+        let params = Arc::new(EnvParams {
+            price: 100,
+            commission: 50,
+            commission_change: 20,
+            total_amount: 100_000,
+            max_transactions: 100,
+            failure_rate: Some(0.0),
+            force_fail_indices: Arc::new([1, 4].into()),
+            ..Default::default()
+        });
+        let limiter = LimitChecker::new(&params);
+
+        let mut expected = 0;
+        for id in 0..6 {
+            let mut tx = SomeTransaction::new(&params);
+            tx.id = id;
+            if let States::Success { .. } = limiter.process_transaction(&tx).unwrap() {
+                expected += tx.commission();
+            }
+        }
+
+        assert_eq!(limiter.metrics.fail.load(Ordering::SeqCst), 2);
+        assert_eq!(limiter.paid_commission.load(Ordering::SeqCst), expected);
+        assert_eq!(limiter.stats().commission_paid, expected);
+    }
+
+    #[test]
+    fn test_trace_mode_logs_every_stage() {
+        let params = EnvParams {
+            total_amount: 1000,
+            max_transactions: 10,
+            validate_amounts: true,
+            trace_tx: true,
+            ..Default::default()
+        };
+        let limiter = LimitChecker::new(&params);
+
+        let stages: Vec<_> = test_log::capture(|| {
+            limiter.process_transaction(&traced()).unwrap();
+        })
+        .into_iter()
+        .filter(|(level, _)| *level == Level::Trace)
+        .map(|(_, message)| message)
+        .collect();
+
+        assert_eq!(stages.len(), 4);
+        assert!(stages.iter().all(|message| message.starts_with("[tx 7] ")));
+        assert_eq!(stages[0], "[tx 7] validated: amount = 150");
+        assert_eq!(
+            stages[1],
+            "[tx 7] limit-checked: count = 0/10, amount = 0 + 150 (limit = 1000)"
         );
+        assert!(stages[2].starts_with("[tx 7] executed in ") && stages[2].ends_with(": ok"));
+        assert_eq!(stages[3], "[tx 7] committed: amount = 150");
+    }
+
+    #[test]
+    fn test_log_sample_rate_keeps_one_in_n_detail_lines() {
+        let limiter = LimitChecker::new(&EnvParams {
+            total_amount: 100_000,
+            max_transactions: 20,
+            log_sample_rate: 4,
+            ..Default::default()
+        });
+
+        let logs = test_log::capture(|| {
+            while limiter.process_transaction(&traced()).unwrap() != States::Finish {}
+        });
+        let count = |prefix: &str| {
+            logs.iter()
+                .filter(|(_, message)| message.starts_with(prefix))
+                .count()
+        };
+
+        // 21 transactions processed: 20 admitted, the last one hits the count cap.
+        assert_eq!(count("Checking transaction"), 6);
+        assert_eq!(count("Transaction within limits"), 5);
+        assert_eq!(count("Transaction skipped: exceeds limits"), 1);
+    }
+
+    #[test]
+    fn test_trace_mode_disabled_by_default() {
+        let limiter = LimitChecker::new(&EnvParams {
+            total_amount: 1000,
+            max_transactions: 10,
+            ..Default::default()
+        });
+        let traces = test_log::capture(|| {
+            limiter.process_transaction(&traced()).unwrap();
+        })
+        .into_iter()
+        .filter(|(level, _)| *level == Level::Trace)
+        .count();
+        assert_eq!(traces, 0);
+    }
+
+    #[test]
+    fn test_finalizer_runs_on_drop() {
+        use std::sync::{Arc, Mutex};
+
+        let finalized = Arc::new(Mutex::new(None));
+        let sink = finalized.clone();
+        let limiter = LimitChecker::new(&EnvParams {
+            total_amount: 1000,
+            max_transactions: 10,
+            ..Default::default()
+        })
+        .with_finalizer(move |stats| *sink.lock().unwrap() = Some(stats.clone()));
+
+        limiter.process_transaction(&traced()).unwrap();
+        assert!(finalized.lock().unwrap().is_none());
+
+        drop(limiter);
+        let stats = finalized
+            .lock()
+            .unwrap()
+            .take()
+            .expect("finalizer did not run");
+        assert_eq!(stats.transactions_count, 1);
+        assert_eq!(stats.committed_amount, 150);
+    }
+
+    fn summary_levels(log_drop_summary: bool) -> Vec<Level> {
+        let params = EnvParams {
+            log_drop_summary,
+            ..Default::default()
+        };
+        test_log::capture(|| drop(LimitChecker::new(&params)))
+            .into_iter()
+            .filter(|(_, message)| message.starts_with("LimitChecker is being dropped"))
+            .map(|(level, _)| level)
+            .collect()
+    }
+
+    #[test]
+    fn test_drop_summary_suppressed_by_default() {
+        assert_eq!(summary_levels(false), vec![Level::Debug]);
+    }
+
+    #[test]
+    fn test_drop_summary_logged_when_enabled() {
+        assert_eq!(summary_levels(true), vec![Level::Info]);
     }
 }
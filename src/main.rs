@@ -1,92 +1,356 @@
-use limits::{LimitChecker, States};
+use clap::Parser;
 use log::{info, warn};
-use rayon::ThreadPoolBuilder;
 use std::sync::{Arc, Mutex};
-use tx_genertor::TransactionGenerator;
-
-pub mod env_utils;
-mod limits;
-mod tx;
-mod tx_genertor;
+use std::time::Duration;
+use test_bot::backoff::AdaptiveThrottle;
+use test_bot::bidding::Bidding;
+use test_bot::checkpoint::Checkpoint;
+use test_bot::env_utils::{self, Command};
+use test_bot::limits::{LimitChecker, States};
+use test_bot::output::{LogTarget, OutputFormat, OutputStream};
+use test_bot::pool::{ConnectionPool, ExecutionBackend, PooledTransaction, SimBackend};
+use test_bot::runner::{
+    check_min_successes, check_post_conditions, display_results_to_sinks, display_run,
+    report_zero_success, run_targets, run_transaction_process, self_test, sort_results,
+    target_runs, unwrap_results, TargetSummary, EXIT_TOO_FEW_SUCCESSES,
+};
+use test_bot::summary::{compare_summaries, RunSummary};
+use test_bot::tx::SomeTransaction;
+use test_bot::tx_genertor::{CachedGenerator, TransactionGenerator};
 
 /// Initializes and starts the bot for processing transactions.
+///
+/// The commands and the parameters given on the command line are described by `Args`.
 fn main() {
-    env_logger::Builder::from_default_env()
-        .filter_level(log::LevelFilter::Info)
-        .init();
+    let args = env_utils::Args::parse();
+    if args.self_test {
+        match self_test() {
+            Ok(report) => println!("Self-test passed: {}", report),
+            Err(failures) => {
+                println!("Self-test failed:");
+                for failure in failures {
+                    println!("  {}", failure);
+                }
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if let Some(Command::Diff { a, b }) = &args.command {
+        diff_summaries(a, b);
+        return;
+    }
+    let estimate_only = args.command == Some(Command::Estimate);
+
+    let mut params = env_utils::EnvParams::read_with_args(&args).unwrap_or_else(|err| {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    });
+    if let Some(path) = params.checkpoint_file.clone() {
+        match Checkpoint::load(&path) {
+            Ok(Some(checkpoint)) => checkpoint.apply(&mut params),
+            Ok(None) => {}
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Err(err) = params.validate() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+    let params = Arc::new(params);
+
+    // Load the replayed transactions up front, so a bad file fails before the run starts.
+    let replay = params.replay_path.as_ref().map(|path| {
+        CachedGenerator::load(path, &params).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+    });
 
-    let params = Arc::new(env_utils::EnvParams::read_env());
+    if estimate_only {
+        println!("Network: {}", params.network);
+        println!("{}", params.estimate());
+        return;
+    }
+
+    let mut logger = env_logger::Builder::from_default_env();
+    logger.filter_level(log::LevelFilter::Info);
+    logger.target(match params.log_target {
+        LogTarget::Stderr => env_logger::Target::Stderr,
+        LogTarget::Stdout => env_logger::Target::Stdout,
+    });
+    if params.trace_tx {
+        logger.filter_module("test_bot", log::LevelFilter::Trace);
+    }
+    logger.init();
+
+    warn!(
+        "==================== NETWORK: {} ====================",
+        params.network
+    );
     info!("Starting bot with parameters: {:?}", &params);
+    if params.dry_run {
+        warn!("Dry run: the transactions are checked against the limits but not executed.");
+    }
+    if let Some(target) = params.target_success_rate {
+        info!(
+            "Break-even commission for a {:.1}% success rate: {}",
+            target * 100.0,
+            params.break_even_commission(target)
+        );
+    }
+
+    if !params.targets.is_empty() {
+        run_all_targets(&params);
+        return;
+    }
 
     let limiter = LimitChecker::new(&params);
-    let generator = TransactionGenerator::new(params.clone());
+    #[cfg(feature = "otel")]
+    let tracer_provider = params.otel_endpoint.as_deref().map(|endpoint| {
+        test_bot::telemetry::Telemetry::otlp_provider(endpoint).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+    });
+    #[cfg(feature = "otel")]
+    let limiter = match &tracer_provider {
+        Some(provider) => limiter.with_telemetry(test_bot::telemetry::Telemetry::new(provider)),
+        None => limiter,
+    };
+    #[cfg(feature = "sqlite")]
+    let recorder = params.db_path.as_deref().map(|path| {
+        Arc::new(
+            test_bot::sqlite::SqliteRecorder::open(
+                path,
+                params.flush_batch_size,
+                Duration::from_millis(params.flush_interval_ms),
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }),
+        )
+    });
+    #[cfg(feature = "sqlite")]
+    let limiter = match &recorder {
+        Some(recorder) => limiter.with_recorder(recorder.clone()),
+        None => limiter,
+    };
+    let limiter = Arc::new(limiter);
+    #[cfg(unix)]
+    if let Err(err) = test_bot::signals::install_snapshot_handler(&limiter) {
+        warn!("Failed to install the SIGUSR1 snapshot handler: {}", err);
+    }
+    if let Err(err) = test_bot::signals::install_shutdown_handler(&limiter) {
+        warn!("Failed to install the shutdown handler: {}", err);
+    }
     let results = Arc::new(Mutex::new(Vec::new()));
+    // Created once, its warm connections are shared by all the workers.
+    let pool = (params.backend == ExecutionBackend::Pool).then(|| {
+        let pool = Arc::new(ConnectionPool::new(SimBackend, params.max_threads));
+        pool.warm(params.max_threads).unwrap_or_else(|err| {
+            eprintln!("Failed to open the backend connections: {}", err);
+            std::process::exit(1);
+        });
+        pool
+    });
+
+    let summary = match replay {
+        Some(generator) => run_generated(&params, generator, pool, limiter.clone(), &results),
+        None => {
+            let mut generator = TransactionGenerator::new(params.clone());
+            generator.completed = Some(limiter.completed.clone());
+            generator.backoff =
+                AdaptiveThrottle::from_params(&params, limiter.recent_outcomes.as_ref());
+            generator.bidding = params.bidding.strategy().map(|strategy| {
+                let window = Duration::from_secs(params.bidding_window_secs);
+                Bidding::new(strategy, limiter.clone(), window)
+            });
+            run_generated(&params, generator, pool, limiter.clone(), &results)
+        }
+    };
+    let stats = limiter.stats();
+    info!("Run stats: {:?}", stats);
+    report_zero_success(&params, &stats);
+
+    if let Some(path) = &params.report_path {
+        match summary
+            .clone()
+            .with_config(&params)
+            .save(path, params.report_compress)
+        {
+            Ok(()) => info!("Run summary written to {}", path),
+            Err(err) => warn!("Failed to write run summary to {}: {}", path, err),
+        }
+    }
 
-    run_transaction_process(params.max_threads, generator, limiter.into(), &results);
+    if let Some(path) = &params.histogram_path {
+        match limiter.metrics.latency_histogram().save(path) {
+            Ok(()) => info!("Latency histogram written to {}", path),
+            Err(err) => warn!("Failed to write latency histogram to {}: {}", path, err),
+        }
+    }
+
+    // Retrieve, order and display the results
+    let mut final_results = unwrap_results(results);
+    sort_results(&mut final_results, params.result_sort);
+    let sinks = params.output_sinks();
+    // Keep stdout parseable when a machine-readable format is written there.
+    let machine_stdout = sinks
+        .iter()
+        .any(|sink| sink.stream == OutputStream::Stdout && sink.format != OutputFormat::Text);
+    if machine_stdout {
+        eprintln!("Network: {}", params.network);
+    } else {
+        println!("Network: {}", params.network);
+    }
+    display_run(&final_results, &summary, &sinks);
+    if machine_stdout {
+        eprintln!("{}", summary);
+    } else {
+        println!("{}", summary);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(recorder) = recorder {
+        match recorder.close() {
+            Ok(attempts) => info!("Recorded {} attempts to the database.", attempts),
+            Err(err) => warn!("Failed to record the attempts: {}", err),
+        }
+    }
 
-    // Retrieve and display the results
-    let final_results = unwrap_results(results);
-    display_results(final_results);
+    #[cfg(feature = "otel")]
+    if let Some(provider) = tracer_provider {
+        if let Err(err) = provider.shutdown() {
+            warn!("Failed to flush the transaction spans: {}", err);
+        }
+    }
+
+    if let Err(violations) = check_post_conditions(&params, &stats) {
+        for violation in violations {
+            eprintln!("Assertion failed: {}", violation);
+        }
+        std::process::exit(1);
+    }
+
+    if let Err(shortfall) = check_min_successes(&params, &stats) {
+        eprintln!("{}", shortfall);
+        std::process::exit(EXIT_TOO_FEW_SUCCESSES);
+    }
 }
 
-/// Runs the multi-threaded transaction processing.
+/// Runs the transactions of `generator`, sent over `pool` when the `pool` backend created one.
 ///
 /// # Arguments
-/// * `max_threads` - The maximum number of threads.
-/// * `generator` - The transaction generator.
-/// * `limiter` - The limit checker.
-/// * `results` - Arc wrapper around Mutex for collecting results.
-fn run_transaction_process(
-    max_threads: usize,
-    generator: TransactionGenerator,
+/// * `params` - The parameters of the run.
+/// * `generator` - The generated or replayed transactions.
+/// * `pool` - The connection pool of the run, if any.
+/// * `limiter` - The limit checker of the run.
+/// * `results` - The results of the transactions.
+fn run_generated<I>(
+    params: &env_utils::EnvParams,
+    generator: I,
+    pool: Option<Arc<ConnectionPool<SimBackend>>>,
     limiter: Arc<LimitChecker>,
     results: &Arc<Mutex<Vec<States>>>,
-) {
-    let pool = ThreadPoolBuilder::new()
-        .num_threads(max_threads)
-        .build()
-        .expect("Failed to create thread pool");
-
-    pool.install(|| {
-        let local_results: Vec<_> = generator
-            .into_iter()
-            .map_while(|tx| match limiter.process_transaction(&tx) {
-                Ok(state) if state != States::Finish => Some(state),
-                _ => None,
-            })
-            .collect();
-
-        let mut global_results = results.lock().unwrap();
-        global_results.extend(local_results);
-    });
+) -> RunSummary
+where
+    I: Iterator<Item = SomeTransaction> + Send,
+{
+    match pool {
+        Some(pool) => {
+            let generator = generator.map(move |tx| PooledTransaction::new(tx, pool.clone()));
+            run_transaction_process(params.max_threads, generator, limiter, results)
+        }
+        None => run_transaction_process(params.max_threads, generator, limiter, results),
+    }
 }
 
-/// Extracts results from the shared storage and returns them.
+/// Runs the `targets` at once, then displays the results of all of them and the summary
+/// of each, and checks every target against the post-conditions and minimum successes.
 ///
 /// # Arguments
-/// * `results` - Arc wrapper around Mutex for collecting results.
-fn unwrap_results(results: Arc<Mutex<Vec<States>>>) -> Vec<States> {
-    match Arc::try_unwrap(results) {
-        Ok(mutex) => mutex.into_inner().unwrap_or_else(|_| {
-            warn!("Failed to lock mutex, returning empty results");
-            Vec::new()
-        }),
-        Err(_) => {
-            warn!("Arc still has multiple owners, returning empty results");
-            Vec::new()
+/// * `params` - The parameters of the run, with the `targets`.
+fn run_all_targets(params: &env_utils::EnvParams) {
+    let targets = target_runs(params);
+    for run in &targets {
+        #[cfg(unix)]
+        if let Err(err) = test_bot::signals::install_snapshot_handler(&run.limiter) {
+            warn!("Failed to install the SIGUSR1 snapshot handler: {}", err);
+        }
+        if let Err(err) = test_bot::signals::install_shutdown_handler(&run.limiter) {
+            warn!("Failed to install the shutdown handler: {}", err);
+        }
+    }
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let summaries = run_targets(params.max_threads, &targets, &results);
+
+    let mut final_results = unwrap_results(results);
+    sort_results(&mut final_results, params.result_sort);
+    let sinks = params.output_sinks();
+    let machine_stdout = sinks
+        .iter()
+        .any(|sink| sink.stream == OutputStream::Stdout && sink.format != OutputFormat::Text);
+    let report = |line: String| {
+        if machine_stdout {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
         }
+    };
+    report(format!("Network: {}", params.network));
+    display_results_to_sinks(&final_results, &sinks);
+    for TargetSummary {
+        target, summary, ..
+    } in &summaries
+    {
+        report(format!(
+            "{} / {}:\n{}",
+            target.wallet, target.token, summary
+        ));
+    }
+
+    let mut exit_code = 0;
+    for (run, TargetSummary { target, stats, .. }) in targets.iter().zip(&summaries) {
+        let params = run.limiter.params();
+        report_zero_success(params, stats);
+        if let Err(violations) = check_post_conditions(params, stats) {
+            for violation in violations {
+                eprintln!(
+                    "Assertion failed for {} / {}: {}",
+                    target.wallet, target.token, violation
+                );
+            }
+            exit_code = 1;
+        }
+        if let Err(shortfall) = check_min_successes(params, stats) {
+            eprintln!("{} / {}: {}", target.wallet, target.token, shortfall);
+            if exit_code == 0 {
+                exit_code = EXIT_TOO_FEW_SUCCESSES;
+            }
+        }
+    }
+    if exit_code != 0 {
+        std::process::exit(exit_code);
     }
 }
 
-/// Displays the transaction results (signatures) in the console with numbering.
+/// Loads two run summaries and prints the deltas from `a` to `b`.
 ///
 /// # Arguments
-/// * `results` - A vector of `States` containing the transaction states.
-fn display_results(results: Vec<States>) {
-    info!("Transaction Signatures:");
-    for (index, state) in results.into_iter().enumerate() {
-        if let States::InProgres(signature) = state {
-            println!("{}. {}", index + 1, signature);
-        }
-    }
+/// * `a` - Path of the baseline summary.
+/// * `b` - Path of the summary compared against the baseline.
+fn diff_summaries(a: &str, b: &str) {
+    let load = |path: &str| {
+        RunSummary::load(path).unwrap_or_else(|err| {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        })
+    };
+
+    println!("{}", compare_summaries(&load(a), &load(b)));
 }
@@ -0,0 +1,106 @@
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use log::warn;
+
+use crate::histogram::LatencyHistogram;
+use crate::stats::LatencyPercentiles;
+
+/// Shared collection of transaction metrics.
+///
+/// Counters are kept in atomics so that exporters can read them without locking,
+/// and every event is additionally forwarded to StatsD when an endpoint is configured.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    /// Number of successfully executed transactions.
+    pub success: AtomicU64,
+    /// Number of failed transaction executions.
+    pub fail: AtomicU64,
+    /// Sum of execution latencies, in microseconds.
+    pub latency_us: AtomicU64,
+    /// Every recorded latency, in microseconds, used for percentiles.
+    latencies: Mutex<Vec<u64>>,
+    statsd: Option<StatsdClient>,
+}
+
+impl Metrics {
+    /// Creates metrics that are also emitted to the StatsD endpoint at `statsd_addr`, if given.
+    /// A failure to set up the socket is logged and leaves only the in-process counters.
+    pub fn new(statsd_addr: Option<&str>) -> Self {
+        let statsd = statsd_addr.and_then(|addr| {
+            StatsdClient::connect(addr)
+                .map_err(|err| warn!("StatsD disabled, failed to connect to {}: {}", addr, err))
+                .ok()
+        });
+
+        Self {
+            statsd,
+            ..Default::default()
+        }
+    }
+
+    /// Records a successful execution together with its latency.
+    pub fn record_success(&self, latency: Duration) {
+        self.success.fetch_add(1, Ordering::Relaxed);
+        self.record_latency(latency);
+        self.emit("tx.success:1|c");
+    }
+
+    /// Records a failed execution together with its latency.
+    pub fn record_failure(&self, latency: Duration) {
+        self.fail.fetch_add(1, Ordering::Relaxed);
+        self.record_latency(latency);
+        self.emit("tx.fail:1|c");
+    }
+
+    /// Returns the average latency of the recorded executions, zero before the first one.
+    pub fn average_latency(&self) -> Duration {
+        let executions = self.success.load(Ordering::Relaxed) + self.fail.load(Ordering::Relaxed);
+        Duration::from_micros(self.latency_us.load(Ordering::Relaxed) / executions.max(1))
+    }
+
+    /// Returns the percentiles of the recorded latencies.
+    pub fn latency_percentiles(&self) -> LatencyPercentiles {
+        LatencyPercentiles::from_samples(&self.latencies.lock().unwrap())
+    }
+
+    /// Returns the full histogram of the recorded latencies.
+    pub fn latency_histogram(&self) -> LatencyHistogram {
+        LatencyHistogram::from_samples(&self.latencies.lock().unwrap())
+    }
+
+    fn record_latency(&self, latency: Duration) {
+        let micros = latency.as_micros() as u64;
+        self.latency_us.fetch_add(micros, Ordering::Relaxed);
+        self.latencies.lock().unwrap().push(micros);
+        self.emit(&format!("tx.latency:{}|ms", latency.as_millis()));
+    }
+
+    fn emit(&self, packet: &str) {
+        if let Some(statsd) = &self.statsd {
+            statsd.send(packet);
+        }
+    }
+}
+
+/// Minimal fire-and-forget StatsD client over UDP.
+#[derive(Debug)]
+struct StatsdClient {
+    socket: UdpSocket,
+}
+
+impl StatsdClient {
+    fn connect(addr: &str) -> std::io::Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0")?;
+        socket.connect(addr)?;
+        Ok(Self { socket })
+    }
+
+    fn send(&self, packet: &str) {
+        if let Err(err) = self.socket.send(packet.as_bytes()) {
+            warn!("Failed to send StatsD packet: {}", err);
+        }
+    }
+}
@@ -0,0 +1,446 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+use crate::limits::States;
+use crate::summary::RunSummary;
+
+/// Serializes the collected results into an output format.
+pub trait ResultFormatter: Send + Sync {
+    /// Renders `states` in the order given.
+    fn format(&self, states: &[States]) -> Vec<u8>;
+
+    /// Renders `states` along with the `summary` of the run; formats without a place for
+    /// the summary render the states alone.
+    fn format_with_summary(&self, states: &[States], summary: &RunSummary) -> Vec<u8> {
+        let _ = summary;
+        self.format(states)
+    }
+}
+
+/// Output format of the results, selected with `OUTPUT_FORMAT`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    /// Numbered list of signatures.
+    #[default]
+    Text,
+    /// A single JSON array of records.
+    Json,
+    /// One JSON record per line.
+    Ndjson,
+    /// Comma-separated records with a header line.
+    Csv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!("unknown output format: {}", other)),
+        }
+    }
+}
+
+impl OutputFormat {
+    /// Returns the built-in formatter of this format.
+    pub fn formatter(self) -> Box<dyn ResultFormatter> {
+        match self {
+            Self::Text => Box::new(TextFormatter),
+            Self::Json => Box::new(JsonFormatter),
+            Self::Ndjson => Box::new(NdjsonFormatter),
+            Self::Csv => Box::new(CsvFormatter),
+        }
+    }
+}
+
+/// Destination of the formatted results, selected with `OUTPUT_STREAM`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    /// Standard output, apart from the logs.
+    #[default]
+    Stdout,
+    /// Standard error, merged with the logs.
+    Stderr,
+    /// A file, created or truncated.
+    File(String),
+}
+
+impl FromStr for OutputStream {
+    type Err = String;
+
+    /// Parses `stdout`, `stderr`, or any other value as a file path.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stdout" => Ok(Self::Stdout),
+            "stderr" => Ok(Self::Stderr),
+            "" => Err("empty output stream".to_string()),
+            path => Ok(Self::File(path.to_string())),
+        }
+    }
+}
+
+impl OutputStream {
+    /// Opens the stream for writing.
+    pub fn open(&self) -> io::Result<Box<dyn Write>> {
+        Ok(match self {
+            Self::Stdout => Box::new(io::stdout().lock()),
+            Self::Stderr => Box::new(io::stderr().lock()),
+            Self::File(path) => Box::new(File::create(path)?),
+        })
+    }
+}
+
+/// A destination of the results: a format written to a stream, one of the `OUTPUT_SINKS`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct OutputSink {
+    pub format: OutputFormat,
+    pub stream: OutputStream,
+}
+
+impl FromStr for OutputSink {
+    type Err = String;
+
+    /// Parses `format:stream`, e.g. `csv:out/results.csv`, or a bare `format` written to stdout.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, stream) = match s.split_once(':') {
+            Some((format, stream)) => (format, stream.parse()?),
+            None => (s, OutputStream::Stdout),
+        };
+        Ok(Self {
+            format: format.parse()?,
+            stream,
+        })
+    }
+}
+
+/// Stream the logs are written to, selected with `LOG_TARGET`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogTarget {
+    #[default]
+    Stderr,
+    Stdout,
+}
+
+impl FromStr for LogTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stderr" => Ok(Self::Stderr),
+            "stdout" => Ok(Self::Stdout),
+            other => Err(format!("unknown log target: {}", other)),
+        }
+    }
+}
+
+/// A single result as written by the structured formatters.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResultRecord {
+    /// Position of the result, starting at 1.
+    pub index: usize,
+    /// One of `success`, `failed`, `skipped` or `finish`.
+    pub status: &'static str,
+    /// Amount of the transaction, 0 when it was not executed.
+    pub amount: i64,
+    /// Signature, error message or rejection reason.
+    pub message: String,
+}
+
+impl ResultRecord {
+    /// Builds the record of the `index`-th (0-based) result.
+    pub fn new(index: usize, state: &States) -> Self {
+        let (status, amount, message) = match state {
+            States::Success { signature, amount } => ("success", *amount, signature.clone()),
+            States::Failed { error, amount } => ("failed", *amount, error.clone()),
+            States::Skipped(reason) => ("skipped", 0, reason.to_string()),
+            States::Finish => ("finish", 0, String::new()),
+        };
+
+        Self {
+            index: index + 1,
+            status,
+            amount,
+            message,
+        }
+    }
+}
+
+fn records(states: &[States]) -> impl Iterator<Item = ResultRecord> + '_ {
+    states
+        .iter()
+        .enumerate()
+        .map(|(index, state)| ResultRecord::new(index, state))
+}
+
+/// Numbered list of the signatures of the successful transactions.
+pub struct TextFormatter;
+
+impl ResultFormatter for TextFormatter {
+    fn format(&self, states: &[States]) -> Vec<u8> {
+        let mut out = String::new();
+        let signatures = states.iter().filter_map(|state| match state {
+            States::Success { signature, .. } => Some(signature),
+            _ => None,
+        });
+        for (index, signature) in signatures.enumerate() {
+            out.push_str(&format!("{}. {}\n", index + 1, signature));
+        }
+        out.into_bytes()
+    }
+}
+
+/// All results as one JSON array, or along with the summary as one JSON document
+/// `{"results": [...], "summary": {...}}`.
+pub struct JsonFormatter;
+
+/// JSON document of the results and the summary of a run.
+#[derive(Serialize)]
+struct RunDocument<'a> {
+    results: Vec<ResultRecord>,
+    summary: &'a RunSummary,
+}
+
+impl ResultFormatter for JsonFormatter {
+    fn format(&self, states: &[States]) -> Vec<u8> {
+        let records: Vec<_> = records(states).collect();
+        let mut out = serde_json::to_vec(&records).expect("result records serialize to JSON");
+        out.push(b'\n');
+        out
+    }
+
+    fn format_with_summary(&self, states: &[States], summary: &RunSummary) -> Vec<u8> {
+        let document = RunDocument {
+            results: records(states).collect(),
+            summary,
+        };
+        let mut out = serde_json::to_vec(&document).expect("the run document serializes to JSON");
+        out.push(b'\n');
+        out
+    }
+}
+
+/// One JSON record per line.
+pub struct NdjsonFormatter;
+
+impl ResultFormatter for NdjsonFormatter {
+    fn format(&self, states: &[States]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for record in records(states) {
+            serde_json::to_writer(&mut out, &record).expect("result records serialize to JSON");
+            out.push(b'\n');
+        }
+        out
+    }
+}
+
+/// Comma-separated records, `index,status,amount,message`, with a header line.
+pub struct CsvFormatter;
+
+impl CsvFormatter {
+    /// Quotes a field when it contains a separator, a quote or a line break.
+    fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+}
+
+impl ResultFormatter for CsvFormatter {
+    fn format(&self, states: &[States]) -> Vec<u8> {
+        let mut out = String::from("index,status,amount,message\n");
+        for record in records(states) {
+            out.push_str(&format!(
+                "{},{},{},{}\n",
+                record.index,
+                record.status,
+                record.amount,
+                Self::escape(&record.message)
+            ));
+        }
+        out.into_bytes()
+    }
+}
+
+/// Buffers the records written to a sink and hands them out in batches of `batch_size`
+/// (`FLUSH_BATCH_SIZE`), or earlier once the oldest buffered record has waited `interval`
+/// (`FLUSH_INTERVAL_MS`), so the records of a slow run are not held indefinitely.
+#[derive(Debug)]
+pub struct FlushBatcher<T> {
+    buffer: Vec<T>,
+    batch_size: usize,
+    interval: Duration,
+    /// Time the oldest buffered record was pushed at.
+    oldest: Instant,
+    /// Number of batches handed out so far.
+    pub flushes: usize,
+}
+
+impl<T> FlushBatcher<T> {
+    /// Creates a batcher flushing every `batch_size` records (at least one) or `interval`.
+    pub fn new(batch_size: usize, interval: Duration) -> Self {
+        let batch_size = batch_size.max(1);
+        Self {
+            buffer: Vec::with_capacity(batch_size),
+            batch_size,
+            interval,
+            oldest: Instant::now(),
+            flushes: 0,
+        }
+    }
+
+    /// Buffers `record`, returning the batch to write once it is full or due.
+    pub fn push(&mut self, record: T) -> Option<Vec<T>> {
+        if self.buffer.is_empty() {
+            self.oldest = Instant::now();
+        }
+        self.buffer.push(record);
+        if self.buffer.len() >= self.batch_size {
+            return Some(self.flush());
+        }
+        self.flush_due()
+    }
+
+    /// Returns the buffered records if the oldest one has waited `interval`.
+    pub fn flush_due(&mut self) -> Option<Vec<T>> {
+        (!self.buffer.is_empty() && self.time_left().is_zero()).then(|| self.flush())
+    }
+
+    /// Returns the time left before the buffered records are due, a whole `interval`
+    /// while none is buffered.
+    pub fn time_left(&self) -> Duration {
+        if self.buffer.is_empty() {
+            return self.interval;
+        }
+        self.interval.saturating_sub(self.oldest.elapsed())
+    }
+
+    /// Returns every buffered record, e.g. for the final flush at the end of the run.
+    pub fn flush(&mut self) -> Vec<T> {
+        if self.buffer.is_empty() {
+            return Vec::new();
+        }
+        self.flushes += 1;
+        std::mem::replace(&mut self.buffer, Vec::with_capacity(self.batch_size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::Rejection;
+
+    fn results() -> Vec<States> {
+        vec![
+            States::Success {
+                signature: "sig-1".to_string(),
+                amount: 190,
+            },
+            States::Failed {
+                error: "rpc error, retry".to_string(),
+                amount: 200,
+            },
+            States::Skipped(Rejection::Dust),
+            States::Success {
+                signature: "sig-2".to_string(),
+                amount: 190,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_json_formatter() {
+        let out = String::from_utf8(JsonFormatter.format(&results())).unwrap();
+        assert_eq!(
+            out,
+            concat!(
+                r#"[{"index":1,"status":"success","amount":190,"message":"sig-1"},"#,
+                r#"{"index":2,"status":"failed","amount":200,"message":"rpc error, retry"},"#,
+                r#"{"index":3,"status":"skipped","amount":0,"message":"dust amount"},"#,
+                r#"{"index":4,"status":"success","amount":190,"message":"sig-2"}]"#,
+                "\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_csv_formatter() {
+        let out = String::from_utf8(CsvFormatter.format(&results())).unwrap();
+        assert_eq!(
+            out,
+            "index,status,amount,message\n\
+             1,success,190,sig-1\n\
+             2,failed,200,\"rpc error, retry\"\n\
+             3,skipped,0,dust amount\n\
+             4,success,190,sig-2\n"
+        );
+    }
+
+    #[test]
+    fn test_output_stream_selection() {
+        assert_eq!("stdout".parse(), Ok(OutputStream::Stdout));
+        assert_eq!("stderr".parse(), Ok(OutputStream::Stderr));
+        assert_eq!(
+            "out/results.csv".parse(),
+            Ok(OutputStream::File("out/results.csv".to_string()))
+        );
+        assert_eq!("stdout".parse(), Ok(LogTarget::Stdout));
+        assert_eq!(
+            "csv:out/results.csv".parse(),
+            Ok(OutputSink {
+                format: OutputFormat::Csv,
+                stream: OutputStream::File("out/results.csv".to_string()),
+            })
+        );
+        assert_eq!(
+            "json".parse(),
+            Ok(OutputSink {
+                format: OutputFormat::Json,
+                stream: OutputStream::Stdout,
+            })
+        );
+        assert!("xml:stdout".parse::<OutputSink>().is_err());
+    }
+
+    #[test]
+    fn test_text_formatter_and_format_selection() {
+        let out = String::from_utf8(TextFormatter.format(&results())).unwrap();
+        // Only the successes are listed, numbered among themselves.
+        assert_eq!(out, "1. sig-1\n2. sig-2\n");
+
+        assert_eq!("ndjson".parse(), Ok(OutputFormat::Ndjson));
+        assert!("xml".parse::<OutputFormat>().is_err());
+        let lines = OutputFormat::Ndjson.formatter().format(&results());
+        assert_eq!(String::from_utf8(lines).unwrap().lines().count(), 4);
+    }
+
+    #[test]
+    fn test_batcher_flushes_full_and_due_batches_without_losing_records() {
+        let mut batcher = FlushBatcher::new(3, Duration::from_millis(50));
+        let mut written: Vec<Vec<u32>> =
+            (1..=7).filter_map(|record| batcher.push(record)).collect();
+        assert_eq!(written, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(batcher.flush_due(), None);
+
+        std::thread::sleep(Duration::from_millis(60));
+        written.extend(batcher.flush_due());
+        assert!(batcher.push(8).is_none());
+        written.push(batcher.flush());
+
+        assert_eq!(written.concat(), (1..=8).collect::<Vec<_>>());
+        assert_eq!(batcher.flushes, 4);
+        assert!(batcher.flush().is_empty());
+    }
+}
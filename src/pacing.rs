@@ -0,0 +1,41 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Paces the submissions to at most `max_tps` per second across all threads (`MAX_TPS`).
+///
+/// Every caller reserves the next free slot under the lock and sleeps outside of it until
+/// the slot is due, so the waiting threads neither hold the lock nor poll.
+#[derive(Debug)]
+pub struct TpsLimiter {
+    interval: Duration,
+    /// Time the next submission is allowed at, `None` before the first one.
+    next: Mutex<Option<Instant>>,
+}
+
+impl TpsLimiter {
+    /// Creates a limiter spacing the submissions `1 / max_tps` seconds apart, `None` when
+    /// `max_tps` is not positive or too small for the interval to be represented.
+    pub fn new(max_tps: f64) -> Option<Self> {
+        Duration::try_from_secs_f64(1.0 / max_tps)
+            .ok()
+            .map(|interval| Self {
+                interval,
+                next: Mutex::new(None),
+            })
+    }
+
+    /// Blocks until the next submission is allowed.
+    pub fn wait(&self) {
+        let due = {
+            let now = Instant::now();
+            let mut next = self.next.lock().unwrap();
+            let due = next.map_or(now, |next| next.max(now));
+            *next = Some(due + self.interval);
+            due
+        };
+        if let Some(wait) = due.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+}
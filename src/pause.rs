@@ -0,0 +1,184 @@
+use std::fmt;
+use std::io::BufRead;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use log::info;
+
+/// Pauses the run after every `pause_every_n` successful transactions until the operator resumes it,
+/// e.g. to inspect the on-chain state between batches.
+///
+/// The thread completing the n-th success blocks in `resume`; the other threads wait in `wait`
+/// before their next execution until the pause is over.
+pub struct PauseGate {
+    every: usize,
+    successes: AtomicUsize,
+    paused: Mutex<bool>,
+    resumed: Condvar,
+    resume: Box<dyn Fn() + Send + Sync>,
+}
+
+impl fmt::Debug for PauseGate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PauseGate")
+            .field("every", &self.every)
+            .field("successes", &self.successes)
+            .finish()
+    }
+}
+
+impl PauseGate {
+    /// Creates a gate pausing after every `every` successes (at least one) until `resume` returns.
+    pub fn new(every: usize, resume: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            every: every.max(1),
+            successes: AtomicUsize::new(0),
+            paused: Mutex::new(false),
+            resumed: Condvar::new(),
+            resume: Box::new(resume),
+        }
+    }
+
+    /// Returns a continuation waiting until `path` is touched (`PAUSE_RESUME_FILE`),
+    /// removing the file so that the next pause waits for a new touch. It also returns once
+    /// `completed` is set, e.g. by an abort, since no transaction is left to resume.
+    pub fn resume_on_file(
+        path: impl Into<PathBuf>,
+        completed: Arc<AtomicBool>,
+    ) -> impl Fn() + Send + Sync {
+        let path = path.into();
+        move || {
+            info!("Touch {} to resume.", path.display());
+            while !path.exists() {
+                if completed.load(Ordering::SeqCst) {
+                    info!("Run completed during the pause.");
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(200));
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+    /// Returns a continuation waiting for the Enter key on stdin.
+    pub fn resume_on_enter() -> impl Fn() + Send + Sync {
+        || {
+            info!("Press Enter to resume.");
+            let _ = std::io::stdin().lock().read_line(&mut String::new());
+        }
+    }
+
+    /// Returns whether a pause is in progress.
+    pub fn is_paused(&self) -> bool {
+        *self.paused.lock().unwrap()
+    }
+
+    /// Blocks while a pause is in progress.
+    pub fn wait(&self) {
+        let mut paused = self.paused.lock().unwrap();
+        while *paused {
+            paused = self.resumed.wait(paused).unwrap();
+        }
+    }
+
+    /// Counts a success, pausing the calling thread until resumed when it is the n-th one.
+    pub fn on_success(&self) {
+        let successes = self.successes.fetch_add(1, Ordering::SeqCst) + 1;
+        if !successes.is_multiple_of(self.every) {
+            return;
+        }
+
+        *self.paused.lock().unwrap() = true;
+        info!("Paused after {} successful transactions.", successes);
+        (self.resume)();
+        *self.paused.lock().unwrap() = false;
+        self.resumed.notify_all();
+        info!("Resumed.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::limits::LimitChecker;
+    use crate::test_tx::StubTransaction;
+
+    #[test]
+    fn test_pauses_at_every_nth_success() {
+        let pauses = Arc::new(AtomicUsize::new(0));
+        let gate = {
+            let pauses = pauses.clone();
+            PauseGate::new(3, move || {
+                pauses.fetch_add(1, Ordering::SeqCst);
+            })
+        };
+        let limiter = LimitChecker::new(&EnvParams {
+            total_amount: 10_000,
+            max_transactions: 100,
+            ..Default::default()
+        })
+        .with_pause_gate(gate);
+
+        let mut pauses_after = Vec::new();
+        for _ in 0..7 {
+            limiter
+                .process_transaction(&StubTransaction::new())
+                .unwrap();
+            pauses_after.push(pauses.load(Ordering::SeqCst));
+        }
+        assert_eq!(pauses_after, vec![0, 0, 1, 1, 1, 2, 2]);
+    }
+
+    #[test]
+    fn test_other_threads_wait_for_the_pause() {
+        let (resume, resumed) = mpsc::channel::<()>();
+        let resumed = Mutex::new(resumed);
+        let gate = Arc::new(PauseGate::new(1, move || {
+            resumed.lock().unwrap().recv().unwrap();
+        }));
+
+        let pausing = {
+            let gate = gate.clone();
+            std::thread::spawn(move || gate.on_success())
+        };
+        while !gate.is_paused() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        let passed = Arc::new(AtomicBool::new(false));
+        let waiting = {
+            let (gate, passed) = (gate.clone(), passed.clone());
+            std::thread::spawn(move || {
+                gate.wait();
+                passed.store(true, Ordering::SeqCst);
+            })
+        };
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!passed.load(Ordering::SeqCst));
+        resume.send(()).unwrap();
+        pausing.join().unwrap();
+        waiting.join().unwrap();
+        assert!(passed.load(Ordering::SeqCst));
+        assert!(!gate.is_paused());
+    }
+
+    #[test]
+    fn test_file_pause_ends_with_the_run() {
+        let path = std::env::temp_dir().join(format!("resume-{}", std::process::id()));
+        let completed = Arc::new(AtomicBool::new(false));
+        let resume = PauseGate::resume_on_file(path.clone(), completed.clone());
+
+        let pausing = std::thread::spawn(resume);
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!pausing.is_finished());
+        completed.store(true, Ordering::SeqCst);
+
+        pausing.join().unwrap();
+        assert!(!path.exists());
+    }
+}
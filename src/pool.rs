@@ -0,0 +1,317 @@
+use std::fmt::{self, Debug};
+use std::ops::{Deref, DerefMut};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+
+use crate::tx::{SomeTransaction, Transaction};
+
+/// A real backend the transactions are sent to, e.g. an RPC node.
+pub trait Backend: Send + Sync {
+    /// An open connection to the backend.
+    type Connection: Send;
+
+    /// Opens a new connection.
+    fn connect(&self) -> Result<Self::Connection, String>;
+
+    /// Sends `tx` over `connection`, returning its signature.
+    fn submit(
+        &self,
+        connection: &mut Self::Connection,
+        tx: &SomeTransaction,
+    ) -> Result<String, String>;
+}
+
+/// Path the transactions are executed through, selected with `BACKEND`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionBackend {
+    /// The simulated `SomeTransaction::execute`.
+    #[default]
+    Sim,
+    /// A `PooledTransaction` sent over a `ConnectionPool` to the `SimBackend`.
+    Pool,
+}
+
+impl FromStr for ExecutionBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sim" => Ok(Self::Sim),
+            "pool" => Ok(Self::Pool),
+            other => Err(format!("unknown backend: {}", other)),
+        }
+    }
+}
+
+/// Backend standing in for the RPC client until one is integrated: its connections carry no
+/// state and a submission runs the simulated execution, so `BACKEND=pool` exercises the
+/// pooled path of the real backends.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SimBackend;
+
+impl Backend for SimBackend {
+    type Connection = ();
+
+    fn connect(&self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn submit(&self, _connection: &mut (), tx: &SomeTransaction) -> Result<String, String> {
+        tx.execute()
+    }
+}
+
+/// Pool of warm connections to a `Backend`, created once and shared by all workers through an `Arc`.
+///
+/// Connections are opened on demand and returned to the pool after each use, keeping at most
+/// `max_idle` of them; a connection whose submission failed is dropped so that a broken
+/// connection is not handed out again.
+pub struct ConnectionPool<B: Backend> {
+    backend: B,
+    idle: Mutex<Vec<B::Connection>>,
+    max_idle: usize,
+    /// Number of connections opened so far.
+    pub opened: AtomicUsize,
+}
+
+impl<B: Backend> Debug for ConnectionPool<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionPool")
+            .field("max_idle", &self.max_idle)
+            .field("opened", &self.opened)
+            .finish()
+    }
+}
+
+impl<B: Backend> ConnectionPool<B> {
+    /// Creates a pool of up to `max_idle` connections (at least one) to `backend`.
+    pub fn new(backend: B, max_idle: usize) -> Self {
+        Self {
+            backend,
+            idle: Mutex::new(Vec::new()),
+            max_idle: max_idle.max(1),
+            opened: AtomicUsize::new(0),
+        }
+    }
+
+    /// Opens `count` connections up front (capped at `max_idle`), so the first transactions do not pay for them.
+    pub fn warm(&self, count: usize) -> Result<(), String> {
+        for _ in 0..count.min(self.max_idle) {
+            let connection = self.open()?;
+            self.idle.lock().unwrap().push(connection);
+        }
+        Ok(())
+    }
+
+    /// Takes an idle connection, or opens one if none is available.
+    pub fn get(&self) -> Result<PooledConnection<'_, B>, String> {
+        let idle = self.idle.lock().unwrap().pop();
+        let connection = match idle {
+            Some(connection) => connection,
+            None => self.open()?,
+        };
+        Ok(PooledConnection {
+            pool: self,
+            connection: Some(connection),
+        })
+    }
+
+    /// Sends `tx` over a pooled connection.
+    pub fn submit(&self, tx: &SomeTransaction) -> Result<String, String> {
+        let mut connection = self.get()?;
+        let result = self.backend.submit(&mut connection, tx);
+        if result.is_err() {
+            connection.discard();
+        }
+        result
+    }
+
+    fn open(&self) -> Result<B::Connection, String> {
+        let connection = self.backend.connect()?;
+        self.opened.fetch_add(1, Ordering::SeqCst);
+        Ok(connection)
+    }
+
+    fn release(&self, connection: B::Connection) {
+        let mut idle = self.idle.lock().unwrap();
+        if idle.len() < self.max_idle {
+            idle.push(connection);
+        }
+    }
+}
+
+/// A connection taken from a `ConnectionPool`, returned to it on drop.
+pub struct PooledConnection<'a, B: Backend> {
+    pool: &'a ConnectionPool<B>,
+    connection: Option<B::Connection>,
+}
+
+impl<B: Backend> PooledConnection<'_, B> {
+    /// Drops the connection instead of returning it to the pool.
+    pub fn discard(mut self) {
+        self.connection = None;
+    }
+}
+
+impl<B: Backend> Deref for PooledConnection<'_, B> {
+    type Target = B::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        self.connection
+            .as_ref()
+            .expect("connection is present until dropped")
+    }
+}
+
+impl<B: Backend> DerefMut for PooledConnection<'_, B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.connection
+            .as_mut()
+            .expect("connection is present until dropped")
+    }
+}
+
+impl<B: Backend> Drop for PooledConnection<'_, B> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.release(connection);
+        }
+    }
+}
+
+/// A generated transaction executed against a real backend through a shared `ConnectionPool`,
+/// instead of the simulated `SomeTransaction::execute`.
+pub struct PooledTransaction<B: Backend> {
+    pub tx: SomeTransaction,
+    pub pool: Arc<ConnectionPool<B>>,
+}
+
+impl<B: Backend> PooledTransaction<B> {
+    /// Wraps `tx` to be sent over `pool`.
+    pub fn new(tx: SomeTransaction, pool: Arc<ConnectionPool<B>>) -> Self {
+        Self { tx, pool }
+    }
+}
+
+impl<B: Backend> Debug for PooledTransaction<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledTransaction")
+            .field("tx", &self.tx)
+            .finish()
+    }
+}
+
+impl<B: Backend + 'static> Transaction for PooledTransaction<B> {
+    fn amount(&self) -> i64 {
+        self.tx.amount()
+    }
+
+    fn price(&self) -> i64 {
+        self.tx.price()
+    }
+
+    fn commission(&self) -> i64 {
+        self.tx.commission()
+    }
+
+    fn execute(&self) -> Result<String, String> {
+        self.pool.submit(&self.tx)
+    }
+
+    fn info(&self) -> String {
+        self.tx.info()
+    }
+
+    fn id(&self) -> u64 {
+        self.tx.id()
+    }
+
+    fn token(&self) -> &str {
+        self.tx.token()
+    }
+
+    fn depends_on(&self) -> Option<u64> {
+        self.tx.depends_on()
+    }
+
+    fn tokens(&self) -> i64 {
+        self.tx.tokens()
+    }
+
+    fn wallet(&self) -> &str {
+        self.tx.wallet()
+    }
+
+    /// Detaches a copy sending over the same pool.
+    fn detach(&self) -> Option<Box<dyn Transaction + Send>> {
+        Some(Box::new(Self::new(self.tx.clone(), self.pool.clone())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::limits::LimitChecker;
+    use crate::runner::{run_transaction_process, unwrap_results};
+    use crate::tx_genertor::TransactionGenerator;
+
+    /// Backend whose connections are numbered in opening order.
+    #[derive(Default)]
+    struct MockBackend {
+        connects: AtomicUsize,
+        submissions: Mutex<Vec<usize>>,
+    }
+
+    impl Backend for MockBackend {
+        type Connection = usize;
+
+        fn connect(&self) -> Result<usize, String> {
+            Ok(self.connects.fetch_add(1, Ordering::SeqCst))
+        }
+
+        fn submit(&self, connection: &mut usize, tx: &SomeTransaction) -> Result<String, String> {
+            self.submissions.lock().unwrap().push(*connection);
+            Ok(format!("sig-{}", tx.id()))
+        }
+    }
+
+    #[test]
+    fn test_backend_selection() {
+        assert_eq!("sim".parse(), Ok(ExecutionBackend::Sim));
+        assert_eq!("pool".parse(), Ok(ExecutionBackend::Pool));
+        assert!("rpc".parse::<ExecutionBackend>().is_err());
+    }
+
+    #[test]
+    fn test_connections_are_reused_across_transactions() {
+        let params = Arc::new(EnvParams {
+            total_amount: 100_000,
+            max_transactions: 20,
+            price: 100,
+            commission: 10,
+            max_threads: 1,
+            ..Default::default()
+        });
+        let pool = Arc::new(ConnectionPool::new(MockBackend::default(), 2));
+        pool.warm(2).unwrap();
+
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = TransactionGenerator::new(params.clone())
+            .map(|tx| PooledTransaction::new(tx, pool.clone()));
+        run_transaction_process(1, generator, limiter.clone(), &results);
+
+        assert_eq!(unwrap_results(results).len(), 20);
+        assert_eq!(limiter.stats().successes, 20);
+        // Every transaction ran over one of the two warm connections.
+        assert_eq!(pool.opened.load(Ordering::SeqCst), 2);
+        let submissions = pool.backend.submissions.lock().unwrap();
+        assert_eq!(submissions.len(), 20);
+        assert!(submissions.iter().all(|connection| *connection < 2));
+    }
+}
@@ -0,0 +1,1011 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{error, info, warn};
+use rayon::iter::{ParallelBridge, ParallelIterator};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use serde::Serialize;
+
+use crate::env_utils::{EnvParams, Target};
+use crate::exporter::MetricsServer;
+use crate::limits::{LimitChecker, ProcessError, States};
+use crate::output::{OutputSink, OutputStream, ResultFormatter};
+use crate::stats::RunStats;
+use crate::summary::RunSummary;
+use crate::tx::Transaction;
+use crate::tx_genertor::TransactionGenerator;
+use crate::watchdog::Watchdog;
+
+/// Order in which the collected results are displayed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultSort {
+    /// Keep the generation order.
+    #[default]
+    None,
+    /// Sort by transaction amount, smallest first.
+    AmountAsc,
+    /// Sort by transaction amount, largest first.
+    AmountDesc,
+}
+
+impl FromStr for ResultSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Self::None),
+            "amount_asc" => Ok(Self::AmountAsc),
+            "amount_desc" => Ok(Self::AmountDesc),
+            other => Err(format!("unknown result sort: {}", other)),
+        }
+    }
+}
+
+/// What the run does with a transaction that fails to be processed, e.g. a malformed one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnError {
+    /// Stop the run, as when the limits are reached.
+    #[default]
+    Stop,
+    /// Skip the transaction and keep going.
+    Continue,
+}
+
+impl FromStr for OnError {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stop" => Ok(Self::Stop),
+            "continue" => Ok(Self::Continue),
+            other => Err(format!("unknown error mode: {}", other)),
+        }
+    }
+}
+
+/// Runs the multi-threaded transaction processing.
+///
+/// When `deferred_retry_limit` is set, failed transactions are not reported right away.
+/// They are queued and re-processed through the limiter once the main stream has ended,
+/// for up to `deferred_retry_limit` passes; only the final outcome is collected.
+///
+/// In `deterministic` mode the transactions are processed on a single thread in generation
+/// order whatever `max_threads` is, so identical inputs give identical results.
+///
+/// When `min_duration_secs` is set, a run finishing earlier idles until the minimum
+/// duration has elapsed before returning, to keep a steady synthetic load.
+///
+/// When `stall_timeout_secs` is set, a `Watchdog` reports the run once no transaction
+/// has succeeded for that long.
+///
+/// A transaction that fails to be processed stops the run, unless `on_error` is `continue`,
+/// in which case it is skipped and left out of the results.
+///
+/// # Arguments
+/// * `max_threads` - The maximum number of threads.
+/// * `generator` - The transaction generator.
+/// * `limiter` - The limit checker.
+/// * `results` - Arc wrapper around Mutex for collecting results.
+///
+/// # Returns
+///
+/// The summary of the run, from the final counters of the limiter.
+pub fn run_transaction_process<I, T>(
+    max_threads: usize,
+    generator: I,
+    limiter: Arc<LimitChecker>,
+    results: &Arc<Mutex<Vec<States>>>,
+) -> RunSummary
+where
+    I: IntoIterator<Item = T> + Send,
+    I::IntoIter: Send,
+    T: Transaction + Debug + Send,
+{
+    let started = Instant::now();
+    let watchdog = spawn_watchdog(&limiter);
+    let metrics_server = spawn_metrics_server(&limiter);
+    build_pool(worker_threads(max_threads, limiter.params())).install(|| {
+        let mut sink = ChunkedSink::new(results, limiter.params().result_chunk_size);
+        process_stream(&limiter, generator, |state| {
+            sink.push(state);
+            true
+        });
+        sink.flush();
+    });
+    drop(watchdog);
+    drop(metrics_server);
+    idle_until_min_duration(limiter.params(), started);
+
+    RunSummary::new(limiter.params().network, &limiter.stats())
+}
+
+/// One target of a multi-target run, with the limiter keeping it to its own budget.
+#[derive(Debug)]
+pub struct TargetRun {
+    pub target: Target,
+    pub limiter: Arc<LimitChecker>,
+}
+
+/// Returns the runs of the `targets` of `params`, each with its own `LimitChecker` built
+/// from `EnvParams::for_target`.
+pub fn target_runs(params: &EnvParams) -> Vec<TargetRun> {
+    params
+        .targets
+        .iter()
+        .map(|target| TargetRun {
+            target: target.clone(),
+            limiter: Arc::new(LimitChecker::new(&params.for_target(target))),
+        })
+        .collect()
+}
+
+/// Outcome of one target of a multi-target run.
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetSummary {
+    pub target: Target,
+    pub summary: RunSummary,
+    /// Final counters of the target, checked against its own parameters.
+    #[serde(skip)]
+    pub stats: RunStats,
+}
+
+/// Runs every target of `targets` at once on a single pool of `max_threads` threads.
+///
+/// Each target is processed by its own `TransactionGenerator` against its own limiter, so it
+/// stops at its own budget whatever the others spend. As in `run_transaction_process`, each
+/// target is monitored by its own `Watchdog` when `stall_timeout_secs` is set, and the run
+/// idles until `min_duration_secs` when it finishes earlier.
+///
+/// # Arguments
+/// * `max_threads` - The maximum number of threads, shared by the targets.
+/// * `targets` - The runs of the targets, see `target_runs`.
+/// * `results` - Arc wrapper around Mutex collecting the results of every target.
+///
+/// # Returns
+///
+/// The summary of every target, in the order of `targets`.
+pub fn run_targets(
+    max_threads: usize,
+    targets: &[TargetRun],
+    results: &Arc<Mutex<Vec<States>>>,
+) -> Vec<TargetSummary> {
+    let Some(first) = targets.first() else {
+        return Vec::new();
+    };
+    let started = Instant::now();
+    let watchdogs: Vec<_> = targets
+        .iter()
+        .map(|run| spawn_watchdog(&run.limiter))
+        .collect();
+
+    build_pool(worker_threads(max_threads, first.limiter.params())).install(|| {
+        rayon::scope(|scope| {
+            for TargetRun { limiter, .. } in targets {
+                let mut generator = TransactionGenerator::new(Arc::new(limiter.params().clone()));
+                generator.completed = Some(limiter.completed.clone());
+                scope.spawn(move |_| {
+                    let mut sink = ChunkedSink::new(results, limiter.params().result_chunk_size);
+                    process_stream(limiter, generator, |state| {
+                        sink.push(state);
+                        true
+                    });
+                    sink.flush();
+                });
+            }
+        });
+    });
+    drop(watchdogs);
+    idle_until_min_duration(first.limiter.params(), started);
+
+    targets
+        .iter()
+        .map(|TargetRun { target, limiter }| {
+            let stats = limiter.stats();
+            TargetSummary {
+                target: target.clone(),
+                summary: RunSummary::new(limiter.params().network, &stats),
+                stats,
+            }
+        })
+        .collect()
+}
+
+/// Idles until `min_duration_secs` have elapsed since `started`, when set.
+fn idle_until_min_duration(params: &EnvParams, started: Instant) {
+    if let Some(min_secs) = params.min_duration_secs {
+        let remaining = Duration::from_secs(min_secs).saturating_sub(started.elapsed());
+        if !remaining.is_zero() {
+            info!(
+                "Run finished early, idling {:?} to reach the minimum duration.",
+                remaining
+            );
+            thread::sleep(remaining);
+        }
+    }
+}
+
+/// Runs the transaction processing in the background, returning a handle able to cancel it
+/// from any thread and to wait for its results, e.g. when the bot is embedded as a library.
+///
+/// # Arguments
+/// * `max_threads` - The maximum number of threads.
+/// * `generator` - The transaction generator.
+/// * `limiter` - The limit checker.
+pub fn spawn_transaction_process<I, T>(
+    max_threads: usize,
+    generator: I,
+    limiter: Arc<LimitChecker>,
+) -> RunHandle
+where
+    I: IntoIterator<Item = T> + Send + 'static,
+    I::IntoIter: Send,
+    T: Transaction + Debug + Send,
+{
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let thread = {
+        let (limiter, results) = (limiter.clone(), results.clone());
+        thread::spawn(move || {
+            run_transaction_process(max_threads, generator, limiter, &results);
+        })
+    };
+
+    RunHandle {
+        limiter,
+        results,
+        thread,
+    }
+}
+
+/// Handle of a run started with `spawn_transaction_process`.
+#[derive(Debug)]
+pub struct RunHandle {
+    limiter: Arc<LimitChecker>,
+    results: Arc<Mutex<Vec<States>>>,
+    thread: thread::JoinHandle<()>,
+}
+
+impl RunHandle {
+    /// Stops the run before the next transaction is admitted; the executing ones complete.
+    pub fn cancel(&self) {
+        info!("Run cancelled.");
+        self.limiter.abort();
+    }
+
+    /// Returns whether the run is over.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Waits for the end of the run, returning its results and final statistics.
+    pub fn join(self) -> (Vec<States>, RunStats) {
+        if self.thread.join().is_err() {
+            error!("The run panicked, returning the results collected so far");
+        }
+        let results =
+            std::mem::take(&mut *self.results.lock().unwrap_or_else(|err| err.into_inner()));
+        (results, self.limiter.stats())
+    }
+}
+
+/// Buffers results locally and moves them into the shared storage in chunks,
+/// so peak memory is bounded by the chunk size instead of the run length.
+pub struct ChunkedSink<'a> {
+    results: &'a Mutex<Vec<States>>,
+    buffer: Vec<States>,
+    chunk_size: usize,
+    /// Number of chunks moved into the shared storage so far.
+    pub flushes: usize,
+}
+
+impl<'a> ChunkedSink<'a> {
+    /// Creates a sink flushing every `chunk_size` results (at least one).
+    pub fn new(results: &'a Mutex<Vec<States>>, chunk_size: usize) -> Self {
+        let chunk_size = chunk_size.max(1);
+        Self {
+            results,
+            buffer: Vec::with_capacity(chunk_size),
+            chunk_size,
+            flushes: 0,
+        }
+    }
+
+    /// Buffers a result, flushing the buffer once it holds a full chunk.
+    pub fn push(&mut self, state: States) {
+        self.buffer.push(state);
+        if self.buffer.len() >= self.chunk_size {
+            self.flush();
+        }
+    }
+
+    /// Moves the buffered results into the shared storage.
+    pub fn flush(&mut self) {
+        if self.buffer.is_empty() {
+            return;
+        }
+        self.results.lock().unwrap().append(&mut self.buffer);
+        self.flushes += 1;
+    }
+}
+
+/// Runs the transaction processing in the background, yielding each result as soon as it is produced.
+///
+/// The returned iterator ends once the run is over. Dropping it stops the run
+/// before the next transaction is admitted.
+///
+/// # Arguments
+/// * `max_threads` - The maximum number of threads.
+/// * `generator` - The transaction generator.
+/// * `limiter` - The limit checker.
+pub fn run_streaming<I, T>(
+    max_threads: usize,
+    generator: I,
+    limiter: Arc<LimitChecker>,
+) -> impl Iterator<Item = States>
+where
+    I: IntoIterator<Item = T> + Send + 'static,
+    I::IntoIter: Send,
+    T: Transaction + Debug + Send,
+{
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let _watchdog = spawn_watchdog(&limiter);
+        let _metrics_server = spawn_metrics_server(&limiter);
+        build_pool(worker_threads(max_threads, limiter.params())).install(|| {
+            process_stream(&limiter, generator, |state| sender.send(state).is_ok());
+        });
+    });
+
+    receiver.into_iter()
+}
+
+/// Starts the stall monitoring of the run when `stall_timeout_secs` is set.
+fn spawn_watchdog(limiter: &Arc<LimitChecker>) -> Option<Watchdog> {
+    limiter
+        .params()
+        .stall_timeout_secs
+        .map(|secs| Watchdog::spawn(limiter, Duration::from_secs(secs)))
+}
+
+/// Starts serving the live counters of the run when `metrics_addr` is set. A failure to
+/// listen is logged and leaves the run without the endpoint.
+fn spawn_metrics_server(limiter: &Arc<LimitChecker>) -> Option<MetricsServer> {
+    let addr = limiter.params().metrics_addr.as_deref()?;
+    MetricsServer::spawn(limiter, addr)
+        .inspect(|server| info!("Serving metrics on http://{}/metrics", server.local_addr()))
+        .map_err(|err| {
+            warn!(
+                "Metrics endpoint disabled, failed to listen on {}: {}",
+                addr, err
+            )
+        })
+        .ok()
+}
+
+/// Returns the number of worker threads to process with: one in `deterministic` mode.
+fn worker_threads(max_threads: usize, params: &EnvParams) -> usize {
+    if params.deterministic {
+        1
+    } else {
+        max_threads
+    }
+}
+
+/// Builds the thread pool the transactions are processed on.
+fn build_pool(max_threads: usize) -> ThreadPool {
+    ThreadPoolBuilder::new()
+        .num_threads(max_threads)
+        .build()
+        .expect("Failed to create thread pool")
+}
+
+/// Processes the stream until the limiter finishes, handing each result to `emit`,
+/// then re-processes the deferred failures. Stops early once `emit` returns `false`.
+///
+/// The stream is bridged onto the threads of the current rayon pool, so the transactions are
+/// processed concurrently, in generation order on a single thread. Once a transaction is
+/// answered with `Finish` (or `emit` returns `false`), no further transaction is pulled from
+/// the generator; those already being processed on the other threads complete and their
+/// results are still emitted. The deferred retries and the transactions left waiting for
+/// their dependency are then processed serially.
+///
+/// Transactions whose dependency has not succeeded yet wait until it does; those still waiting
+/// at the end are reported as `Skipped(Rejection::UnmetDependency)` by the limiter.
+///
+/// With `dedupe_by_token`, only the first success of each token is emitted; the later ones
+/// are still counted in the limiter statistics.
+///
+/// # Arguments
+/// * `limiter` - The limit checker.
+/// * `generator` - The transaction generator.
+/// * `emit` - Receives each result, returns whether processing should continue.
+fn process_stream<I, T>(
+    limiter: &LimitChecker,
+    generator: I,
+    mut emit: impl FnMut(States) -> bool + Send,
+) where
+    I: IntoIterator<Item = T>,
+    I::IntoIter: Send,
+    T: Transaction + Debug + Send,
+{
+    let retry_limit = limiter.params().deferred_retry_limit;
+    let deferred = Mutex::new(Vec::new());
+    let waiting = Mutex::new(Vec::new());
+
+    let dedupe_by_token = limiter.params().dedupe_by_token;
+    let mut succeeded_tokens = HashSet::new();
+    let emitter = Mutex::new(move |tx: &T, state: States| {
+        let duplicate = dedupe_by_token
+            && matches!(state, States::Success { .. })
+            && !succeeded_tokens.insert(tx.token().to_string());
+        duplicate || emit(state)
+    });
+    let emit = |tx: &T, state: States| (emitter.lock().unwrap())(tx, state);
+
+    // Whether `emit` asked to stop, which also skips the deferred and waiting transactions.
+    let closed = AtomicBool::new(false);
+    let _ = generator.into_iter().par_bridge().try_for_each(|tx| {
+        if !limiter.dependency_met(&tx) {
+            waiting.lock().unwrap().push(tx);
+            return Ok(());
+        }
+
+        let mut ready = vec![tx];
+        while let Some(tx) = ready.pop() {
+            match limiter.process_transaction(&tx) {
+                Ok(States::Failed { .. }) if retry_limit > 0 => deferred.lock().unwrap().push(tx),
+                Err(err) if skip_error(limiter, &err) => {}
+                Ok(state) if state != States::Finish => {
+                    let succeeded = matches!(state, States::Success { .. });
+                    if !emit(&tx, state) {
+                        closed.store(true, Ordering::SeqCst);
+                        return Err(());
+                    }
+                    if succeeded {
+                        let mut waiting = waiting.lock().unwrap();
+                        ready.extend(waiting.extract_if(.., |tx| limiter.dependency_met(tx)));
+                    }
+                }
+                _ => return Err(()),
+            }
+        }
+        Ok(())
+    });
+    if closed.load(Ordering::SeqCst) {
+        return;
+    }
+
+    drain_deferred(limiter, deferred.into_inner().unwrap(), retry_limit, emit);
+
+    for tx in waiting.into_inner().unwrap() {
+        match limiter.process_transaction(&tx) {
+            Err(err) if skip_error(limiter, &err) => {}
+            Ok(States::Finish) | Err(_) => return,
+            Ok(state) => {
+                if !emit(&tx, state) {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Logs a transaction that failed to be processed and returns whether the run skips it,
+/// as `on_error` is `continue`, rather than stopping.
+fn skip_error(limiter: &LimitChecker, err: &ProcessError) -> bool {
+    let skip = limiter.params().on_error == OnError::Continue;
+    if skip {
+        warn!(
+            "Skipping a transaction that failed to be processed: {}",
+            err
+        );
+    }
+    skip
+}
+
+/// Re-processes deferred failed transactions for up to `passes` passes.
+/// Transactions failing again are carried over to the next pass; the failure of the last pass is kept.
+///
+/// # Arguments
+/// * `limiter` - The limit checker.
+/// * `deferred` - Transactions whose execution failed in the main stream.
+/// * `passes` - The maximum number of deferred passes.
+/// * `emit` - Receives each result, returns whether processing should continue.
+fn drain_deferred<T: Transaction + Debug>(
+    limiter: &LimitChecker,
+    mut deferred: Vec<T>,
+    passes: usize,
+    mut emit: impl FnMut(&T, States) -> bool,
+) {
+    for pass in 1..=passes {
+        if deferred.is_empty() {
+            break;
+        }
+        info!(
+            "Deferred retry pass {}: {} transactions",
+            pass,
+            deferred.len()
+        );
+
+        let mut failed_again = Vec::new();
+        for tx in deferred {
+            match limiter.process_transaction(&tx) {
+                Ok(States::Failed { .. }) if pass < passes => failed_again.push(tx),
+                Err(err) if skip_error(limiter, &err) => {}
+                Ok(States::Finish) | Err(_) => return,
+                Ok(state) => {
+                    if !emit(&tx, state) {
+                        return;
+                    }
+                }
+            }
+        }
+        deferred = failed_again;
+    }
+}
+
+/// Logs a prominent error when the run ended without a single successful transaction,
+/// explaining the most likely configuration issue.
+///
+/// # Arguments
+/// * `params` - The parameters of the run.
+/// * `stats` - The final statistics of the run.
+///
+/// # Returns
+/// `true` if the run had no successful transaction.
+pub fn report_zero_success(params: &EnvParams, stats: &RunStats) -> bool {
+    if stats.successes > 0 {
+        return false;
+    }
+
+    let reason = if stats.rejections.insufficient_funds > 0 {
+        format!(
+            "TOTAL_AMOUNT ({}) is too small for even one transaction (price {} + commission ~{})",
+            params.total_amount, params.price, params.commission
+        )
+    } else if params.max_transactions == 0 {
+        "MAX_TRANSACTIONS is 0".to_string()
+    } else if stats.failures > 0 {
+        format!("all {} executed transactions failed", stats.failures)
+    } else {
+        "no transaction was admitted by the limits".to_string()
+    };
+
+    error!(
+        "!!! Run finished with zero successful transactions: {} !!!",
+        reason
+    );
+    true
+}
+
+/// Checks the `ASSERT_*` post-conditions against the final statistics.
+///
+/// # Arguments
+/// * `params` - The parameters of the run, holding the expected post-conditions.
+/// * `stats` - The final statistics of the run.
+///
+/// # Returns
+/// `Err` with a description of every violated post-condition.
+pub fn check_post_conditions(params: &EnvParams, stats: &RunStats) -> Result<(), Vec<String>> {
+    let mut violations = Vec::new();
+
+    if let Some(min_pct) = params.assert_min_committed_pct {
+        let committed_pct = if params.total_amount > 0 {
+            stats.committed_amount as f64 * 100.0 / params.total_amount as f64
+        } else {
+            0.0
+        };
+        if committed_pct < min_pct {
+            violations.push(format!(
+                "ASSERT_MIN_COMMITTED_PCT: committed {:.2}% of the budget ({} of {}), expected at least {}%",
+                committed_pct, stats.committed_amount, params.total_amount, min_pct
+            ));
+        }
+    }
+
+    if let Some(max_pct) = params.assert_max_failure_pct {
+        let executions = stats.successes + stats.failures;
+        let failure_pct = if executions > 0 {
+            stats.failures as f64 * 100.0 / executions as f64
+        } else {
+            0.0
+        };
+        if failure_pct >= max_pct {
+            violations.push(format!(
+                "ASSERT_MAX_FAILURE_PCT: {:.2}% of the executions failed ({} of {}), expected less than {}%",
+                failure_pct, stats.failures, executions, max_pct
+            ));
+        }
+    }
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+/// Exit code of a run that completed with fewer than `min_successes` successful transactions.
+pub const EXIT_TOO_FEW_SUCCESSES: i32 = 3;
+
+/// Checks that at least `min_successes` transactions succeeded, however the run ended,
+/// e.g. to fail a CI job whose budget only allowed a handful of transactions.
+///
+/// # Arguments
+/// * `params` - The parameters of the run, holding `min_successes`.
+/// * `stats` - The final statistics of the run.
+///
+/// # Returns
+/// `Err` with a description of the shortfall.
+pub fn check_min_successes(params: &EnvParams, stats: &RunStats) -> Result<(), String> {
+    match params.min_successes {
+        Some(min) if stats.successes < min => Err(format!(
+            "MIN_SUCCESSES: {} transactions succeeded, expected at least {}",
+            stats.successes, min
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Runs the whole pipeline (generator, limiter and result collection) on a tiny in-memory
+/// configuration whose transactions never fail, and checks the outcome (`--self-test`).
+///
+/// # Returns
+/// A description of the run if every check passed, otherwise the failed checks.
+pub fn self_test() -> Result<String, Vec<String>> {
+    // Room for 5 transactions of 200, capped at 3.
+    let params = Arc::new(EnvParams {
+        wallet: "self-test-wallet".to_string(),
+        token: "self-test-token".to_string(),
+        total_amount: 1_000,
+        commission: 100,
+        price: 100,
+        max_transactions: 3,
+        max_threads: 1,
+        deterministic: true,
+        rng_seed: Some(0),
+        token_failure_rates: Arc::new(HashMap::from([("self-test-token".to_string(), 0.0)])),
+        ..Default::default()
+    });
+    let limiter = Arc::new(LimitChecker::new(&params));
+    let mut generator = TransactionGenerator::new(params.clone());
+    generator.completed = Some(limiter.completed.clone());
+    let results = Arc::new(Mutex::new(Vec::new()));
+
+    run_transaction_process(params.max_threads, generator, limiter.clone(), &results);
+    let stats = limiter.stats();
+    drop(limiter);
+    let results = unwrap_results(results);
+
+    let mut failures = Vec::new();
+    let mut check = |passed: bool, failure: String| {
+        if !passed {
+            failures.push(failure);
+        }
+    };
+    let successes = results
+        .iter()
+        .filter(|state| matches!(state, States::Success { .. }))
+        .count();
+    check(
+        results.len() == 3 && successes == 3,
+        format!("expected 3 successful results, got {:?}", results),
+    );
+    check(
+        stats.transactions_count == 3 && stats.successes == 3,
+        format!(
+            "expected 3 committed transactions, got {} ({} successes)",
+            stats.transactions_count, stats.successes
+        ),
+    );
+    check(
+        stats.committed_amount == 600,
+        format!("expected 600 committed, got {}", stats.committed_amount),
+    );
+    check(
+        stats.rejections.count_cap == 1,
+        format!(
+            "expected the run to end on the count cap, got {:?}",
+            stats.rejections
+        ),
+    );
+
+    if failures.is_empty() {
+        Ok(format!(
+            "{} transactions committed for {}",
+            stats.transactions_count, stats.committed_amount
+        ))
+    } else {
+        Err(failures)
+    }
+}
+
+/// Extracts results from the shared storage and returns them.
+///
+/// # Arguments
+/// * `results` - Arc wrapper around Mutex for collecting results.
+pub fn unwrap_results(results: Arc<Mutex<Vec<States>>>) -> Vec<States> {
+    match Arc::try_unwrap(results) {
+        Ok(mutex) => mutex.into_inner().unwrap_or_else(|_| {
+            warn!("Failed to lock mutex, returning empty results");
+            Vec::new()
+        }),
+        Err(_) => {
+            warn!("Arc still has multiple owners, returning empty results");
+            Vec::new()
+        }
+    }
+}
+
+/// Sorts the collected results according to the requested order.
+/// The sort is stable, so transactions with equal amounts keep their generation order.
+///
+/// # Arguments
+/// * `results` - The collected transaction states.
+/// * `sort` - The requested order.
+pub fn sort_results(results: &mut [States], sort: ResultSort) {
+    let amount = |state: &States| match state {
+        States::Success { amount, .. } | States::Failed { amount, .. } => *amount,
+        States::Finish | States::Skipped(_) => 0,
+    };
+
+    match sort {
+        ResultSort::None => {}
+        ResultSort::AmountAsc => results.sort_by_key(amount),
+        ResultSort::AmountDesc => results.sort_by_key(|state| std::cmp::Reverse(amount(state))),
+    }
+}
+
+/// Writes the transaction results, rendered by `formatter`, to `stream`.
+///
+/// # Arguments
+/// * `results` - The transaction states.
+/// * `formatter` - The output format, e.g. `OutputFormat::formatter()`.
+/// * `stream` - Where the results are written, e.g. stdout or a file.
+pub fn display_results(results: &[States], formatter: &dyn ResultFormatter, stream: &OutputStream) {
+    warn_failures(results);
+    info!("Transaction Signatures:");
+    write_results(results, formatter, stream);
+}
+
+/// Writes the transaction results to every sink, each in its own format.
+///
+/// # Arguments
+/// * `results` - The transaction states.
+/// * `sinks` - The destinations of the results, e.g. `EnvParams::output_sinks()`.
+pub fn display_results_to_sinks(results: &[States], sinks: &[OutputSink]) {
+    warn_failures(results);
+    info!("Transaction Signatures:");
+    for sink in sinks {
+        write_results(results, sink.format.formatter().as_ref(), &sink.stream);
+    }
+}
+
+/// Logs a warning with the error of every failed transaction.
+fn warn_failures(results: &[States]) {
+    for (index, state) in results.iter().enumerate() {
+        if let States::Failed { error, .. } = state {
+            warn!("Transaction {} failed: {}", index + 1, error);
+        }
+    }
+}
+
+/// Writes the results and the summary of a run to every sink, each in its own format,
+/// e.g. a single JSON document for `json`.
+///
+/// # Arguments
+/// * `results` - The transaction states.
+/// * `summary` - The summary of the run, as returned by `run_transaction_process`.
+/// * `sinks` - The destinations of the results, e.g. `EnvParams::output_sinks()`.
+pub fn display_run(results: &[States], summary: &RunSummary, sinks: &[OutputSink]) {
+    warn_failures(results);
+    info!("Transaction Signatures:");
+    for sink in sinks {
+        let formatter = sink.format.formatter();
+        write_output(
+            &formatter.format_with_summary(results, summary),
+            &sink.stream,
+        );
+    }
+}
+
+/// Writes the results rendered by `formatter` to `stream`, logging a failure.
+fn write_results(results: &[States], formatter: &dyn ResultFormatter, stream: &OutputStream) {
+    write_output(&formatter.format(results), stream);
+}
+
+/// Writes the rendered `output` to `stream`, logging a failure.
+fn write_output(output: &[u8], stream: &OutputStream) {
+    let written = stream.open().and_then(|mut out| {
+        out.write_all(output)?;
+        out.flush()
+    });
+    if let Err(err) = written {
+        warn!("Failed to write the results to {:?}: {}", stream, err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(message: &str, amount: i64) -> States {
+        States::Success {
+            signature: message.to_string(),
+            amount,
+        }
+    }
+
+    #[test]
+    fn test_sort_results_by_amount() {
+        let original = vec![
+            state("b", 210),
+            state("a", 190),
+            state("c", 205),
+            state("d", 190),
+        ];
+
+        let mut results = original.clone();
+        sort_results(&mut results, ResultSort::None);
+        assert_eq!(results, original);
+
+        sort_results(&mut results, ResultSort::AmountAsc);
+        assert_eq!(
+            results,
+            vec![
+                state("a", 190),
+                state("d", 190),
+                state("c", 205),
+                state("b", 210)
+            ]
+        );
+
+        sort_results(&mut results, ResultSort::AmountDesc);
+        assert_eq!(
+            results,
+            vec![
+                state("b", 210),
+                state("c", 205),
+                state("a", 190),
+                state("d", 190)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zero_success_run_is_reported() {
+        let params = EnvParams {
+            total_amount: 100,
+            price: 100,
+            commission: 20,
+            max_transactions: 10,
+            ..Default::default()
+        };
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let generator = crate::tx_genertor::TransactionGenerator::new(Arc::new(params.clone()));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        run_transaction_process(1, generator, limiter.clone(), &results);
+
+        let mut reported = false;
+        let logs =
+            crate::test_log::capture(|| reported = report_zero_success(&params, &limiter.stats()));
+
+        assert!(reported);
+        assert!(unwrap_results(results).is_empty());
+        assert!(logs
+            .iter()
+            .any(|(level, message)| *level == log::Level::Error
+                && message.contains("zero successful transactions")
+                && message.contains("TOTAL_AMOUNT (100) is too small")));
+    }
+
+    #[test]
+    fn test_successful_run_is_not_reported() {
+        let stats = RunStats {
+            successes: 1,
+            ..Default::default()
+        };
+        assert!(!report_zero_success(&EnvParams::default(), &stats));
+    }
+
+    #[test]
+    fn test_post_conditions() {
+        let params = EnvParams {
+            total_amount: 1000,
+            assert_min_committed_pct: Some(90.0),
+            assert_max_failure_pct: Some(5.0),
+            ..Default::default()
+        };
+        let stats = RunStats {
+            committed_amount: 950,
+            successes: 99,
+            failures: 1,
+            ..Default::default()
+        };
+        assert_eq!(check_post_conditions(&params, &stats), Ok(()));
+
+        let stats = RunStats {
+            committed_amount: 800,
+            ..stats
+        };
+        assert_eq!(
+            check_post_conditions(&params, &stats),
+            Err(vec![
+                "ASSERT_MIN_COMMITTED_PCT: committed 80.00% of the budget (800 of 1000), expected at least 90%"
+                    .to_string()
+            ])
+        );
+    }
+
+    #[test]
+    fn test_min_successes_gate() {
+        let params = EnvParams {
+            min_successes: Some(3),
+            ..Default::default()
+        };
+        let stats = |successes| RunStats {
+            successes,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            check_min_successes(&params, &stats(2)),
+            Err("MIN_SUCCESSES: 2 transactions succeeded, expected at least 3".to_string())
+        );
+        assert_eq!(check_min_successes(&params, &stats(3)), Ok(()));
+        assert_eq!(
+            check_min_successes(&EnvParams::default(), &stats(0)),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_results_are_flushed_in_chunks() {
+        let results = Mutex::new(Vec::new());
+        let mut sink = ChunkedSink::new(&results, 2);
+
+        sink.push(state("a", 1));
+        assert!(results.lock().unwrap().is_empty());
+        sink.push(state("b", 2));
+        assert_eq!(results.lock().unwrap().len(), 2);
+
+        for (message, amount) in [("c", 3), ("d", 4), ("e", 5)] {
+            sink.push(state(message, amount));
+        }
+        assert_eq!(results.lock().unwrap().len(), 4);
+
+        sink.flush();
+        assert_eq!(sink.flushes, 3);
+        assert_eq!(
+            *results.lock().unwrap(),
+            vec![
+                state("a", 1),
+                state("b", 2),
+                state("c", 3),
+                state("d", 4),
+                state("e", 5)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_result_sort() {
+        assert_eq!("amount_desc".parse(), Ok(ResultSort::AmountDesc));
+        assert!("by_amount".parse::<ResultSort>().is_err());
+    }
+
+    #[test]
+    fn test_self_test_passes() {
+        assert_eq!(
+            self_test(),
+            Ok("3 transactions committed for 600".to_string())
+        );
+    }
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+#[cfg(unix)]
+use std::sync::Weak;
+
+use log::{info, warn};
+
+use crate::limits::LimitChecker;
+
+/// Logs the current progress snapshot of the limiter.
+///
+/// # Arguments
+/// * `limiter` - The limit checker of the running process.
+pub fn log_snapshot(limiter: &LimitChecker) {
+    info!("Progress snapshot: {}", limiter.snapshot());
+}
+
+/// Installs a SIGUSR1 handler logging the progress snapshot of `limiter` on demand.
+///
+/// The handler thread only holds a weak reference, so it does not keep the limiter alive
+/// past the end of the run.
+///
+/// # Arguments
+/// * `limiter` - The limit checker shared with the workers.
+#[cfg(unix)]
+pub fn install_snapshot_handler(limiter: &Arc<LimitChecker>) -> std::io::Result<()> {
+    use signal_hook::consts::SIGUSR1;
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGUSR1])?;
+    let limiter: Weak<LimitChecker> = Arc::downgrade(limiter);
+
+    std::thread::spawn(move || {
+        for _ in signals.forever() {
+            match limiter.upgrade() {
+                Some(limiter) => log_snapshot(&limiter),
+                None => break,
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Installs a SIGINT/SIGTERM handler stopping the run gracefully: the `limiter` is aborted,
+/// so no worker admits a new transaction, the in-flight ones complete and the results
+/// collected so far are still reported. A second signal exits at once.
+///
+/// # Arguments
+/// * `limiter` - The limit checker shared with the workers.
+#[cfg(unix)]
+pub fn install_shutdown_handler(limiter: &Arc<LimitChecker>) -> std::io::Result<()> {
+    use signal_hook::consts::{SIGINT, SIGTERM};
+    use signal_hook::iterator::Signals;
+
+    let mut signals = Signals::new([SIGINT, SIGTERM])?;
+    let limiter: Weak<LimitChecker> = Arc::downgrade(limiter);
+
+    std::thread::spawn(move || {
+        let mut interrupted = false;
+        for signal in signals.forever() {
+            if interrupted {
+                std::process::exit(128 + signal);
+            }
+            interrupted = true;
+            match limiter.upgrade() {
+                Some(limiter) => {
+                    warn!("Interrupted: finishing the in-flight transactions, interrupt again to exit now.");
+                    limiter.abort();
+                }
+                None => std::process::exit(128 + signal),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+/// Fallback of the platforms without POSIX signals: Ctrl-C keeps its default behavior and
+/// exits at once, without the results collected so far, which the returned error tells.
+///
+/// # Arguments
+/// * `_limiter` - The limit checker shared with the workers.
+#[cfg(not(unix))]
+pub fn install_shutdown_handler(_limiter: &Arc<LimitChecker>) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "graceful shutdown needs POSIX signals, an interrupt exits at once without the results",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env_utils::EnvParams;
+
+    #[test]
+    fn test_snapshot_handler_logs_progress() {
+        let limiter = LimitChecker::new(&EnvParams {
+            total_amount: 1000,
+            ..Default::default()
+        });
+        limiter
+            .transactions_count
+            .store(3, std::sync::atomic::Ordering::SeqCst);
+        limiter
+            .settled_amount
+            .store(600, std::sync::atomic::Ordering::SeqCst);
+
+        let logs = crate::test_log::capture(|| log_snapshot(&limiter));
+
+        assert!(logs.iter().any(|(_, message)| message
+            == "Progress snapshot: transactions processed: 3, amount committed: 600, remaining budget: 400"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_interrupt_stops_the_run_with_partial_results() {
+        use std::time::Duration;
+
+        use crate::runner::spawn_transaction_process;
+        use crate::stats::StopReason;
+        use crate::tx_genertor::TransactionGenerator;
+
+        let params = Arc::new(EnvParams {
+            price: 100,
+            commission: 10,
+            total_amount: i64::MAX,
+            max_transactions: usize::MAX,
+            max_threads: 2,
+            failure_rate: Some(0.0),
+            ..Default::default()
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        install_shutdown_handler(&limiter).unwrap();
+        let mut generator = TransactionGenerator::new(params);
+        generator.completed = Some(limiter.completed.clone());
+
+        let run = spawn_transaction_process(2, generator, limiter);
+        std::thread::sleep(Duration::from_millis(50));
+        signal_hook::low_level::raise(signal_hook::consts::SIGINT).unwrap();
+        let (results, stats) = run.join();
+
+        assert!(!results.is_empty());
+        assert_eq!(results.len() as u64, stats.successes);
+        assert_eq!(stats.stop_reason, StopReason::Aborted);
+    }
+}
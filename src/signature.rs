@@ -0,0 +1,246 @@
+use std::str::FromStr;
+
+use log::warn;
+use rand::RngCore;
+use serde::Serialize;
+
+const BASE58_ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Canonical encoding of the signatures returned by `execute()` (`SIGNATURE_FORMAT`).
+///
+/// Backends return signatures in different encodings; with a format other than `raw`, every
+/// signature is decoded from whichever of hex, base58 or base64 it is in and re-encoded,
+/// so the results and the duplicate detection see a single form.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureFormat {
+    /// Keep the signatures as returned.
+    #[default]
+    Raw,
+    /// Bitcoin-alphabet base58, as used by Solana.
+    Base58,
+    /// Lowercase hex.
+    Hex,
+    /// Standard base64 with padding.
+    Base64,
+}
+
+impl FromStr for SignatureFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raw" => Ok(Self::Raw),
+            "base58" => Ok(Self::Base58),
+            "hex" => Ok(Self::Hex),
+            "base64" => Ok(Self::Base64),
+            other => Err(format!("unknown signature format: {}", other)),
+        }
+    }
+}
+
+impl SignatureFormat {
+    /// Returns `signature` in this format. A signature already valid in this format is
+    /// re-encoded, e.g. uppercase hex to lowercase, otherwise it is decoded as the first of hex, base58 and base64 it is valid in; a base64
+    /// signature is recognized by its characters outside the base58 alphabet (`0OIl+/=`),
+    /// which a 64-byte one all but always has. A signature in none of them is kept as is,
+    /// with a warning.
+    pub fn normalize(self, signature: &str) -> String {
+        if self == Self::Raw {
+            return signature.to_string();
+        }
+        if let Some(bytes) = self.decode(signature) {
+            return self.encode(&bytes);
+        }
+
+        let bytes = [Self::Hex, Self::Base58, Self::Base64]
+            .into_iter()
+            .find_map(|format| format.decode(signature));
+        match bytes {
+            Some(bytes) => self.encode(&bytes),
+            None => {
+                warn!("Signature in an unknown encoding kept as is: {}", signature);
+                signature.to_string()
+            }
+        }
+    }
+
+    /// Returns a random 64-byte signature in this format, the hex one for `raw`,
+    /// for the simulated executions.
+    pub fn fake(self, rng: &mut impl RngCore) -> String {
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        match self {
+            Self::Raw => Self::Hex.encode(&bytes),
+            format => format.encode(&bytes),
+        }
+    }
+
+    fn encode(self, bytes: &[u8]) -> String {
+        match self {
+            Self::Raw | Self::Hex => bytes.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            Self::Base58 => encode_base58(bytes),
+            Self::Base64 => encode_base64(bytes),
+        }
+    }
+
+    fn decode(self, s: &str) -> Option<Vec<u8>> {
+        if s.is_empty() {
+            return None;
+        }
+        match self {
+            Self::Raw => Some(s.as_bytes().to_vec()),
+            Self::Hex => decode_hex(s),
+            Self::Base58 => decode_base58(s),
+            Self::Base64 => decode_base64(s),
+        }
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn encode_base58(bytes: &[u8]) -> String {
+    let zeros = bytes.iter().take_while(|&&byte| byte == 0).count();
+    // Base-58 digits, least significant first.
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &bytes[zeros..] {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut out = "1".repeat(zeros);
+    out.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&digit| BASE58_ALPHABET[digit as usize] as char),
+    );
+    out
+}
+
+fn decode_base58(s: &str) -> Option<Vec<u8>> {
+    let zeros = s.bytes().take_while(|&b| b == b'1').count();
+    // Bytes, least significant first.
+    let mut bytes: Vec<u8> = Vec::new();
+    for b in s.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET.iter().position(|&c| c == b)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += *byte as u32 * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut out = vec![0; zeros];
+    out.extend(bytes.iter().rev());
+    Some(out)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | (byte as u32) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64_ALPHABET[(group >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(4) {
+        return None;
+    }
+    let data = s.trim_end_matches('=');
+    if s.len() - data.len() > 2 {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut group = 0u32;
+    for (i, b) in data.bytes().enumerate() {
+        group = group << 6 | BASE64_ALPHABET.iter().position(|&c| c == b)? as u32;
+        if i % 4 == 3 {
+            out.extend_from_slice(&group.to_be_bytes()[1..]);
+            group = 0;
+        }
+    }
+    match data.len() % 4 {
+        0 => {}
+        2 => out.push((group >> 4) as u8),
+        3 => out.extend_from_slice(&((group >> 2) as u16).to_be_bytes()),
+        _ => return None,
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_signature_is_normalized_to_base58() {
+        let base58 = SignatureFormat::Base58;
+        assert_eq!(
+            base58.normalize("48656c6c6f20576f726c6421"),
+            "2NEpo7TZRRrLZSi2U"
+        );
+        // Leading zero bytes map to leading ones.
+        assert_eq!(base58.normalize("000000287fb4cd"), "111233QC4");
+        // An already canonical signature is kept.
+        assert_eq!(base58.normalize("2NEpo7TZRRrLZSi2U"), "2NEpo7TZRRrLZSi2U");
+
+        assert_eq!(
+            SignatureFormat::Hex.normalize("SGVsbG8gV29ybGQ="),
+            "48656c6c6f20576f726c64"
+        );
+        assert_eq!(
+            SignatureFormat::Base64.normalize("48656c6c6f20576f726c64"),
+            "SGVsbG8gV29ybGQ="
+        );
+        assert_eq!(SignatureFormat::Hex.normalize("4865"), "4865");
+        assert_eq!(SignatureFormat::Hex.normalize("ABCD"), "abcd");
+        assert_eq!(SignatureFormat::Raw.normalize("0xABC"), "0xABC");
+    }
+
+    #[test]
+    fn test_fake_signatures_round_trip() {
+        let mut rng = rand::thread_rng();
+        for format in [
+            SignatureFormat::Base58,
+            SignatureFormat::Hex,
+            SignatureFormat::Base64,
+        ] {
+            let signature = format.fake(&mut rng);
+            assert_eq!(format.decode(&signature).unwrap().len(), 64);
+            assert_eq!(format.normalize(&signature), signature);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use std::fmt;
+use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+
+use crate::output::FlushBatcher;
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS attempts (
+    id INTEGER NOT NULL,
+    token TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    latency_us INTEGER NOT NULL,
+    timestamp_ms INTEGER NOT NULL
+)";
+
+/// One execution attempt of a transaction, as stored in the `attempts` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttemptRecord {
+    pub id: u64,
+    pub token: String,
+    /// Amount of the transaction in budget units.
+    pub amount: i64,
+    /// `ok` or `failed`.
+    pub status: String,
+    pub latency_us: u64,
+    /// Time the execution ended at, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+}
+
+impl AttemptRecord {
+    /// Creates the record of an execution ending now.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the transaction.
+    /// * `token` - The token the transaction buys.
+    /// * `amount` - The amount of the transaction in budget units.
+    /// * `failed` - Whether the execution failed.
+    /// * `latency` - The duration of the execution.
+    pub fn new(id: u64, token: &str, amount: i64, failed: bool, latency: Duration) -> Self {
+        Self {
+            id,
+            token: token.to_string(),
+            amount,
+            status: if failed { "failed" } else { "ok" }.to_string(),
+            latency_us: latency.as_micros() as u64,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_millis() as u64),
+        }
+    }
+}
+
+/// Records every execution attempt to a SQLite database (`DB_PATH`, `sqlite` feature).
+///
+/// The workers only send their records over a channel; a dedicated collector thread owns the
+/// connection and inserts them in batches through a `FlushBatcher`, one database transaction
+/// per batch, so the workers never contend for the database.
+pub struct SqliteRecorder {
+    sender: Mutex<Option<Sender<AttemptRecord>>>,
+    collector: Mutex<Option<JoinHandle<Result<usize, String>>>>,
+}
+
+impl fmt::Debug for SqliteRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SqliteRecorder")
+    }
+}
+
+impl SqliteRecorder {
+    /// Opens or creates the database at `path`, creating the `attempts` table if needed,
+    /// and starts the collector.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The database file.
+    /// * `batch_size` - The number of attempts inserted per batch.
+    /// * `interval` - The longest time an attempt waits for its batch.
+    pub fn open(
+        path: impl AsRef<Path>,
+        batch_size: usize,
+        interval: Duration,
+    ) -> Result<Self, String> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .map_err(|err| format!("failed to open {}: {}", path.display(), err))?;
+        connection
+            .execute(SCHEMA, [])
+            .map_err(|err| format!("failed to create the attempts table: {}", err))?;
+
+        let (sender, receiver) = mpsc::channel();
+        let batcher = FlushBatcher::new(batch_size, interval);
+        let collector = thread::spawn(move || collect(connection, receiver, batcher));
+        Ok(Self {
+            sender: Mutex::new(Some(sender)),
+            collector: Mutex::new(Some(collector)),
+        })
+    }
+
+    /// Queues `record` for insertion; records sent after `close` are dropped.
+    pub fn record(&self, record: AttemptRecord) {
+        if let Some(sender) = &*self.sender.lock().unwrap() {
+            let _ = sender.send(record);
+        }
+    }
+
+    /// Stops the collector once the queued records are inserted.
+    ///
+    /// # Returns
+    ///
+    /// The number of inserted records, or the database error that stopped the collector.
+    pub fn close(&self) -> Result<usize, String> {
+        self.sender.lock().unwrap().take();
+        match self.collector.lock().unwrap().take() {
+            Some(collector) => collector
+                .join()
+                .unwrap_or_else(|_| Err("the collector panicked".to_string())),
+            None => Ok(0),
+        }
+    }
+}
+
+impl Drop for SqliteRecorder {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// Inserts the received records in batches until every sender is dropped, then the last batch.
+fn collect(
+    mut connection: Connection,
+    receiver: Receiver<AttemptRecord>,
+    mut batcher: FlushBatcher<AttemptRecord>,
+) -> Result<usize, String> {
+    let mut inserted = 0;
+    let mut write = |batch: Vec<AttemptRecord>| {
+        insert(&mut connection, &batch).map_err(|err| format!("failed to insert: {}", err))?;
+        inserted += batch.len();
+        Ok::<_, String>(())
+    };
+    loop {
+        let batch = match receiver.recv_timeout(batcher.time_left()) {
+            Ok(record) => batcher.push(record),
+            Err(RecvTimeoutError::Timeout) => batcher.flush_due(),
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        if let Some(batch) = batch {
+            write(batch)?;
+        }
+    }
+    write(batcher.flush())?;
+    Ok(inserted)
+}
+
+fn insert(connection: &mut Connection, records: &[AttemptRecord]) -> rusqlite::Result<()> {
+    let transaction = connection.transaction()?;
+    {
+        let mut statement = transaction.prepare_cached(
+            "INSERT INTO attempts (id, token, amount, status, latency_us, timestamp_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for record in records {
+            statement.execute(params![
+                record.id as i64,
+                record.token,
+                record.amount,
+                record.status,
+                record.latency_us as i64,
+                record.timestamp_ms as i64,
+            ])?;
+        }
+    }
+    transaction.commit()
+}
+
+/// Reads back every attempt stored in the database at `path`, in insertion order.
+pub fn read_attempts(path: impl AsRef<Path>) -> Result<Vec<AttemptRecord>, String> {
+    let path = path.as_ref();
+    let connection = Connection::open(path)
+        .map_err(|err| format!("failed to open {}: {}", path.display(), err))?;
+    let mut statement = connection
+        .prepare(
+            "SELECT id, token, amount, status, latency_us, timestamp_ms
+             FROM attempts ORDER BY rowid",
+        )
+        .map_err(|err| format!("failed to query the attempts: {}", err))?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok(AttemptRecord {
+                id: row.get::<_, i64>(0)? as u64,
+                token: row.get(1)?,
+                amount: row.get(2)?,
+                status: row.get(3)?,
+                latency_us: row.get::<_, i64>(4)? as u64,
+                timestamp_ms: row.get::<_, i64>(5)? as u64,
+            })
+        })
+        .map_err(|err| format!("failed to query the attempts: {}", err))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|err| format!("failed to read an attempt: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::limits::LimitChecker;
+    use crate::tx::SomeTransaction;
+
+    #[test]
+    fn test_attempts_are_inserted_and_queried_back() {
+        let path = std::env::temp_dir().join(format!("attempts-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let params = Arc::new(EnvParams {
+            token: "SOL".to_string(),
+            price: 100,
+            commission: 10,
+            total_amount: 10_000,
+            max_transactions: 100,
+            max_threads: 1,
+            force_fail_indices: Arc::new([1].into()),
+            token_failure_rates: Arc::new([("SOL".to_string(), 0.0)].into()),
+            ..Default::default()
+        });
+        let recorder = Arc::new(SqliteRecorder::open(&path, 2, Duration::from_secs(60)).unwrap());
+        let limiter = LimitChecker::new(&params).with_recorder(recorder.clone());
+
+        for id in 0..3 {
+            let mut tx = SomeTransaction::new(&params);
+            tx.id = id;
+            limiter.process_transaction(&tx).unwrap();
+        }
+        assert_eq!(recorder.close(), Ok(3));
+
+        let attempts = read_attempts(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let rows: Vec<_> = attempts
+            .iter()
+            .map(|attempt| {
+                (
+                    attempt.id,
+                    attempt.token.as_str(),
+                    attempt.amount,
+                    attempt.status.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                (0, "SOL", 110, "ok"),
+                (1, "SOL", 110, "failed"),
+                (2, "SOL", 110, "ok")
+            ]
+        );
+        assert!(attempts.iter().all(|attempt| attempt.timestamp_ms > 0));
+    }
+}
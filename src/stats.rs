@@ -0,0 +1,258 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+/// Reason why the limit checker refused a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Rejection {
+    /// The transaction alone costs more than the whole budget.
+    InsufficientFunds,
+    /// The maximum number of transactions has been reached.
+    CountCap,
+    /// The transaction would push the committed amount over the budget.
+    AmountCap,
+    /// The transaction amount is below the dust threshold.
+    Dust,
+    /// The transaction alone exceeds the per-window spending limit.
+    WindowCap,
+    /// The pre-execute hook vetoed the transaction.
+    Vetoed,
+    /// The transaction it depends on has not succeeded.
+    UnmetDependency,
+    /// The transaction would push the number of bought tokens over `max_tokens`.
+    TokenCap,
+    /// The transaction would push the amount of its wallet over `per_wallet_amount_limit`.
+    WalletCap,
+    /// The number of `execute()` calls reached `max_execute_calls`.
+    ExecuteCap,
+    /// The run has been going for `max_runtime_secs`.
+    TimeLimit,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::InsufficientFunds => "insufficient funds",
+            Self::CountCap => "transaction count cap",
+            Self::AmountCap => "amount cap",
+            Self::Dust => "dust amount",
+            Self::WindowCap => "window amount cap",
+            Self::Vetoed => "vetoed by the pre-execute hook",
+            Self::UnmetDependency => "unmet dependency",
+            Self::TokenCap => "token count cap",
+            Self::WalletCap => "wallet amount cap",
+            Self::ExecuteCap => "execute call cap",
+            Self::TimeLimit => "time limit",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// Closed-form projection of a run, computed from the configuration without executing anything.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Estimate {
+    /// Projected number of committed transactions.
+    pub transactions: usize,
+    /// Projected committed amount.
+    pub spend: f64,
+    /// The cap expected to end the run.
+    pub binding: Rejection,
+}
+
+impl fmt::Display for Estimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "projected transactions: {}, projected spend: {:.0}, binding cap: {}",
+            self.transactions, self.spend, self.binding
+        )
+    }
+}
+
+/// Number of rejected transactions per reason.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RejectionCounts {
+    pub insufficient_funds: usize,
+    pub count_cap: usize,
+    pub amount_cap: usize,
+    pub dust: usize,
+    pub window_cap: usize,
+    pub vetoed: usize,
+    pub unmet_dependency: usize,
+    pub token_cap: usize,
+    pub wallet_cap: usize,
+    pub execute_cap: usize,
+    pub time_limit: usize,
+}
+
+/// Thread-safe counters of rejected transactions per reason.
+#[derive(Debug, Default)]
+pub struct RejectionCounters {
+    insufficient_funds: AtomicUsize,
+    count_cap: AtomicUsize,
+    amount_cap: AtomicUsize,
+    dust: AtomicUsize,
+    window_cap: AtomicUsize,
+    vetoed: AtomicUsize,
+    unmet_dependency: AtomicUsize,
+    token_cap: AtomicUsize,
+    wallet_cap: AtomicUsize,
+    execute_cap: AtomicUsize,
+    time_limit: AtomicUsize,
+}
+
+impl RejectionCounters {
+    /// Records a rejection for the given reason.
+    pub fn record(&self, reason: Rejection) {
+        let counter = match reason {
+            Rejection::InsufficientFunds => &self.insufficient_funds,
+            Rejection::CountCap => &self.count_cap,
+            Rejection::AmountCap => &self.amount_cap,
+            Rejection::Dust => &self.dust,
+            Rejection::WindowCap => &self.window_cap,
+            Rejection::Vetoed => &self.vetoed,
+            Rejection::UnmetDependency => &self.unmet_dependency,
+            Rejection::TokenCap => &self.token_cap,
+            Rejection::WalletCap => &self.wallet_cap,
+            Rejection::ExecuteCap => &self.execute_cap,
+            Rejection::TimeLimit => &self.time_limit,
+        };
+        counter.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the current counts.
+    pub fn counts(&self) -> RejectionCounts {
+        RejectionCounts {
+            insufficient_funds: self.insufficient_funds.load(Ordering::SeqCst),
+            count_cap: self.count_cap.load(Ordering::SeqCst),
+            amount_cap: self.amount_cap.load(Ordering::SeqCst),
+            dust: self.dust.load(Ordering::SeqCst),
+            window_cap: self.window_cap.load(Ordering::SeqCst),
+            vetoed: self.vetoed.load(Ordering::SeqCst),
+            unmet_dependency: self.unmet_dependency.load(Ordering::SeqCst),
+            token_cap: self.token_cap.load(Ordering::SeqCst),
+            wallet_cap: self.wallet_cap.load(Ordering::SeqCst),
+            execute_cap: self.execute_cap.load(Ordering::SeqCst),
+            time_limit: self.time_limit.load(Ordering::SeqCst),
+        }
+    }
+}
+
+/// Execution latency percentiles, in microseconds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+}
+
+impl LatencyPercentiles {
+    /// Computes the percentiles from unordered latency samples (nearest-rank method).
+    pub fn from_samples(samples: &[u64]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let percentile = |p: f64| {
+            if sorted.is_empty() {
+                return 0;
+            }
+            let rank = (p * sorted.len() as f64).ceil() as usize;
+            sorted[rank.clamp(1, sorted.len()) - 1]
+        };
+
+        Self {
+            p50_us: percentile(0.50),
+            p90_us: percentile(0.90),
+            p99_us: percentile(0.99),
+        }
+    }
+}
+
+/// Distribution of executed transactions across worker threads.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ThreadUtilization {
+    /// Executed transactions per worker thread index.
+    pub per_thread: Vec<usize>,
+    pub min: usize,
+    pub max: usize,
+    pub avg: f64,
+}
+
+impl ThreadUtilization {
+    /// Builds the breakdown from per-thread counts.
+    pub fn from_counts(per_thread: Vec<usize>) -> Self {
+        let min = per_thread.iter().copied().min().unwrap_or(0);
+        let max = per_thread.iter().copied().max().unwrap_or(0);
+        let avg = if per_thread.is_empty() {
+            0.0
+        } else {
+            per_thread.iter().sum::<usize>() as f64 / per_thread.len() as f64
+        };
+
+        Self {
+            per_thread,
+            min,
+            max,
+            avg,
+        }
+    }
+}
+
+/// Why a run stopped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The generator ran out of transactions before any limit was reached.
+    #[default]
+    Exhausted,
+    /// A limit finished the process, e.g. the amount or the transaction count cap.
+    Limit(Rejection),
+    /// The run was aborted, e.g. by the stall watchdog or the balance monitor.
+    Aborted,
+}
+
+impl fmt::Display for StopReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exhausted => f.write_str("no transactions left"),
+            Self::Limit(reason) => write!(f, "{} reached", reason),
+            Self::Aborted => f.write_str("aborted"),
+        }
+    }
+}
+
+/// Statistics of a run, as accumulated by the `LimitChecker`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunStats {
+    /// Number of committed transactions.
+    pub transactions_count: usize,
+    /// Total committed amount.
+    pub committed_amount: i64,
+    /// Total commission of the successfully executed transactions.
+    pub commission_paid: i64,
+    /// Why the run stopped.
+    pub stop_reason: StopReason,
+    /// Number of successful executions.
+    pub successes: u64,
+    /// Number of failed executions.
+    pub failures: u64,
+    /// Rejected transactions by reason.
+    pub rejections: RejectionCounts,
+    /// Number of transactions whose processing exceeded the SLA.
+    pub sla_violations: usize,
+    /// Number of successful executions that returned an already seen signature.
+    pub duplicate_signatures: usize,
+    /// Number of admitted transactions whose counters were rolled back (failed or vetoed).
+    pub rollback_count: usize,
+    /// Total amount returned to the budget by the rollbacks.
+    pub rolled_back_amount: i64,
+    /// Failed executions per error category.
+    pub error_categories: BTreeMap<String, usize>,
+    /// Percentiles of the execution latency.
+    pub latency: LatencyPercentiles,
+    /// Executed transactions per worker thread.
+    pub thread_utilization: ThreadUtilization,
+}
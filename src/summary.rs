@@ -0,0 +1,267 @@
+use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+
+use crate::env_utils::{EnvParams, Network};
+use crate::stats::{LatencyPercentiles, RunStats, StopReason};
+
+/// Leading bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Serializable report of a finished run.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunSummary {
+    /// Network the run was sent to.
+    pub network: String,
+    /// Number of committed transactions.
+    pub transactions_count: usize,
+    /// Total committed amount.
+    pub committed_amount: i64,
+    /// Total commission of the successfully executed transactions.
+    #[serde(default)]
+    pub commission_paid: i64,
+    /// Why the run stopped.
+    #[serde(default)]
+    pub stop_reason: StopReason,
+    /// Number of successful executions.
+    pub successes: u64,
+    /// Number of failed executions.
+    pub failures: u64,
+    /// Share of executions that succeeded, in `[0, 1]`.
+    pub success_rate: f64,
+    /// Percentiles of the execution latency.
+    pub latency: LatencyPercentiles,
+    /// Resolved parameters of the run, with the wallets redacted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<serde_json::Value>,
+}
+
+impl RunSummary {
+    /// Builds the summary of a run from the limiter statistics.
+    ///
+    /// # Arguments
+    /// * `network` - The network the run was sent to.
+    /// * `stats` - The final statistics of the `LimitChecker`.
+    pub fn new(network: Network, stats: &RunStats) -> Self {
+        let executions = stats.successes + stats.failures;
+        let success_rate = if executions == 0 {
+            0.0
+        } else {
+            stats.successes as f64 / executions as f64
+        };
+
+        Self {
+            network: network.to_string(),
+            transactions_count: stats.transactions_count,
+            committed_amount: stats.committed_amount,
+            commission_paid: stats.commission_paid,
+            stop_reason: stats.stop_reason,
+            successes: stats.successes,
+            failures: stats.failures,
+            success_rate,
+            latency: stats.latency,
+            config: None,
+        }
+    }
+
+    /// Embeds the resolved `params` of the run, secrets redacted, so the report documents
+    /// how to reproduce it.
+    pub fn with_config(mut self, params: &EnvParams) -> Self {
+        self.config = Some(serde_json::to_value(params).expect("the parameters serialize to JSON"));
+        self
+    }
+
+    /// Writes the summary as pretty-printed JSON to `path`, gzip-compressed when `compress`
+    /// is set or the path ends in `.gz`.
+    pub fn save(&self, path: impl AsRef<Path>, compress: bool) -> Result<(), String> {
+        let path = path.as_ref();
+        let json = serde_json::to_string_pretty(self).map_err(|err| err.to_string())?;
+        if compress || path.extension().is_some_and(|ext| ext == "gz") {
+            let file = fs::File::create(path).map_err(|err| err.to_string())?;
+            let mut encoder = GzEncoder::new(file, Compression::default());
+            encoder
+                .write_all(json.as_bytes())
+                .and_then(|()| encoder.finish().map(drop))
+                .map_err(|err| err.to_string())
+        } else {
+            fs::write(path, json).map_err(|err| err.to_string())
+        }
+    }
+
+    /// Loads a summary previously written with `save`, compressed or not.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let read_err = |err: std::io::Error| format!("failed to read {}: {}", path.display(), err);
+        let bytes = fs::read(path).map_err(read_err)?;
+        let json = if bytes.starts_with(&GZIP_MAGIC) {
+            let mut json = String::new();
+            GzDecoder::new(bytes.as_slice())
+                .read_to_string(&mut json)
+                .map_err(read_err)?;
+            json
+        } else {
+            String::from_utf8(bytes)
+                .map_err(|err| format!("failed to read {}: {}", path.display(), err))?
+        };
+        serde_json::from_str(&json)
+            .map_err(|err| format!("failed to parse {}: {}", path.display(), err))
+    }
+}
+
+impl fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "successes: {}, failures: {} ({:.2}% success)",
+            self.successes,
+            self.failures,
+            self.success_rate * 100.0
+        )?;
+        writeln!(
+            f,
+            "committed: {} transactions, {} spent, {} in commissions",
+            self.transactions_count, self.committed_amount, self.commission_paid
+        )?;
+        write!(f, "stopped: {}", self.stop_reason)
+    }
+}
+
+/// Differences between two run summaries, computed as `b - a`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SummaryDiff {
+    pub committed_amount: i64,
+    pub success_rate: f64,
+    pub latency_p50_us: i64,
+    pub latency_p90_us: i64,
+    pub latency_p99_us: i64,
+}
+
+impl fmt::Display for SummaryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "committed amount: {:+}", self.committed_amount)?;
+        writeln!(f, "success rate:     {:+.2}%", self.success_rate * 100.0)?;
+        writeln!(f, "latency p50:      {:+}us", self.latency_p50_us)?;
+        writeln!(f, "latency p90:      {:+}us", self.latency_p90_us)?;
+        write!(f, "latency p99:      {:+}us", self.latency_p99_us)
+    }
+}
+
+/// Compares two summaries, returning the deltas from `a` to `b`.
+pub fn compare_summaries(a: &RunSummary, b: &RunSummary) -> SummaryDiff {
+    let delta = |a: u64, b: u64| b as i64 - a as i64;
+
+    SummaryDiff {
+        committed_amount: b.committed_amount - a.committed_amount,
+        success_rate: b.success_rate - a.success_rate,
+        latency_p50_us: delta(a.latency.p50_us, b.latency.p50_us),
+        latency_p90_us: delta(a.latency.p90_us, b.latency.p90_us),
+        latency_p99_us: delta(a.latency.p99_us, b.latency.p99_us),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(committed_amount: i64, successes: u64, failures: u64, p50_us: u64) -> RunSummary {
+        RunSummary::new(
+            Network::Devnet,
+            &RunStats {
+                transactions_count: successes as usize,
+                committed_amount,
+                successes,
+                failures,
+                latency: LatencyPercentiles {
+                    p50_us,
+                    p90_us: p50_us * 2,
+                    p99_us: p50_us * 3,
+                },
+                ..Default::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_compare_summaries() {
+        let a = summary(1000, 8, 2, 100);
+        let b = summary(1500, 9, 1, 80);
+
+        let diff = compare_summaries(&a, &b);
+
+        assert_eq!(diff.committed_amount, 500);
+        assert!((diff.success_rate - 0.1).abs() < 1e-9);
+        assert_eq!(diff.latency_p50_us, -20);
+        assert_eq!(diff.latency_p90_us, -40);
+        assert_eq!(diff.latency_p99_us, -60);
+    }
+
+    #[test]
+    fn test_summary_round_trips_through_file() {
+        let path = std::env::temp_dir().join(format!("summary-{}.json", std::process::id()));
+        let original = summary(1000, 8, 2, 100);
+
+        original.save(&path, false).unwrap();
+        let loaded = RunSummary::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, original);
+        assert_eq!(
+            compare_summaries(&original, &loaded),
+            SummaryDiff::default()
+        );
+    }
+
+    #[test]
+    fn test_summary_embeds_the_redacted_config() {
+        let params = EnvParams {
+            wallet: "secret-wallet".to_string(),
+            wallets: vec!["w1".to_string(), "w2".to_string()],
+            targets: vec![crate::env_utils::Target::parse("secret-target:SOL:1k:10", 0).unwrap()],
+            token: "BONK".to_string(),
+            total_amount: 1000,
+            network: Network::Devnet,
+            ..Default::default()
+        };
+        let original = summary(1000, 8, 2, 100).with_config(&params);
+
+        let config = original.config.as_ref().unwrap();
+        assert_eq!(config["wallet"], crate::env_utils::REDACTED);
+        assert_eq!(
+            config["wallets"],
+            serde_json::json!([crate::env_utils::REDACTED, crate::env_utils::REDACTED])
+        );
+        assert_eq!(config["targets"][0]["wallet"], crate::env_utils::REDACTED);
+        assert_eq!(config["token"], "BONK");
+        assert_eq!(config["total_amount"], 1000);
+        assert_eq!(config["network"], "devnet");
+        let json = serde_json::to_string(&original).unwrap();
+        assert!(!json.contains("secret-wallet"));
+        assert!(!json.contains("secret-target"));
+
+        let path = std::env::temp_dir().join(format!("summary-config-{}.json", std::process::id()));
+        original.save(&path, false).unwrap();
+        let loaded = RunSummary::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, original);
+    }
+
+    #[test]
+    fn test_compressed_summary_round_trips_through_file() {
+        let path = std::env::temp_dir().join(format!("summary-{}.json.gz", std::process::id()));
+        let original = summary(1000, 8, 2, 100);
+
+        original.save(&path, false).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let loaded = RunSummary::load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(bytes.starts_with(&GZIP_MAGIC));
+        assert_eq!(loaded, original);
+    }
+}
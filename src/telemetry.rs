@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use opentelemetry::trace::{
+    Span, SpanContext, SpanKind, Status, TraceContextExt, Tracer, TracerProvider,
+};
+use opentelemetry::{Context, KeyValue};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::{SdkTracer, SdkTracerProvider};
+
+use crate::limits::{ProcessError, States};
+use crate::output::ResultRecord;
+use crate::tx::Transaction;
+
+/// Traces the lifecycle of every processed transaction as an OpenTelemetry span
+/// carrying its id, token, amount and outcome (`otel` feature).
+///
+/// A transaction processed again after a failed execution, e.g. by a deferred retry pass,
+/// is traced as a `retry` child span of its first failed attempt.
+pub struct Telemetry {
+    tracer: SdkTracer,
+    /// Context and number of attempts of the transactions whose last execution failed, by id.
+    failed_attempts: Mutex<HashMap<u64, (SpanContext, i64)>>,
+}
+
+impl fmt::Debug for Telemetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Telemetry")
+    }
+}
+
+impl Telemetry {
+    /// Creates the tracing of the transactions with a tracer of `provider`.
+    pub fn new(provider: &SdkTracerProvider) -> Self {
+        Self {
+            tracer: provider.tracer("test-bot"),
+            failed_attempts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Creates a provider exporting the spans in batches over OTLP/HTTP to `endpoint`
+    /// (`OTEL_ENDPOINT`); shut it down at the end of the run to flush the last batch.
+    pub fn otlp_provider(endpoint: &str) -> Result<SdkTracerProvider, String> {
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(endpoint)
+            .build()
+            .map_err(|err| format!("failed to create the OTLP exporter: {}", err))?;
+        Ok(SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .build())
+    }
+
+    /// Runs `process` for `tx` inside a span, recording its outcome.
+    ///
+    /// # Arguments
+    ///
+    /// * `tx` - The processed transaction.
+    /// * `amount` - The amount of the transaction in budget units.
+    /// * `process` - The processing of the transaction.
+    pub fn trace(
+        &self,
+        tx: &impl Transaction,
+        amount: i64,
+        process: impl FnOnce() -> Result<States, ProcessError>,
+    ) -> Result<States, ProcessError> {
+        let id = tx.id();
+        let previous = self.failed_attempts.lock().unwrap().get(&id).cloned();
+        let (name, parent, attempt) = match previous {
+            Some((first, attempts)) => (
+                "retry",
+                Context::new().with_remote_span_context(first),
+                attempts + 1,
+            ),
+            None => ("process_transaction", Context::new(), 1),
+        };
+        let mut span = self
+            .tracer
+            .span_builder(name)
+            .with_kind(SpanKind::Internal)
+            .with_attributes([
+                KeyValue::new("tx.id", id as i64),
+                KeyValue::new("tx.token", tx.token().to_string()),
+                KeyValue::new("tx.amount", amount),
+                KeyValue::new("tx.attempt", attempt),
+            ])
+            .start_with_context(&self.tracer, &parent);
+
+        let result = process();
+
+        let outcome = match &result {
+            Ok(state) => ResultRecord::new(0, state).status,
+            Err(_) => "invalid",
+        };
+        span.set_attribute(KeyValue::new("tx.outcome", outcome));
+        match &result {
+            Ok(States::Failed { error, .. }) => span.set_status(Status::error(error.clone())),
+            Err(err) => span.set_status(Status::error(err.to_string())),
+            Ok(_) => {}
+        }
+
+        let mut failed_attempts = self.failed_attempts.lock().unwrap();
+        if outcome == "failed" {
+            let first = span.span_context().clone();
+            failed_attempts
+                .entry(id)
+                .and_modify(|(_, attempts)| *attempts = attempt)
+                .or_insert((first, attempt));
+        } else {
+            failed_attempts.remove(&id);
+        }
+        span.end();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::sync::Arc;
+
+    use opentelemetry::Value;
+    use opentelemetry_sdk::error::OTelSdkResult;
+    use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::limits::LimitChecker;
+    use crate::test_tx::StubTransaction;
+
+    /// Keeps the exported spans in memory.
+    #[derive(Debug, Clone, Default)]
+    struct Collector(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for Collector {
+        fn export(&self, batch: Vec<SpanData>) -> impl Future<Output = OTelSdkResult> + Send {
+            self.0.lock().unwrap().extend(batch);
+            async { Ok(()) }
+        }
+    }
+
+    fn attribute(span: &SpanData, key: &str) -> Value {
+        span.attributes
+            .iter()
+            .find(|attribute| attribute.key.as_str() == key)
+            .map(|attribute| attribute.value.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_spans_are_created_and_retries_nested() {
+        let collector = Collector::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(collector.clone())
+            .build();
+        let limiter = LimitChecker::new(&EnvParams {
+            total_amount: 1000,
+            max_transactions: 10,
+            ..Default::default()
+        })
+        .with_telemetry(Telemetry::new(&provider));
+
+        let tx = StubTransaction::new()
+            .with_price(100, 50)
+            .with_id(3)
+            .with_token("BONK")
+            .with_failures(2);
+        for _ in 0..3 {
+            limiter.process_transaction(&tx).unwrap();
+        }
+
+        let spans = collector.0.lock().unwrap().clone();
+        let names: Vec<_> = spans.iter().map(|span| span.name.as_ref()).collect();
+        assert_eq!(names, vec!["process_transaction", "retry", "retry"]);
+        let outcomes: Vec<_> = spans
+            .iter()
+            .map(|span| attribute(span, "tx.outcome").as_str().into_owned())
+            .collect();
+        assert_eq!(outcomes, vec!["failed", "failed", "success"]);
+
+        let first = &spans[0].span_context;
+        for retry in &spans[1..] {
+            assert_eq!(retry.parent_span_id, first.span_id());
+            assert_eq!(retry.span_context.trace_id(), first.trace_id());
+        }
+        assert_eq!(attribute(&spans[2], "tx.attempt"), Value::I64(3));
+        assert_eq!(attribute(&spans[0], "tx.token"), Value::from("BONK"));
+        assert_eq!(attribute(&spans[0], "tx.amount"), Value::I64(150));
+        assert!(matches!(spans[0].status, Status::Error { .. }));
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crate::tx::Transaction;
+
+/// Transaction of the unit tests: a price of 90 and a commission of 10, succeeding at once
+/// unless configured otherwise by the `with_*` methods.
+#[derive(Debug)]
+pub(crate) struct StubTransaction {
+    price: i64,
+    commission: i64,
+    id: u64,
+    token: &'static str,
+    /// Time every execution takes.
+    delay: Duration,
+    /// Number of executions failing before the first success.
+    failures: usize,
+    executions: AtomicUsize,
+}
+
+impl StubTransaction {
+    pub(crate) fn new() -> Self {
+        Self {
+            price: 90,
+            commission: 10,
+            id: 0,
+            token: "",
+            delay: Duration::ZERO,
+            failures: 0,
+            executions: AtomicUsize::new(0),
+        }
+    }
+
+    /// Sets the price and the commission.
+    pub(crate) fn with_price(self, price: i64, commission: i64) -> Self {
+        Self {
+            price,
+            commission,
+            ..self
+        }
+    }
+
+    pub(crate) fn with_id(self, id: u64) -> Self {
+        Self { id, ..self }
+    }
+
+    // Only the telemetry tests report a token.
+    #[cfg_attr(not(feature = "otel"), allow(dead_code))]
+    pub(crate) fn with_token(self, token: &'static str) -> Self {
+        Self { token, ..self }
+    }
+
+    /// Makes every execution take at least `delay`.
+    pub(crate) fn with_delay(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+
+    /// Fails the first `failures` executions with `rpc error`, `usize::MAX` failing them all.
+    pub(crate) fn with_failures(self, failures: usize) -> Self {
+        Self { failures, ..self }
+    }
+}
+
+impl Transaction for StubTransaction {
+    fn amount(&self) -> i64 {
+        self.price + self.commission
+    }
+
+    fn price(&self) -> i64 {
+        self.price
+    }
+
+    fn commission(&self) -> i64 {
+        self.commission
+    }
+
+    fn execute(&self) -> Result<String, String> {
+        if !self.delay.is_zero() {
+            thread::sleep(self.delay);
+        }
+        if self.executions.fetch_add(1, Ordering::SeqCst) < self.failures {
+            Err("rpc error".to_string())
+        } else {
+            Ok("signature".to_string())
+        }
+    }
+
+    fn info(&self) -> String {
+        "stub".to_string()
+    }
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+
+    fn token(&self) -> &str {
+        self.token
+    }
+}
@@ -1,51 +1,415 @@
-use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use derive_builder::Builder;
 use log::warn;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{rngs::StdRng, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Normal};
+use serde::Serialize;
 
-use crate::env_utils::EnvParams;
+use crate::env_utils::{parse_amount, EnvParams};
+use crate::signature::SignatureFormat;
 
-/// Trait that defines a transaction. 
+/// Failure probability of the default failure model, before any commission discount.
+pub const DEFAULT_FAILURE_RATE: f64 = 0.1;
+
+/// Number of transactions created from a set of `EnvParams` outside of a generator, which
+/// derive their draws from it. The clones of the parameters share the count.
+#[derive(Clone, Default)]
+pub struct StandaloneSequence(Arc<AtomicU64>);
+
+impl StandaloneSequence {
+    /// Returns the number of standalone transactions created before, counting a new one.
+    fn next(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for StandaloneSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StandaloneSequence")
+    }
+}
+
+/// Logs a `trace!` line prefixed with the transaction id when `TRACE_TX` is enabled.
+macro_rules! trace_tx {
+    ($params:expr, $id:expr, $($arg:tt)+) => {
+        if $params.trace_tx {
+            log::trace!("[tx {}] {}", $id, format_args!($($arg)+));
+        }
+    };
+}
+
+pub(crate) use trace_tx;
+
+/// Trait that defines a transaction.
 /// Implementations of this trait should define how to calculate the amount of the transaction, execute it, and provide info about it.
 pub trait Transaction {
     /// Returns the total amount of the transaction (price + commission).
     fn amount(&self) -> i64;
 
+    /// Returns the price part of the transaction amount.
+    fn price(&self) -> i64;
+
+    /// Returns the commission part of the transaction amount.
+    fn commission(&self) -> i64;
+
     /// Executes the transaction. Returns the transaction signature as `Ok(String)` if successful,
     /// or an error message as `Err(String)` if the transaction fails.
     fn execute(&self) -> Result<String, String>;
 
     /// Returns information about the transaction in the form of a string.
     fn info(&self) -> String;
+
+    /// Returns the identifier of the transaction, used to correlate logs.
+    fn id(&self) -> u64 {
+        0
+    }
+
+    /// Returns the token the transaction buys, used to share the threads between token campaigns.
+    fn token(&self) -> &str {
+        ""
+    }
+
+    /// Returns the id of the transaction that must succeed before this one executes, if any.
+    fn depends_on(&self) -> Option<u64> {
+        None
+    }
+
+    /// Returns the number of tokens the transaction buys, counted against `max_tokens`.
+    fn tokens(&self) -> i64 {
+        1
+    }
+
+    /// Returns the wallet paying for the transaction, counted against `per_wallet_amount_limit`.
+    fn wallet(&self) -> &str {
+        ""
+    }
+
+    /// Returns the memo the transaction carries on-chain, if any.
+    fn memo(&self) -> Option<String> {
+        None
+    }
+
+    /// Returns an owned copy executing on another thread, so that `TX_TIMEOUT_MS` can leave
+    /// a hung execution behind. Without one, the execution runs on the thread processing the
+    /// transaction and cannot be interrupted.
+    fn detach(&self) -> Option<Box<dyn Transaction + Send>> {
+        None
+    }
+}
+
+/// Step of a tiered fee schedule (`COMMISSION_TIERS`): transactions priced at
+/// `price_threshold` or more pay `commission`, unless a higher tier applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CommissionTier {
+    pub price_threshold: i64,
+    pub commission: i64,
+}
+
+impl CommissionTier {
+    /// Parses a `price_threshold:commission` pair of amounts with `decimals` decimal places,
+    /// e.g. `1k:25`.
+    pub fn parse(s: &str, decimals: u32) -> Result<Self, String> {
+        let (price_threshold, commission) = s
+            .split_once(':')
+            .ok_or_else(|| format!("invalid commission tier: {}", s))?;
+        Ok(Self {
+            price_threshold: parse_amount(price_threshold.trim(), decimals)?,
+            commission: parse_amount(commission.trim(), decimals)?,
+        })
+    }
+}
+
+/// Random number generator algorithm drawing the commissions (`RNG_ALGORITHM`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RngAlgorithm {
+    /// `rand`'s `StdRng`.
+    #[default]
+    Std,
+    /// ChaCha with 8 rounds, whose output is stable across `rand` releases.
+    ChaCha8,
+}
+
+impl FromStr for RngAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "std" => Ok(Self::Std),
+            "chacha8" => Ok(Self::ChaCha8),
+            other => Err(format!("unknown RNG algorithm: {}", other)),
+        }
+    }
+}
+
+impl RngAlgorithm {
+    /// Creates a generator of this algorithm, seeded with `seed` for a reproducible
+    /// sequence or from OS entropy otherwise.
+    pub fn build(self, seed: Option<u64>) -> Box<dyn RngCore + Send> {
+        match (self, seed) {
+            (Self::Std, Some(seed)) => Box::new(StdRng::seed_from_u64(seed)),
+            (Self::Std, None) => Box::new(StdRng::from_entropy()),
+            (Self::ChaCha8, Some(seed)) => Box::new(ChaCha8Rng::seed_from_u64(seed)),
+            (Self::ChaCha8, None) => Box::new(ChaCha8Rng::from_entropy()),
+        }
+    }
+}
+
+/// Distribution of the commissions around the base commission (`COMMISSION_DISTRIBUTION`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommissionDistribution {
+    /// Uniformly within `commission ± commission_change`.
+    #[default]
+    Uniform,
+    /// Normal with mean `commission` and standard deviation `commission_change`.
+    Normal,
+}
+
+impl FromStr for CommissionDistribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(Self::Uniform),
+            "normal" => Ok(Self::Normal),
+            other => Err(format!("unknown commission distribution: {}", other)),
+        }
+    }
+}
+
+/// Policy deciding the commission of a transaction from the configured base commission.
+pub trait CommissionAdjuster: Send + Sync {
+    /// Returns the adjusted commission for a new transaction.
+    fn adjust(&self, base: i64, rng: &mut dyn RngCore) -> i64;
+}
+
+/// Default adjuster: a uniformly random commission within `base ± change`.
+#[derive(Debug, Clone, Copy)]
+pub struct UniformJitter {
+    pub change: i64,
+}
+
+impl CommissionAdjuster for UniformJitter {
+    fn adjust(&self, base: i64, rng: &mut dyn RngCore) -> i64 {
+        base + rng.gen_range(-self.change..=self.change)
+    }
+}
+
+/// A normally distributed commission with mean `base` and standard deviation `std_dev`.
+#[derive(Debug, Clone, Copy)]
+pub struct NormalJitter {
+    pub std_dev: i64,
+}
+
+impl CommissionAdjuster for NormalJitter {
+    fn adjust(&self, base: i64, rng: &mut dyn RngCore) -> i64 {
+        let normal = Normal::new(base as f64, self.std_dev.max(0) as f64)
+            .expect("a non-negative standard deviation");
+        normal.sample(rng).round() as i64
+    }
+}
+
+/// The jitter of the `commission_distribution`, spread by `commission_change`.
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultJitter {
+    pub distribution: CommissionDistribution,
+    pub change: i64,
+}
+
+impl DefaultJitter {
+    /// Creates the jitter configured in `params`.
+    pub fn from_params(params: &EnvParams) -> Self {
+        Self {
+            distribution: params.commission_distribution,
+            change: params.commission_change,
+        }
+    }
+}
+
+impl CommissionAdjuster for DefaultJitter {
+    fn adjust(&self, base: i64, rng: &mut dyn RngCore) -> i64 {
+        match self.distribution {
+            CommissionDistribution::Uniform => UniformJitter {
+                change: self.change,
+            }
+            .adjust(base, rng),
+            CommissionDistribution::Normal => NormalJitter {
+                std_dev: self.change,
+            }
+            .adjust(base, rng),
+        }
+    }
+}
+
+/// Generator of the simulated failures and signatures of a transaction, seeded from the
+/// generator creating the transaction so that a run under `RNG_SEED` fails the same executions
+/// and signs the others alike.
+///
+/// A clone continues from the current state, and all are equal: the draws are not part of the
+/// transaction.
+pub struct FailureRng(Arc<Mutex<StdRng>>);
+
+impl FailureRng {
+    /// Seeds the failure draws from `rng`.
+    pub fn from_rng(rng: &mut dyn RngCore) -> Self {
+        Self(Arc::new(Mutex::new(StdRng::seed_from_u64(rng.next_u64()))))
+    }
+
+    /// Returns a handle on the same draws, so the retries of a detached execution keep drawing
+    /// new outcomes.
+    fn share(&self) -> Self {
+        Self(self.0.clone())
+    }
+
+    /// Draws whether an execution fails with probability `rate`.
+    pub fn fails(&self, rate: f64) -> bool {
+        self.0.lock().unwrap().gen_bool(rate.clamp(0.0, 1.0))
+    }
+
+    /// Draws the signature of a successful execution in `format`.
+    pub fn signature(&self, format: SignatureFormat) -> String {
+        format.fake(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl Default for FailureRng {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(StdRng::from_entropy())))
+    }
+}
+
+impl Clone for FailureRng {
+    fn clone(&self) -> Self {
+        Self(Arc::new(Mutex::new(self.0.lock().unwrap().clone())))
+    }
+}
+
+impl PartialEq for FailureRng {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl fmt::Debug for FailureRng {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FailureRng")
+    }
 }
 
 /// Struct representing a transaction with specific parameters such as wallet, token, adjusted commission, and price.
-#[derive(Builder, Default, Debug)]
+#[derive(Builder, Default, Debug, Clone, PartialEq)]
 pub struct SomeTransaction {
-    pub(crate) wallet: String,
-    pub(crate) token: String,
+    pub(crate) id: u64,
+    /// Shared with the other transactions of the generator, so generating one does not copy it.
+    pub(crate) wallet: Arc<str>,
+    pub(crate) token: Arc<str>,
     pub(crate) adjusted_commission: i64,
     pub(crate) price: i64,
+    /// Indices of transactions forced to fail on execution.
+    pub(crate) force_fail_indices: Arc<HashSet<u64>>,
+    /// Simulated failure probability per token, the default failure model is used for other tokens.
+    pub(crate) token_failure_rates: Arc<HashMap<String, f64>>,
+    /// Id of the transaction that must succeed before this one executes.
+    pub(crate) depends_on: Option<u64>,
+    /// Number of tokens bought by the transaction.
+    pub(crate) tokens: i64,
+    /// Encoding of the simulated signature, the transaction details are returned for `raw`.
+    pub(crate) signature_format: SignatureFormat,
+    /// Failure probability of the execution, `failure_rate` discounted by the commission-aware
+    /// failure model when `commission_failure_scale` is set.
+    pub(crate) failure_rate: f64,
+    /// Draws the simulated failures against `failure_rate`.
+    pub(crate) failure_rng: FailureRng,
+    /// Template of the memo, expanded with the id and the token of the transaction.
+    pub(crate) memo_template: Option<Arc<str>>,
 }
 
 impl SomeTransaction {
     /// Creates a new instance of `SomeTransaction`, adjusting the commission based on the provided parameters.
+    /// The base commission is looked up in the `commission_tiers` by price, when configured.
     ///
     /// # Arguments
     ///
     /// * `params` - A reference-counted pointer to `EnvParams` that contains the environment parameters for the transaction.
     pub fn new(params: &Arc<EnvParams>) -> Self {
-        let mut rng = StdRng::from_entropy();
-        let adjusted_commission =
-            params.commission + rng.gen_range(-params.commission_change..=params.commission_change);
+        Self::with_adjuster(params, &DefaultJitter::from_params(params))
+    }
+
+    /// Creates a new instance of `SomeTransaction` whose commission is decided by `adjuster`,
+    /// drawing from a fresh generator of the configured `rng_algorithm`, seeded from entropy or,
+    /// when `rng_seed` is set, from the seed mixed with the number of transactions created before
+    /// from `params`, so every standalone transaction draws its own commission and failures and
+    /// the same parameters draw the same ones in every run.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - A reference-counted pointer to `EnvParams` that contains the environment parameters for the transaction.
+    /// * `adjuster` - The policy adjusting the base commission.
+    pub fn with_adjuster(params: &Arc<EnvParams>, adjuster: &dyn CommissionAdjuster) -> Self {
+        let seed = params.rng_seed.map(|seed| {
+            let sequence = params.standalone_sequence.next();
+            seed ^ sequence.wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        });
+        Self::with_rng(params, adjuster, params.rng_algorithm.build(seed).as_mut())
+    }
+
+    /// Creates a new instance of `SomeTransaction` whose commission is decided by `adjuster`
+    /// drawing from `rng`, e.g. the seeded generator of a `TransactionGenerator`.
+    /// The commission is clamped at 0, a negative fee would shrink the budgeted amount.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - A reference-counted pointer to `EnvParams` that contains the environment parameters for the transaction.
+    /// * `adjuster` - The policy adjusting the base commission.
+    /// * `rng` - The random number generator the adjuster draws from.
+    pub fn with_rng(
+        params: &Arc<EnvParams>,
+        adjuster: &dyn CommissionAdjuster,
+        rng: &mut dyn RngCore,
+    ) -> Self {
+        Self::with_names(
+            params,
+            adjuster,
+            rng,
+            params.wallet.as_str().into(),
+            params.token.as_str().into(),
+        )
+    }
+
+    /// Creates a new instance of `SomeTransaction` for already shared `wallet` and `token`
+    /// names, which lets a generator create transactions without allocating them again.
+    pub(crate) fn with_names(
+        params: &Arc<EnvParams>,
+        adjuster: &dyn CommissionAdjuster,
+        rng: &mut dyn RngCore,
+        wallet: Arc<str>,
+        token: Arc<str>,
+    ) -> Self {
+        let adjusted_commission = adjuster
+            .adjust(params.base_commission(params.price), rng)
+            .max(0);
 
         Self {
-            wallet: params.wallet.clone(),
-            token: params.token.clone(),
+            id: 0,
+            wallet,
+            token,
             adjusted_commission,
             price: params.price,
+            force_fail_indices: params.force_fail_indices.clone(),
+            token_failure_rates: params.token_failure_rates.clone(),
+            depends_on: None,
+            tokens: 1,
+            signature_format: params.signature_format,
+            failure_rate: params.failure_probability(adjusted_commission),
+            failure_rng: FailureRng::from_rng(rng),
+            memo_template: params.memo_template.clone(),
         }
     }
 }
@@ -56,35 +420,333 @@ impl Transaction for SomeTransaction {
         self.adjusted_commission + self.price
     }
 
-    /// Executes the transaction. There is a small probability of failure determined by the current time in nanoseconds.
-    /// If the transaction fails, it logs a warning and returns an error message. Otherwise, it returns the transaction information.
+    fn price(&self) -> i64 {
+        self.price
+    }
+
+    fn commission(&self) -> i64 {
+        self.adjusted_commission
+    }
+
+    fn token(&self) -> &str {
+        &self.token
+    }
+
+    fn depends_on(&self) -> Option<u64> {
+        self.depends_on
+    }
+
+    fn tokens(&self) -> i64 {
+        self.tokens
+    }
+
+    fn wallet(&self) -> &str {
+        &self.wallet
+    }
+
+    /// Returns the `memo_template` with `{id}` and `{token}` replaced by those of the transaction.
+    fn memo(&self) -> Option<String> {
+        self.memo_template.as_deref().map(|template| {
+            template
+                .replace("{id}", &self.id.to_string())
+                .replace("{token}", &self.token)
+        })
+    }
+
+    /// Detaches a copy drawing its failures from the same `failure_rng`.
+    fn detach(&self) -> Option<Box<dyn Transaction + Send>> {
+        Some(Box::new(Self {
+            failure_rng: self.failure_rng.share(),
+            ..self.clone()
+        }))
+    }
+
+    /// Executes the transaction. It fails with the probability of its token in `TOKEN_FAILURE_RATES`,
+    /// or its `failure_rate` otherwise, drawn from its seeded `failure_rng`,
+    /// and transactions whose id is listed in `FORCE_FAIL_INDICES` always fail.
+    /// If the transaction fails, it logs a warning and returns an error message. Otherwise, it returns the transaction information
+    /// with its memo,
+    /// or a fake signature in the `signature_format` when one is set, drawn from the `failure_rng` as well.
     fn execute(&self) -> Result<String, String> {
-        let fail_condition = || {
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .subsec_nanos()
-                % 10
-                == 0
-        };
+        if self.force_fail_indices.contains(&self.id) {
+            warn!("FAIL (forced at index {})", self.id);
+            return Err(format!("forced failure at index {}", self.id));
+        }
+
+        let rate = self
+            .token_failure_rates
+            .get(&*self.token)
+            .copied()
+            .unwrap_or(self.failure_rate);
 
-        if fail_condition() {
+        if self.failure_rng.fails(rate) {
             warn!("FAIL");
             Err("failed tx".to_string())
+        } else if self.signature_format == SignatureFormat::Raw {
+            // The details alone repeat across transactions, the id keeps the signature unique.
+            Ok(format!("{}, Id: {}", self.info(), self.id))
         } else {
-            Ok(self.info())
+            Ok(self.failure_rng.signature(self.signature_format))
         }
     }
 
-    /// Provides a formatted string with details about the transaction, including the wallet, token, commission, price, and total amount.
+    /// Provides a formatted string with details about the transaction, including the wallet, token, commission, price, and total amount,
+    /// followed by the memo when one is configured.
     fn info(&self) -> String {
-        format!(
+        let info = format!(
             "Wallet: {}, Token: {}, Commission: {}, Price: {}, Amount: {}",
             self.wallet,
             self.token,
             self.adjusted_commission,
             self.price,
             self.amount()
-        )
+        );
+        match self.memo() {
+            Some(memo) => format!("{}, Memo: {}", info, memo),
+            None => info,
+        }
+    }
+
+    fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx_genertor::TransactionGenerator;
+
+    #[test]
+    fn test_commission_distributions_spread_around_the_commission() {
+        let commissions = |commission_distribution| {
+            let params = Arc::new(EnvParams {
+                price: 1_000,
+                commission: 100,
+                commission_change: 20,
+                commission_distribution,
+                rng_seed: Some(3),
+                ..Default::default()
+            });
+            TransactionGenerator::new(params)
+                .take(10_000)
+                .map(|tx| tx.adjusted_commission)
+                .collect::<Vec<_>>()
+        };
+
+        let uniform = commissions(CommissionDistribution::Uniform);
+        assert!(uniform
+            .iter()
+            .all(|commission| (80..=120).contains(commission)));
+
+        let normal = commissions(CommissionDistribution::Normal);
+        let mean = normal.iter().sum::<i64>() as f64 / normal.len() as f64;
+        assert!((mean - 100.0).abs() < 1.0, "mean {}", mean);
+        // Unlike the uniform draws, about 5% of the normal ones fall beyond 2 deviations.
+        assert!(normal
+            .iter()
+            .any(|commission| !(60..=140).contains(commission)));
+        assert_eq!("normal".parse(), Ok(CommissionDistribution::Normal));
+    }
+
+    #[test]
+    fn test_forced_failures_at_indices() {
+        let params = Arc::new(EnvParams {
+            price: 100,
+            commission: 10,
+            force_fail_indices: Arc::new(HashSet::from([2, 5])),
+            ..Default::default()
+        });
+
+        let outcomes: Vec<_> = TransactionGenerator::new(params)
+            .take(7)
+            .map(|tx| (tx.id, tx.execute()))
+            .collect();
+
+        for (id, outcome) in outcomes {
+            match id {
+                2 | 5 => assert_eq!(outcome, Err(format!("forced failure at index {}", id))),
+                _ => assert!(!matches!(outcome, Err(err) if err.starts_with("forced"))),
+            }
+        }
+    }
+
+    #[test]
+    fn test_failure_rates_per_token() {
+        let params = Arc::new(EnvParams {
+            price: 100,
+            commission: 10,
+            tokens: vec!["A".to_string(), "B".to_string()],
+            token_failure_rates: Arc::new(HashMap::from([
+                ("A".to_string(), 0.5),
+                ("B".to_string(), 0.1),
+            ])),
+            ..Default::default()
+        });
+
+        let mut failures = HashMap::new();
+        for tx in TransactionGenerator::new(params).take(2000) {
+            if tx.execute().is_err() {
+                *failures.entry(tx.token.to_string()).or_insert(0) += 1;
+            }
+        }
+
+        // 1000 transactions per token: ~500 failures for A and ~100 for B.
+        assert!((400..=600).contains(&failures["A"]), "{:?}", failures);
+        assert!((50..=150).contains(&failures["B"]), "{:?}", failures);
+    }
+
+    #[test]
+    fn test_seeded_failure_rate_is_reproducible() {
+        let failures = |failure_rate| {
+            let params = Arc::new(EnvParams {
+                price: 100,
+                commission: 10,
+                commission_change: 5,
+                rng_seed: Some(7),
+                failure_rate: Some(failure_rate),
+                ..Default::default()
+            });
+            TransactionGenerator::new(params)
+                .take(200)
+                .map(|tx| tx.execute().is_err())
+                .collect::<Vec<_>>()
+        };
+
+        assert!(failures(0.0).iter().all(|failed| !failed));
+        assert!(failures(1.0).iter().all(|failed| *failed));
+        let half = failures(0.5);
+        assert_eq!(failures(0.5), half);
+        assert!((60..=140).contains(&half.iter().filter(|failed| **failed).count()));
+    }
+
+    #[test]
+    fn test_seeded_signatures_are_reproducible() {
+        let signatures = || {
+            let params = Arc::new(EnvParams {
+                rng_seed: Some(7),
+                failure_rate: Some(0.0),
+                signature_format: SignatureFormat::Base58,
+                ..Default::default()
+            });
+            TransactionGenerator::new(params)
+                .take(20)
+                .map(|tx| tx.execute().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        let first = signatures();
+        assert_eq!(signatures(), first);
+        assert_eq!(first.iter().collect::<HashSet<_>>().len(), first.len());
+    }
+
+    #[test]
+    fn test_break_even_commission_reaches_the_target_rate() {
+        let model = EnvParams {
+            price: 100,
+            commission_failure_scale: Some(50),
+            ..Default::default()
+        };
+        // Already reached by the default 90% success.
+        assert_eq!(model.break_even_commission(0.8), 0);
+        assert_eq!(EnvParams::default().break_even_commission(0.99), i64::MAX);
+
+        let commission = model.break_even_commission(0.98);
+        assert_eq!(commission, 81);
+        let params = Arc::new(EnvParams {
+            commission,
+            ..model
+        });
+        let failures = TransactionGenerator::new(params)
+            .take(20_000)
+            .filter(|tx| tx.execute().is_err())
+            .count();
+
+        // ~400 failures for a 2% failure rate.
+        assert!((300..=500).contains(&failures), "{} failures", failures);
+    }
+
+    #[test]
+    fn test_memo_template_is_expanded_per_transaction() {
+        let params = Arc::new(EnvParams {
+            price: 100,
+            commission: 10,
+            tokens: vec!["A".to_string(), "B".to_string()],
+            token_failure_rates: Arc::new(HashMap::from([("A".to_string(), 0.0)])),
+            memo_template: Some("campaign-{token}/{id}".into()),
+            ..Default::default()
+        });
+
+        let transactions: Vec<_> = TransactionGenerator::new(params).take(3).collect();
+        let memos: Vec<_> = transactions.iter().map(|tx| tx.memo().unwrap()).collect();
+        assert_eq!(memos, ["campaign-A/0", "campaign-B/1", "campaign-A/2"]);
+        assert!(transactions[1].info().ends_with(", Memo: campaign-B/1"));
+        assert_eq!(
+            transactions[2].execute(),
+            Ok(format!("{}, Id: 2", transactions[2].info()))
+        );
+        assert_eq!(SomeTransaction::new(&Arc::default()).memo(), None);
+    }
+
+    #[test]
+    fn test_seeded_standalone_transactions_draw_their_own_commissions() {
+        let params = Arc::new(EnvParams {
+            price: 1_000,
+            commission: 100,
+            commission_change: 50,
+            rng_seed: Some(11),
+            ..Default::default()
+        });
+
+        let commissions: HashSet<_> = (0..20)
+            .map(|_| SomeTransaction::new(&params).commission())
+            .collect();
+        assert!(commissions.len() > 1, "{:?}", commissions);
+    }
+
+    #[test]
+    fn test_standalone_draws_do_not_depend_on_other_parameters() {
+        let params = || {
+            Arc::new(EnvParams {
+                price: 1_000,
+                commission: 100,
+                commission_change: 50,
+                rng_seed: Some(11),
+                ..Default::default()
+            })
+        };
+        let commissions = |params: &Arc<EnvParams>| {
+            (0..10)
+                .map(|_| SomeTransaction::new(params).commission())
+                .collect::<Vec<_>>()
+        };
+
+        let first = commissions(&params());
+        // Transactions of other parameters created in between are not counted.
+        commissions(&params());
+        assert_eq!(commissions(&params()), first);
+    }
+
+    #[test]
+    fn test_commission_is_selected_by_price_tier() {
+        let tiers = ["1k:50", "100:20", "10k:80"]
+            .map(|tier| CommissionTier::parse(tier, 0).unwrap())
+            .to_vec();
+        let commission = |price| {
+            let params = Arc::new(EnvParams {
+                price,
+                commission: 10,
+                commission_tiers: tiers.clone(),
+                ..Default::default()
+            });
+            SomeTransaction::new(&params).commission()
+        };
+
+        assert_eq!(commission(50), 10);
+        assert_eq!(commission(100), 20);
+        assert_eq!(commission(999), 20);
+        assert_eq!(commission(1_000), 50);
+        assert_eq!(commission(25_000), 80);
+        assert!(CommissionTier::parse("1k", 0).is_err());
     }
 }
@@ -1,17 +1,74 @@
+use std::cmp::Reverse;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use crate::backoff::AdaptiveThrottle;
+use crate::bidding::Bidding;
 use crate::env_utils::EnvParams;
-use crate::tx::SomeTransaction;
+use crate::tx::{trace_tx, CommissionAdjuster, DefaultJitter, FailureRng, SomeTransaction};
 
 use derive_builder::Builder;
+use rand::RngCore;
+use serde::Deserialize;
 use SomeTransaction as Transaction;
 
 /// A generator that creates an infinite stream of transactions
 /// using the specified parameters.
 #[derive(Default, derive_new::new, Builder)]
-pub(crate) struct TransactionGenerator {
+pub struct TransactionGenerator {
     /// Environment parameters containing information about the wallet, token, etc.
-    pub(crate) params: Arc<EnvParams>,
+    pub params: Arc<EnvParams>,
+    /// Optional bidding strategy overriding the random commission of each transaction.
+    #[new(default)]
+    #[builder(default)]
+    pub bidding: Option<Bidding>,
+    /// Number of leading transactions to discard before yielding, e.g. to resume a replay.
+    #[new(default)]
+    #[builder(default)]
+    pub skip: usize,
+    /// Optional commission policy; the uniform `commission ± commission_change` jitter is used when unset.
+    #[new(default)]
+    #[builder(default)]
+    pub adjuster: Option<Arc<dyn CommissionAdjuster>>,
+    /// Identifier assigned to the next generated transaction, skipped ones included.
+    #[new(default)]
+    #[builder(default)]
+    pub next_id: u64,
+    /// Optional completion flag of the limiter (`LimitChecker::completed`);
+    /// generation stops as soon as it is set.
+    #[new(default)]
+    #[builder(default)]
+    pub completed: Option<Arc<AtomicBool>>,
+    /// Optional throttle slowing the generation down while the recent failure rate is high.
+    #[new(default)]
+    #[builder(default)]
+    pub backoff: Option<AdaptiveThrottle>,
+    /// Random number generator shared by the generated transactions, created on first use
+    /// from `rng_algorithm` and `rng_seed`.
+    #[new(default)]
+    #[builder(setter(skip))]
+    rng: Option<Box<dyn RngCore + Send>>,
+    /// Wallet names shared by the generated transactions, `wallets` or else `wallet`.
+    #[new(default)]
+    #[builder(setter(skip))]
+    wallet_names: Vec<Arc<str>>,
+    /// Token names shared by the generated transactions, `tokens` or else `token`.
+    #[new(default)]
+    #[builder(setter(skip))]
+    token_names: Vec<Arc<str>>,
+    /// Start of the throttled generation and number of transactions yielded since,
+    /// when `max_generation_rate` is set.
+    #[new(default)]
+    #[builder(setter(skip))]
+    throttle: Option<(Instant, u32)>,
+    /// Smoothed commission of the previous transaction, when `commission_ema_alpha` is set.
+    #[new(default)]
+    #[builder(setter(skip))]
+    last_commission: Option<f64>,
 }
 
 impl Iterator for TransactionGenerator {
@@ -20,9 +77,671 @@ impl Iterator for TransactionGenerator {
     /// Returns the next transaction in the sequence.
     ///
     /// # Returns
-    /// `Option<Transaction>` - A new transaction based on the current parameters.
+    /// `Option<Transaction>` - A new transaction based on the current parameters,
+    /// or `None` once the limiter has completed.
     fn next(&mut self) -> Option<Self::Item> {
-        let tx = Transaction::new(&self.params);
+        if self
+            .completed
+            .as_ref()
+            .is_some_and(|completed| completed.load(Ordering::SeqCst))
+        {
+            return None;
+        }
+        while self.skip > 0 {
+            self.skip -= 1;
+            self.generate();
+        }
+        self.throttle();
+        if let Some(backoff) = &mut self.backoff {
+            backoff.wait();
+        }
+        Some(self.generate())
+    }
+}
+
+impl TransactionGenerator {
+    /// Generates `n` transactions up front, e.g. to replay the exact same set across runs.
+    ///
+    /// # Arguments
+    /// * `n` - The number of transactions to generate.
+    pub fn materialize(&mut self, n: usize) -> Vec<Transaction> {
+        self.take(n).collect()
+    }
+
+    /// Sleeps until the next transaction is due under `max_generation_rate`, if set,
+    /// so that generation does not take the CPU from the executing threads.
+    fn throttle(&mut self) {
+        let Some(rate) = self.params.max_generation_rate.filter(|rate| *rate > 0.0) else {
+            return;
+        };
+        let (started, yielded) = self.throttle.get_or_insert_with(|| (Instant::now(), 0));
+        let due = Duration::try_from_secs_f64(f64::from(*yielded) / rate)
+            .ok()
+            .and_then(|offset| started.checked_add(offset));
+        *yielded += 1;
+        if let Some(wait) = due.and_then(|due| due.checked_duration_since(Instant::now())) {
+            std::thread::sleep(wait);
+        }
+    }
+
+    /// Creates the next transaction, rotating the `wallets` and interleaving the `tokens`
+    /// campaigns round-robin. The drawn commission is smoothed with `commission_ema_alpha`
+    /// and then overridden by the bidding strategy, if configured, before the failure
+    /// probability is derived from it.
+    /// The wallet and token names are shared between the transactions instead of cloned.
+    fn generate(&mut self) -> Transaction {
+        let params = &self.params;
+        let rng = self
+            .rng
+            .get_or_insert_with(|| params.rng_algorithm.build(params.rng_seed));
+        if self.wallet_names.is_empty() {
+            self.wallet_names = shared_names(&params.wallets, &params.wallet);
+        }
+        if self.token_names.is_empty() {
+            self.token_names = shared_names(&params.tokens, &params.token);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        let pick = |names: &[Arc<str>]| names[(id % names.len() as u64) as usize].clone();
+        let (wallet, token) = (pick(&self.wallet_names), pick(&self.token_names));
+
+        let jitter = DefaultJitter::from_params(params);
+        let adjuster = self.adjuster.as_deref().unwrap_or(&jitter);
+        let mut tx = Transaction::with_names(params, adjuster, rng.as_mut(), wallet, token);
+        if let Some(alpha) = params.commission_ema_alpha {
+            let draw = tx.adjusted_commission as f64;
+            let smoothed = self
+                .last_commission
+                .map_or(draw, |last| alpha * draw + (1.0 - alpha) * last);
+            self.last_commission = Some(smoothed);
+            tx.adjusted_commission = smoothed.round() as i64;
+        }
+        if let Some(bidding) = &self.bidding {
+            tx.adjusted_commission = bidding.bid().max(0);
+        }
+        // The commission-aware failure model applies to the commission actually paid.
+        tx.failure_rate = params.failure_probability(tx.adjusted_commission);
+        tx.id = id;
+
+        trace_tx!(
+            self.params,
+            tx.id,
+            "generated: price = {}, commission = {}",
+            tx.price,
+            tx.adjusted_commission
+        );
+        tx
+    }
+}
+
+/// Returns the shared copies of `names`, or of `default` alone when `names` is empty.
+fn shared_names(names: &[String], default: &str) -> Vec<Arc<str>> {
+    if names.is_empty() {
+        vec![default.into()]
+    } else {
+        names.iter().map(|name| name.as_str().into()).collect()
+    }
+}
+
+/// A generator replaying a fixed, pre-generated set of transactions.
+/// Cloning it is cheap and every clone replays the set from the start.
+#[derive(Debug, Clone)]
+pub struct CachedGenerator {
+    transactions: Arc<[Transaction]>,
+    position: usize,
+}
+
+impl CachedGenerator {
+    /// Creates a generator replaying `transactions` in order.
+    pub fn new(transactions: Vec<Transaction>) -> Self {
+        Self {
+            transactions: transactions.into(),
+            position: 0,
+        }
+    }
+
+    /// Loads the transactions to replay from a JSON array of records (`REPLAY_PATH`),
+    /// e.g. `[{ "id": 0, "price": 100, "commission": 12 }]`.
+    /// The wallet, and the token of the records without one, are taken from `params`.
+    ///
+    /// # Arguments
+    /// * `path` - The replay file.
+    /// * `params` - The parameters of the run.
+    pub fn load(path: impl AsRef<Path>, params: &Arc<EnvParams>) -> Result<Self, GeneratorError> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path).map_err(|err| {
+            GeneratorError::Read(format!("failed to read {}: {}", path.display(), err))
+        })?;
+        let records: Vec<ReplayRecord> = serde_json::from_str(&json).map_err(|err| {
+            GeneratorError::Parse(format!("failed to parse {}: {}", path.display(), err))
+        })?;
+
+        let wallet: Arc<str> = params.wallet.as_str().into();
+        let mut rng = params.rng_algorithm.build(params.rng_seed);
+        let transactions = records
+            .into_iter()
+            .map(|record| Transaction {
+                id: record.id,
+                wallet: wallet.clone(),
+                token: record.token.as_deref().unwrap_or(&params.token).into(),
+                adjusted_commission: record.commission,
+                price: record.price,
+                force_fail_indices: params.force_fail_indices.clone(),
+                token_failure_rates: params.token_failure_rates.clone(),
+                depends_on: record.depends_on,
+                tokens: 1,
+                signature_format: params.signature_format,
+                failure_rate: params.failure_probability(record.commission),
+                failure_rng: FailureRng::from_rng(rng.as_mut()),
+                memo_template: params.memo_template.clone(),
+            })
+            .collect();
+        Ok(Self::new(transactions))
+    }
+
+    /// Returns a fresh generator replaying the same set from the start.
+    pub fn replay(&self) -> Self {
+        Self {
+            transactions: self.transactions.clone(),
+            position: 0,
+        }
+    }
+}
+
+/// A transaction of a replay file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReplayRecord {
+    id: u64,
+    #[serde(default)]
+    token: Option<String>,
+    price: i64,
+    commission: i64,
+    #[serde(default)]
+    depends_on: Option<u64>,
+}
+
+/// Errors raised while constructing a generator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneratorError {
+    /// The source of the transactions could not be read.
+    Read(String),
+    /// The source is not valid JSON or does not match the record layout.
+    Parse(String),
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeneratorError::Read(message) | GeneratorError::Parse(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+impl Iterator for CachedGenerator {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let tx = self.transactions.get(self.position)?.clone();
+        self.position += 1;
         Some(tx)
     }
 }
+
+/// Policy picking the child generator of a `MergedGenerator` each transaction is pulled from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergePolicy {
+    /// One transaction from each child in turn.
+    RoundRobin,
+    /// Transactions in proportion to the weight of each child, in the order given, spread
+    /// evenly (smooth weighted round-robin); a missing weight counts as 1, a zero weight
+    /// disables the child.
+    Weighted(Vec<u32>),
+}
+
+/// Merges several generators, e.g. one per strategy, into a single stream pulled from
+/// per the `MergePolicy`. An exhausted child is skipped; the stream ends with the last one.
+///
+/// The merged transactions are numbered again in the merged order, so their ids stay unique.
+#[derive(Debug)]
+pub struct MergedGenerator<G = TransactionGenerator> {
+    children: Vec<MergedChild<G>>,
+    round_robin: bool,
+    /// Child pulled from next under `RoundRobin`.
+    cursor: usize,
+    next_id: u64,
+}
+
+#[derive(Debug)]
+struct MergedChild<G> {
+    generator: G,
+    weight: i64,
+    /// Running credit of the smooth weighted round-robin.
+    credit: i64,
+    exhausted: bool,
+}
+
+impl<G: Iterator<Item = Transaction>> MergedGenerator<G> {
+    /// Creates a generator pulling from `generators` per `policy`.
+    pub fn new(generators: Vec<G>, policy: MergePolicy) -> Self {
+        let weights = match &policy {
+            MergePolicy::RoundRobin => Vec::new(),
+            MergePolicy::Weighted(weights) => weights.clone(),
+        };
+        let children = generators
+            .into_iter()
+            .enumerate()
+            .map(|(index, generator)| {
+                let weight = i64::from(weights.get(index).copied().unwrap_or(1));
+                MergedChild {
+                    generator,
+                    weight,
+                    credit: 0,
+                    exhausted: weight == 0,
+                }
+            })
+            .collect();
+        Self {
+            children,
+            round_robin: policy == MergePolicy::RoundRobin,
+            cursor: 0,
+            next_id: 0,
+        }
+    }
+
+    /// Returns the index of the next live child per the policy, crediting the weighted ones.
+    fn pick(&mut self) -> Option<usize> {
+        let count = self.children.len();
+        if self.round_robin {
+            let index = (0..count)
+                .map(|offset| (self.cursor + offset) % count)
+                .find(|index| !self.children[*index].exhausted)?;
+            self.cursor = (index + 1) % count;
+            return Some(index);
+        }
+
+        let mut total = 0;
+        for child in self.children.iter_mut().filter(|child| !child.exhausted) {
+            child.credit += child.weight;
+            total += child.weight;
+        }
+        // The first child with the highest credit.
+        let index = (0..count)
+            .filter(|index| !self.children[*index].exhausted)
+            .min_by_key(|index| Reverse(self.children[*index].credit))?;
+        self.children[index].credit -= total;
+        Some(index)
+    }
+}
+
+impl<G: Iterator<Item = Transaction>> Iterator for MergedGenerator<G> {
+    type Item = Transaction;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(index) = self.pick() {
+            let child = &mut self.children[index];
+            match child.generator.next() {
+                Some(mut tx) => {
+                    tx.id = self.next_id;
+                    self.next_id += 1;
+                    return Some(tx);
+                }
+                None => child.exhausted = true,
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicI64;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::bidding::{BiddingStrategy, RunProgress};
+    use crate::limits::{LimitChecker, States};
+    use crate::tx::RngAlgorithm;
+
+    /// Bids 1, 2, 3, ... so that each generated transaction is numbered by its commission.
+    #[derive(Default)]
+    struct Counter(AtomicI64);
+
+    impl BiddingStrategy for Counter {
+        fn bid(&self, _: i64, _: i64, _: &RunProgress) -> i64 {
+            self.0.fetch_add(1, Ordering::SeqCst) + 1
+        }
+    }
+
+    #[test]
+    fn test_skip_discards_leading_transactions() {
+        let params = Arc::new(EnvParams::default());
+        let bidding = Bidding::new(
+            Arc::new(Counter::default()),
+            Arc::new(LimitChecker::new(&params)),
+            Duration::from_secs(1),
+        );
+        let generator = TransactionGeneratorBuilder::default()
+            .params(params)
+            .bidding(Some(bidding))
+            .skip(3)
+            .build()
+            .unwrap();
+
+        let commissions: Vec<_> = generator.take(2).map(|tx| tx.adjusted_commission).collect();
+        assert_eq!(commissions, vec![4, 5]);
+    }
+
+    struct Constant(i64);
+
+    impl CommissionAdjuster for Constant {
+        fn adjust(&self, _: i64, _: &mut dyn RngCore) -> i64 {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_custom_commission_adjuster() {
+        let params = Arc::new(EnvParams {
+            commission: 100,
+            commission_change: 50,
+            price: 10,
+            ..Default::default()
+        });
+        let generator = TransactionGeneratorBuilder::default()
+            .params(params)
+            .adjuster(Some(Arc::new(Constant(42))))
+            .build()
+            .unwrap();
+
+        assert!(generator.take(20).all(|tx| tx.adjusted_commission == 42));
+    }
+
+    #[test]
+    fn test_materialized_set_is_replayed_identically() {
+        let params = Arc::new(EnvParams {
+            commission: 100,
+            commission_change: 50,
+            price: 10,
+            total_amount: 100_000,
+            max_transactions: 100,
+            ..Default::default()
+        });
+        let materialized = TransactionGenerator::new(params.clone()).materialize(10);
+        let cached = CachedGenerator::new(materialized.clone());
+
+        let first: Vec<_> = cached.replay().collect();
+        let second: Vec<_> = cached.replay().collect();
+        assert_eq!(first, materialized);
+        assert_eq!(second, materialized);
+
+        let runs: Vec<Vec<i64>> = (0..2)
+            .map(|_| {
+                let limiter = LimitChecker::new(&params);
+                cached
+                    .replay()
+                    .map(|tx| {
+                        limiter.process_transaction(&tx).unwrap();
+                        tx.adjusted_commission
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(runs[0], runs[1]);
+    }
+
+    #[test]
+    fn test_generation_stops_once_limiter_completes() {
+        let params = Arc::new(EnvParams {
+            commission: 100,
+            price: 10,
+            total_amount: 100_000,
+            max_transactions: 2,
+            ..Default::default()
+        });
+        let limiter = LimitChecker::new(&params);
+        let mut generator = TransactionGeneratorBuilder::default()
+            .params(params)
+            .completed(Some(limiter.completed.clone()))
+            .build()
+            .unwrap();
+
+        let mut states = Vec::new();
+        for tx in generator.by_ref() {
+            let state = limiter.process_transaction(&tx).unwrap();
+            states.push(state);
+            if states.len() > 10 {
+                break;
+            }
+        }
+
+        // Two committed (plus any failed executions), then the count cap is hit
+        // and nothing is generated afterwards.
+        let failed = states
+            .iter()
+            .filter(|state| matches!(state, States::Failed { .. }))
+            .count();
+        assert_eq!(states.len(), 3 + failed);
+        assert_eq!(states.last(), Some(&States::Finish));
+        assert_eq!(generator.next_id, states.len() as u64);
+        assert!(generator.next().is_none());
+    }
+
+    #[test]
+    fn test_tokens_are_interleaved() {
+        let params = Arc::new(EnvParams {
+            token: "unused".to_string(),
+            tokens: vec!["A".to_string(), "B".to_string()],
+            ..Default::default()
+        });
+        let tokens: Vec<_> = TransactionGenerator::new(params)
+            .take(4)
+            .map(|tx| tx.token.to_string())
+            .collect();
+        assert_eq!(tokens, vec!["A", "B", "A", "B"]);
+    }
+
+    #[test]
+    fn test_seeded_rng_algorithms_are_reproducible() {
+        let commissions = |rng_algorithm, rng_seed| {
+            let params = Arc::new(EnvParams {
+                commission: 1_000,
+                commission_change: 500,
+                rng_algorithm,
+                rng_seed,
+                ..Default::default()
+            });
+            TransactionGenerator::new(params)
+                .take(20)
+                .map(|tx| tx.adjusted_commission)
+                .collect::<Vec<_>>()
+        };
+
+        for algorithm in [RngAlgorithm::Std, RngAlgorithm::ChaCha8] {
+            assert_eq!(
+                commissions(algorithm, Some(7)),
+                commissions(algorithm, Some(7))
+            );
+            assert_ne!(
+                commissions(algorithm, Some(7)),
+                commissions(algorithm, Some(8))
+            );
+        }
+        assert_ne!(
+            commissions(RngAlgorithm::Std, Some(7)),
+            commissions(RngAlgorithm::ChaCha8, Some(7))
+        );
+        assert_eq!("chacha8".parse(), Ok(RngAlgorithm::ChaCha8));
+    }
+
+    #[test]
+    fn test_generated_transactions_share_their_names() {
+        let params = Arc::new(EnvParams {
+            wallet: "W".to_string(),
+            tokens: vec!["A".to_string(), "B".to_string()],
+            ..Default::default()
+        });
+        let txs = TransactionGenerator::new(params).materialize(6);
+
+        // One allocation per distinct name, however many transactions are generated;
+        // the generator is gone, so the transactions hold the only references.
+        assert!(txs.iter().all(|tx| Arc::ptr_eq(&tx.wallet, &txs[0].wallet)));
+        assert!(Arc::ptr_eq(&txs[0].token, &txs[4].token));
+        assert!(Arc::ptr_eq(&txs[1].token, &txs[5].token));
+        assert_eq!(Arc::strong_count(&txs[0].wallet), 6);
+        assert_eq!(Arc::strong_count(&txs[0].token), 3);
+    }
+
+    #[test]
+    fn test_generation_rate_is_throttled() {
+        let params = Arc::new(EnvParams {
+            max_generation_rate: Some(200.0),
+            ..Default::default()
+        });
+        let started = std::time::Instant::now();
+        assert_eq!(TransactionGenerator::new(params).take(11).count(), 11);
+        // The first transaction is immediate, the next 10 are 5ms apart.
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_replay_file_errors_are_reported() {
+        let params = Arc::new(EnvParams {
+            token: "T".to_string(),
+            ..Default::default()
+        });
+        let dir = std::env::temp_dir();
+
+        let missing = dir.join(format!("missing-replay-{}.json", std::process::id()));
+        let err = CachedGenerator::load(&missing, &params).unwrap_err();
+        assert!(matches!(&err, GeneratorError::Read(_)));
+        assert!(err
+            .to_string()
+            .starts_with(&format!("failed to read {}: ", missing.display())));
+
+        let path = dir.join(format!("replay-{}.json", std::process::id()));
+        std::fs::write(&path, r#"[{ "id": 1, "price": 100 }]"#).unwrap();
+        let err = CachedGenerator::load(&path, &params).unwrap_err();
+        assert!(matches!(err, GeneratorError::Parse(message) if message.contains("commission")));
+
+        std::fs::write(
+            &path,
+            r#"[{ "id": 1, "price": 100, "commission": 5 }, { "id": 2, "token": "U", "price": 90, "commission": 7 }]"#,
+        )
+        .unwrap();
+        let replayed: Vec<_> = CachedGenerator::load(&path, &params).unwrap().collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(
+            (&*replayed[0].token, replayed[0].adjusted_commission),
+            ("T", 5)
+        );
+        assert_eq!((&*replayed[1].token, replayed[1].id), ("U", 2));
+    }
+
+    #[test]
+    fn test_merged_generators_are_interleaved_per_policy() {
+        let generator = |token: &str, count| {
+            let params = Arc::new(EnvParams {
+                token: token.to_string(),
+                ..Default::default()
+            });
+            TransactionGenerator::new(params).take(count)
+        };
+        let tokens = |merged: MergedGenerator<_>| {
+            merged
+                .map(|tx| (tx.id, tx.token.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let round_robin = tokens(MergedGenerator::new(
+            vec![generator("A", 2), generator("B", 4)],
+            MergePolicy::RoundRobin,
+        ));
+        let (ids, order): (Vec<_>, Vec<_>) = round_robin.into_iter().unzip();
+        assert_eq!(ids, (0..6).collect::<Vec<_>>());
+        assert_eq!(order.concat(), "ABABBB");
+
+        let weighted = tokens(MergedGenerator::new(
+            vec![
+                generator("A", 100),
+                generator("B", 100),
+                generator("C", 100),
+            ],
+            MergePolicy::Weighted(vec![2, 1, 0]),
+        ));
+        let order: Vec<_> = weighted
+            .into_iter()
+            .take(6)
+            .map(|(_, token)| token)
+            .collect();
+        assert_eq!(order.concat(), "ABAABA");
+    }
+
+    #[test]
+    fn test_commission_ema_smooths_the_draws() {
+        let commissions = |commission_ema_alpha| {
+            let params = Arc::new(EnvParams {
+                commission: 1_000,
+                commission_change: 500,
+                rng_seed: Some(42),
+                commission_ema_alpha,
+                ..Default::default()
+            });
+            TransactionGenerator::new(params)
+                .take(50)
+                .map(|tx| tx.adjusted_commission)
+                .collect::<Vec<_>>()
+        };
+        let draws = commissions(None);
+        let smoothed = commissions(Some(0.2));
+
+        // Same seed, so each smoothed value blends the same draw into the previous average.
+        let mut average = draws[0] as f64;
+        assert_eq!(smoothed[0], draws[0]);
+        for (draw, smoothed) in draws.iter().zip(&smoothed).skip(1) {
+            average = 0.2 * *draw as f64 + 0.8 * average;
+            assert_eq!(*smoothed, average.round() as i64);
+        }
+
+        let jitter = |values: &[i64]| {
+            values
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]).abs())
+                .sum::<i64>()
+        };
+        assert!(jitter(&smoothed) * 2 < jitter(&draws));
+    }
+
+    #[test]
+    fn test_failure_rate_follows_the_final_commission() {
+        let params = Arc::new(EnvParams {
+            commission: 1_000,
+            commission_change: 500,
+            commission_failure_scale: Some(100),
+            commission_ema_alpha: Some(0.2),
+            ..Default::default()
+        });
+        let bidding = Bidding::new(
+            Arc::new(Counter::default()),
+            Arc::new(LimitChecker::new(&params)),
+            Duration::from_secs(1),
+        );
+        let generator = TransactionGeneratorBuilder::default()
+            .params(params.clone())
+            .bidding(Some(bidding))
+            .build()
+            .unwrap();
+
+        for tx in generator.take(5) {
+            // The bids of 1 to 5 fail far more often than the drawn commissions would.
+            assert!(tx.adjusted_commission <= 5);
+            assert_eq!(
+                tx.failure_rate,
+                params.failure_probability(tx.adjusted_commission)
+            );
+        }
+    }
+}
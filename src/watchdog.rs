@@ -0,0 +1,146 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use log::error;
+
+use crate::limits::LimitChecker;
+
+/// Monitors a run for stalls: when no transaction has succeeded within the timeout
+/// (`stall_timeout_secs`), e.g. because every execution fails or blocks, an error is logged
+/// and, with `stall_abort`, the run is finished.
+///
+/// The monitor thread only holds a weak reference to the limiter and stops when dropped.
+#[derive(Debug)]
+pub struct Watchdog {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watchdog {
+    /// Starts monitoring the last success of `limiter` against `timeout`.
+    ///
+    /// # Arguments
+    /// * `limiter` - The limit checker shared with the workers.
+    /// * `timeout` - The longest time allowed without a successful transaction.
+    pub fn spawn(limiter: &Arc<LimitChecker>, timeout: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let limiter: Weak<LimitChecker> = Arc::downgrade(limiter);
+        let interval = (timeout / 4).clamp(Duration::from_millis(1), Duration::from_secs(1));
+
+        let handle = {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                // The last success already reported as stalled, so each stall is logged once.
+                let mut reported: Option<Instant> = None;
+                while !stop.load(Ordering::SeqCst) {
+                    thread::sleep(interval);
+                    let Some(limiter) = limiter.upgrade() else {
+                        break;
+                    };
+                    let last_success = *limiter.last_success.lock().unwrap();
+                    if last_success.elapsed() >= timeout && reported != Some(last_success) {
+                        reported = Some(last_success);
+                        report_stall(&limiter, last_success.elapsed());
+                    }
+                }
+            })
+        };
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Logs the stall of the run and aborts it when `stall_abort` is set.
+///
+/// # Arguments
+/// * `limiter` - The limit checker of the stalled run.
+/// * `idle` - The time elapsed since the last success (or the start of the run).
+pub fn report_stall(limiter: &LimitChecker, idle: Duration) {
+    error!(
+        "No transaction succeeded for {:?}: the run looks stalled.",
+        idle
+    );
+    if limiter.params().stall_abort {
+        error!("Aborting the stalled run.");
+        limiter.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use log::Level;
+
+    use super::*;
+    use crate::env_utils::EnvParams;
+    use crate::limits::States;
+    use crate::runner::{run_transaction_process, unwrap_results};
+    use crate::test_tx::StubTransaction;
+
+    /// Transaction whose execution always fails after a short delay.
+    fn failing() -> StubTransaction {
+        StubTransaction::new()
+            .with_delay(Duration::from_millis(5))
+            .with_failures(usize::MAX)
+    }
+
+    fn params(stall_abort: bool) -> EnvParams {
+        EnvParams {
+            total_amount: 1_000_000,
+            max_transactions: 100_000,
+            max_threads: 1,
+            stall_abort,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_watchdog_aborts_an_all_failing_run() {
+        let limiter = Arc::new(LimitChecker::new(&params(true)));
+        let watchdog = Watchdog::spawn(&limiter, Duration::from_millis(50));
+
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = std::iter::repeat_with(failing).take(10_000);
+        run_transaction_process(1, generator, limiter.clone(), &results);
+        drop(watchdog);
+
+        assert!(limiter.aborted.load(Ordering::SeqCst));
+        let results = unwrap_results(results);
+        assert!(!results.is_empty() && results.len() < 10_000);
+        assert!(results
+            .iter()
+            .all(|state| matches!(state, States::Failed { .. })));
+    }
+
+    #[test]
+    fn test_stall_is_logged_without_aborting() {
+        let limiter = LimitChecker::new(&params(false));
+
+        let logs = crate::test_log::capture(|| report_stall(&limiter, Duration::from_secs(30)));
+
+        assert!(logs.contains(&(
+            Level::Error,
+            "No transaction succeeded for 30s: the run looks stalled.".to_string()
+        )));
+        assert!(!limiter.aborted.load(Ordering::SeqCst));
+        assert_ne!(
+            limiter.process_transaction(&failing()).unwrap(),
+            States::Finish
+        );
+    }
+}
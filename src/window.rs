@@ -0,0 +1,196 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// Sliding window of committed amounts, limiting how much can be spent per `window`.
+///
+/// Threads waiting for room block on a condition variable instead of polling: a single one
+/// of them keeps the time, sleeping until the oldest entry leaves the window, and wakes the
+/// others once it does, so N waiting threads do not each run their own timer.
+#[derive(Debug)]
+pub struct SpendWindow {
+    limit: i64,
+    window: Duration,
+    state: Mutex<WindowState>,
+    changed: Condvar,
+    /// Number of times a waiting thread checked the window for room.
+    checks: AtomicUsize,
+}
+
+/// Entry of a committed amount in a `SpendWindow`, by which it is released.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reservation(u64);
+
+#[derive(Debug, Default)]
+struct WindowState {
+    entries: VecDeque<(Reservation, Instant, i64)>,
+    /// Number of amounts committed so far, numbering the reservations.
+    committed: u64,
+    /// Whether a waiting thread is already timing the expiry of the oldest entry.
+    timer_armed: bool,
+    /// Set once the run was aborted, releasing the waiting threads without committing.
+    aborted: bool,
+}
+
+impl SpendWindow {
+    /// Creates a window allowing at most `limit` to be committed within any `window`.
+    pub fn new(limit: i64, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            state: Mutex::new(WindowState::default()),
+            changed: Condvar::new(),
+            checks: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns whether `amount` can ever fit into the window.
+    pub fn fits(&self, amount: i64) -> bool {
+        amount <= self.limit
+    }
+
+    /// Blocks until `amount` fits into the window, then records it as committed now.
+    /// Callers must check `fits` first, otherwise this only returns once aborted.
+    ///
+    /// # Returns
+    ///
+    /// The reservation to `release` the amount by, `None` when the window was aborted.
+    pub fn wait_and_commit(&self, amount: i64) -> Option<Reservation> {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if state.aborted {
+                return None;
+            }
+            self.checks.fetch_add(1, Ordering::Relaxed);
+            let now = Instant::now();
+            while state
+                .entries
+                .front()
+                .is_some_and(|(_, at, _)| now.duration_since(*at) >= self.window)
+            {
+                state.entries.pop_front();
+            }
+
+            let spent: i64 = state.entries.iter().map(|(_, _, amount)| amount).sum();
+            if spent + amount <= self.limit {
+                let reservation = Reservation(state.committed);
+                state.committed += 1;
+                state.entries.push_back((reservation, now, amount));
+                return Some(reservation);
+            }
+
+            if state.timer_armed {
+                // Another waiting thread keeps the time and wakes this one.
+                state = self.changed.wait(state).unwrap();
+                continue;
+            }
+
+            // Wait for the oldest entry to leave the window, then wake the other waiters.
+            let oldest = state.entries.front().map(|(_, at, _)| *at).unwrap_or(now);
+            let timeout = (oldest + self.window).saturating_duration_since(now);
+            state.timer_armed = true;
+            state = self.changed.wait_timeout(state, timeout).unwrap().0;
+            state.timer_armed = false;
+            self.changed.notify_all();
+        }
+    }
+
+    /// Removes the amount committed under `reservation`, e.g. when its transaction failed.
+    /// An amount that already left the window is not released again.
+    pub fn release(&self, reservation: Reservation) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(index) = state
+            .entries
+            .iter()
+            .position(|(entry, _, _)| *entry == reservation)
+        {
+            state.entries.remove(index);
+            self.changed.notify_all();
+        }
+    }
+
+    /// Releases the threads waiting for room, and the later ones, without committing.
+    pub fn abort(&self) {
+        self.state.lock().unwrap().aborted = true;
+        self.changed.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    #[test]
+    fn test_waiting_threads_idle_until_the_window_frees() {
+        let window = Arc::new(SpendWindow::new(800, Duration::from_millis(100)));
+        window.wait_and_commit(800).unwrap();
+        let started = Instant::now();
+
+        let waiters: Vec<_> = (0..8)
+            .map(|_| {
+                let window = window.clone();
+                std::thread::spawn(move || window.wait_and_commit(100))
+            })
+            .collect();
+        for waiter in waiters {
+            waiter.join().unwrap();
+        }
+
+        assert!(started.elapsed() >= Duration::from_millis(90));
+        // The first commit and each waiter check on arrival and about once per wake-up;
+        // a polling loop would check thousands of times over the 100ms.
+        let checks = window.checks.load(Ordering::Relaxed);
+        assert!(checks <= 1 + 8 * 3, "{} checks", checks);
+    }
+
+    #[test]
+    fn test_release_wakes_a_waiter() {
+        let window = Arc::new(SpendWindow::new(100, Duration::from_secs(60)));
+        let reservation = window.wait_and_commit(100).unwrap();
+
+        let waiter = {
+            let window = window.clone();
+            std::thread::spawn(move || window.wait_and_commit(100))
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        window.release(reservation);
+
+        let released = waiter.join().unwrap().unwrap();
+        assert_eq!(window.state.lock().unwrap().entries.len(), 1);
+        // Only the released entry left the window.
+        window.release(reservation);
+        assert_eq!(window.state.lock().unwrap().entries[0].0, released);
+    }
+
+    #[test]
+    fn test_release_removes_its_own_entry() {
+        let window = SpendWindow::new(1_000, Duration::from_secs(60));
+        let first = window.wait_and_commit(100).unwrap();
+        let second = window.wait_and_commit(100).unwrap();
+
+        window.release(first);
+
+        let entries = &window.state.lock().unwrap().entries;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, second);
+    }
+
+    #[test]
+    fn test_abort_releases_the_waiters() {
+        let window = Arc::new(SpendWindow::new(100, Duration::from_secs(60)));
+        window.wait_and_commit(100).unwrap();
+
+        let waiter = {
+            let window = window.clone();
+            std::thread::spawn(move || window.wait_and_commit(100))
+        };
+        std::thread::sleep(Duration::from_millis(20));
+        window.abort();
+
+        assert_eq!(waiter.join().unwrap(), None);
+        assert_eq!(window.wait_and_commit(0), None);
+    }
+}
@@ -1,13 +1,35 @@
 use std::sync::Arc;
-use test_bot::env_utils::EnvParams;
+use test_bot::env_utils::{EnvParams, Target};
+use test_bot::executor::Executor;
 use test_bot::tx::Transaction;
 
-#[derive(Debug)]
+/// Transaction of the integration tests, configured by the `with_*` methods.
+#[derive(Debug, Clone)]
 pub struct TestTransaction {
     pub(crate) wallet: String,
     pub(crate) token: String,
     pub(crate) adjusted_commission: i64,
     pub(crate) price: i64,
+    pub(crate) fail: bool,
+    /// Id reported to the limiter, which also makes the signature `sig-<id>`.
+    pub(crate) id: Option<u64>,
+    pub(crate) depends_on: Option<u64>,
+    pub(crate) tokens: i64,
+    /// Token reported to the limiter, none unless set.
+    pub(crate) reported_token: Option<String>,
+    /// Wallet reported to the limiter, none unless set.
+    pub(crate) reported_wallet: Option<&'static str>,
+    /// Time every execution takes.
+    pub(crate) delay: std::time::Duration,
+    /// Error every execution fails with.
+    pub(crate) error: Option<&'static str>,
+    /// Makes the amount disagree with the price and the commission.
+    pub(crate) malformed: bool,
+    /// Reports the executions of the reported token.
+    pub(crate) probe: Option<Arc<TokenProbe>>,
+    /// Records the threads executing the transaction.
+    pub(crate) threads:
+        Option<Arc<std::sync::Mutex<std::collections::HashSet<std::thread::ThreadId>>>>,
 }
 
 impl TestTransaction {
@@ -18,17 +40,124 @@ impl TestTransaction {
             token: params.token.clone(),
             adjusted_commission,
             price: params.price,
+            fail: false,
+            id: None,
+            depends_on: None,
+            tokens: 1,
+            reported_token: None,
+            reported_wallet: None,
+            delay: std::time::Duration::ZERO,
+            error: None,
+            malformed: false,
+            probe: None,
+            threads: None,
+        }
+    }
+
+    pub fn new_failing(params: &Arc<EnvParams>) -> Self {
+        Self {
+            fail: true,
+            ..Self::new_stable_min(params)
+        }
+    }
+
+    /// Sets the id, only allowing the execution after `depends_on` succeeded.
+    pub fn with_id(self, id: u64, depends_on: Option<u64>) -> Self {
+        Self {
+            id: Some(id),
+            depends_on,
+            ..self
+        }
+    }
+
+    /// Reports `token` to the limiter, and the executions of it to `probe` when set.
+    pub fn with_token(self, token: &str, probe: Option<&Arc<TokenProbe>>) -> Self {
+        Self {
+            reported_token: Some(token.to_string()),
+            probe: probe.cloned(),
+            ..self
+        }
+    }
+
+    pub fn with_wallet(self, wallet: &'static str) -> Self {
+        Self {
+            reported_wallet: Some(wallet),
+            ..self
+        }
+    }
+
+    /// Buys `tokens` tokens at once.
+    pub fn with_tokens(self, tokens: i64) -> Self {
+        Self { tokens, ..self }
+    }
+
+    /// Makes every execution take at least `delay`.
+    pub fn with_delay(self, delay: std::time::Duration) -> Self {
+        Self { delay, ..self }
+    }
+
+    /// Fails every execution with `error`.
+    pub fn with_error(self, error: &'static str) -> Self {
+        Self {
+            error: Some(error),
+            ..self
+        }
+    }
+
+    /// Makes the amount disagree with the price and the commission when `malformed`.
+    pub fn with_malformed_amount(self, malformed: bool) -> Self {
+        Self { malformed, ..self }
+    }
+
+    /// Records the threads the transaction is executed on into `threads`.
+    pub fn with_thread_record(
+        self,
+        threads: &Arc<std::sync::Mutex<std::collections::HashSet<std::thread::ThreadId>>>,
+    ) -> Self {
+        Self {
+            threads: Some(threads.clone()),
+            ..self
         }
     }
 }
 
 impl Transaction for TestTransaction {
     fn amount(&self) -> i64 {
-        self.adjusted_commission + self.price
+        self.adjusted_commission + self.price + i64::from(self.malformed)
+    }
+
+    fn price(&self) -> i64 {
+        self.price
+    }
+
+    fn commission(&self) -> i64 {
+        self.adjusted_commission
     }
 
     fn execute(&self) -> Result<String, String> {
-        Ok(self.info().to_string())
+        if let Some(threads) = &self.threads {
+            threads.lock().unwrap().insert(std::thread::current().id());
+        }
+        if let Some(probe) = &self.probe {
+            probe.enter(self.token());
+        }
+        if !self.delay.is_zero() {
+            std::thread::sleep(self.delay);
+        }
+        if let Some(probe) = &self.probe {
+            probe.leave(self.token());
+        }
+
+        if let Some(error) = self.error {
+            return Err(error.to_string());
+        }
+        if self.fail {
+            return Err("failed tx".to_string());
+        }
+        Ok(match self.id {
+            Some(id) => format!("sig-{}", id),
+            None => self.info().to_string(),
+        })
     }
 
     fn info(&self) -> String {
@@ -41,6 +170,163 @@ impl Transaction for TestTransaction {
             self.amount()
         )
     }
+
+    fn id(&self) -> u64 {
+        self.id.unwrap_or_default()
+    }
+
+    fn token(&self) -> &str {
+        self.reported_token.as_deref().unwrap_or_default()
+    }
+
+    fn depends_on(&self) -> Option<u64> {
+        self.depends_on
+    }
+
+    fn tokens(&self) -> i64 {
+        self.tokens
+    }
+
+    fn wallet(&self) -> &str {
+        self.reported_wallet.unwrap_or_default()
+    }
+
+    fn detach(&self) -> Option<Box<dyn Transaction + Send>> {
+        Some(Box::new(self.clone()))
+    }
+}
+
+/// Transaction failing its first `failures` executions and succeeding afterwards.
+#[derive(Debug)]
+pub struct FlakyTransaction {
+    pub(crate) inner: TestTransaction,
+    pub(crate) failures: usize,
+    pub(crate) attempts: std::sync::atomic::AtomicUsize,
+}
+
+impl FlakyTransaction {
+    pub fn new(params: &Arc<EnvParams>, failures: usize) -> Self {
+        Self {
+            inner: TestTransaction::new_stable_min(params),
+            failures,
+            attempts: Default::default(),
+        }
+    }
+}
+
+impl Transaction for FlakyTransaction {
+    fn amount(&self) -> i64 {
+        self.inner.amount()
+    }
+
+    fn price(&self) -> i64 {
+        self.inner.price()
+    }
+
+    fn commission(&self) -> i64 {
+        self.inner.commission()
+    }
+
+    fn execute(&self) -> Result<String, String> {
+        let attempt = self
+            .attempts
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if attempt < self.failures {
+            return Err(format!("failed attempt {}", attempt + 1));
+        }
+        self.inner.execute()
+    }
+
+    fn info(&self) -> String {
+        self.inner.info()
+    }
+}
+
+/// Transaction whose amount deliberately disagrees with its price and commission.
+#[derive(Debug)]
+pub struct InconsistentTransaction;
+
+impl Transaction for InconsistentTransaction {
+    fn amount(&self) -> i64 {
+        150
+    }
+
+    fn price(&self) -> i64 {
+        100
+    }
+
+    fn commission(&self) -> i64 {
+        100
+    }
+
+    fn execute(&self) -> Result<String, String> {
+        Ok(self.info())
+    }
+
+    fn info(&self) -> String {
+        "inconsistent".to_string()
+    }
+}
+
+/// Records how many transactions of each token execute concurrently.
+#[derive(Debug, Default)]
+pub struct TokenProbe {
+    pub(crate) in_flight: std::sync::Mutex<std::collections::HashMap<String, usize>>,
+    pub(crate) max_in_flight: std::sync::Mutex<std::collections::HashMap<String, usize>>,
+    /// Executions that started while a transaction of another token was executing.
+    pub(crate) overlaps: std::sync::atomic::AtomicUsize,
+}
+
+impl TokenProbe {
+    /// Counts an execution of `token` starting.
+    fn enter(&self, token: &str) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        let count = in_flight.entry(token.to_string()).or_insert(0);
+        *count += 1;
+        let count = *count;
+        let mut max_in_flight = self.max_in_flight.lock().unwrap();
+        let max = max_in_flight.entry(token.to_string()).or_insert(0);
+        *max = (*max).max(count);
+        if in_flight
+            .iter()
+            .any(|(other, count)| other != token && *count > 0)
+        {
+            self.overlaps
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+
+    /// Counts an execution of `token` ending.
+    fn leave(&self, token: &str) {
+        *self.in_flight.lock().unwrap().get_mut(token).unwrap() -= 1;
+    }
+}
+
+/// Executor recording the ids of the transactions submitted to it, succeeding without
+/// running their simulation.
+#[derive(Debug, Default)]
+pub struct RecordingExecutor {
+    pub(crate) submitted: Arc<std::sync::Mutex<Vec<u64>>>,
+}
+
+impl Executor for RecordingExecutor {
+    fn submit(&self, tx: &dyn Transaction) -> Result<String, String> {
+        self.submitted.lock().unwrap().push(tx.id());
+        Ok(format!("rpc-sig-{}", tx.id()))
+    }
+}
+
+/// Executor taking `delay` to return a signature, standing in for a hanging RPC call.
+#[derive(Debug)]
+pub struct SlowExecutor {
+    pub(crate) delay: std::time::Duration,
+}
+
+impl Executor for SlowExecutor {
+    fn submit(&self, _tx: &dyn Transaction) -> Result<String, String> {
+        std::thread::sleep(self.delay);
+        Ok("late-sig".to_string())
+    }
 }
 
 #[cfg(test)]
@@ -48,7 +334,33 @@ mod tests {
     use std::sync::atomic::Ordering;
 
     use super::*;
-    use test_bot::limits::{LimitChecker, States};
+    use std::sync::Mutex;
+    use test_bot::config::ConfigError;
+    use test_bot::limits::{LimitChecker, ProcessError, States};
+    use test_bot::output::{
+        CsvFormatter, JsonFormatter, OutputFormat, OutputStream, ResultFormatter,
+    };
+    use test_bot::runner::{
+        display_results, display_results_to_sinks, display_run, run_streaming, run_targets,
+        run_transaction_process, spawn_transaction_process, target_runs, unwrap_results, OnError,
+    };
+    use test_bot::stats::{Rejection, RejectionCounts, StopReason};
+    use test_bot::summary::RunSummary;
+    use test_bot::tx_genertor::TransactionGenerator;
+
+    fn test_params(total_amount: i64, max_transactions: usize) -> EnvParams {
+        EnvParams {
+            wallet: "test_wallet".to_string(),
+            token: "test_token".to_string(),
+            total_amount,
+            commission: 100,
+            commission_change: 10,
+            max_transactions,
+            max_threads: 1,
+            price: 100,
+            ..Default::default()
+        }
+    }
 
     // Тесты
     #[test]
@@ -62,6 +374,7 @@ mod tests {
             max_transactions: 100,
             max_threads: 1,
             price: 100,
+            ..Default::default()
         });
 
         let tx = TestTransaction::new_stable_min(&params);
@@ -71,7 +384,7 @@ mod tests {
 
         assert!(matches!(result, Ok(States::Finish)));
         assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 0);
-        assert_eq!(limiter.current_amount.load(Ordering::SeqCst), 0);
+        assert_eq!(limiter.committed_amount(), 0);
     }
 
     #[test]
@@ -85,12 +398,1308 @@ mod tests {
             max_transactions: 100,
             max_threads: 1,
             price: 100,
+            ..Default::default()
         });
 
         let tx = TestTransaction::new_stable_min(&params);
         let limiter = LimitChecker::new(&params);
 
         let result = limiter.process_transaction(&tx);
-        assert!(matches!(result, Ok(States::InProgres(_))));
+        assert!(matches!(result, Ok(States::Success { .. })));
+    }
+
+    #[test]
+    fn test_failed_and_successful_executions_yield_distinct_states() {
+        let params = Arc::new(test_params(1000, 10));
+        let limiter = LimitChecker::new(&params);
+
+        let state = limiter
+            .process_transaction(&TestTransaction::new_failing(&params))
+            .unwrap();
+        assert_eq!(
+            state,
+            States::Failed {
+                error: "failed tx".to_string(),
+                amount: 190
+            }
+        );
+
+        let tx = TestTransaction::new_stable_min(&params);
+        let state = limiter.process_transaction(&tx).unwrap();
+        assert_eq!(
+            state,
+            States::Success {
+                signature: tx.info(),
+                amount: 190
+            }
+        );
+    }
+
+    #[test]
+    fn test_statsd_metrics_are_emitted() {
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
+
+        let params = Arc::new(EnvParams {
+            wallet: "test_wallet".to_string(),
+            token: "test_token".to_string(),
+            total_amount: 1000,
+            commission: 100,
+            commission_change: 10,
+            max_transactions: 100,
+            max_threads: 1,
+            price: 100,
+            statsd_addr: Some(socket.local_addr().unwrap().to_string()),
+            ..Default::default()
+        });
+        let limiter = LimitChecker::new(&params);
+
+        limiter
+            .process_transaction(&TestTransaction::new_stable_min(&params))
+            .unwrap();
+        limiter
+            .process_transaction(&TestTransaction::new_failing(&params))
+            .unwrap();
+
+        let mut packets = Vec::new();
+        let mut buf = [0u8; 512];
+        for _ in 0..4 {
+            let len = socket.recv(&mut buf).unwrap();
+            packets.push(String::from_utf8_lossy(&buf[..len]).to_string());
+        }
+
+        assert_eq!(packets[1], "tx.success:1|c");
+        assert_eq!(packets[3], "tx.fail:1|c");
+        assert!(packets[0].starts_with("tx.latency:") && packets[0].ends_with("|ms"));
+        assert!(packets[2].starts_with("tx.latency:") && packets[2].ends_with("|ms"));
+        assert_eq!(limiter.metrics.success.load(Ordering::SeqCst), 1);
+        assert_eq!(limiter.metrics.fail.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_deferred_retry_after_main_stream() {
+        let params = Arc::new(EnvParams {
+            wallet: "test_wallet".to_string(),
+            token: "test_token".to_string(),
+            total_amount: 1000,
+            commission: 100,
+            commission_change: 10,
+            max_transactions: 100,
+            max_threads: 1,
+            price: 100,
+            deferred_retry_limit: 1,
+            ..Default::default()
+        });
+        let transactions = vec![
+            FlakyTransaction::new(&params, 1),
+            FlakyTransaction::new(&params, 0),
+        ];
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        run_transaction_process(1, transactions, limiter.clone(), &results);
+        let results = unwrap_results(results);
+
+        assert_eq!(results.len(), 2);
+        assert!(results
+            .iter()
+            .all(|state| matches!(state, States::Success { .. })));
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 2);
+        assert_eq!(limiter.metrics.fail.load(Ordering::SeqCst), 1);
+        assert_eq!(limiter.metrics.success.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_run_summary_reports_the_outcome_of_the_run() {
+        let params = Arc::new(test_params(1000, 10));
+        let ok = || TestTransaction::new_stable_min(&params);
+        let transactions = vec![
+            ok(),
+            TestTransaction::new_failing(&params),
+            ok(),
+            ok(),
+            ok(),
+            ok(),
+            ok(),
+        ];
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        let summary = run_transaction_process(1, transactions, limiter, &results);
+
+        assert_eq!(summary.successes, 5);
+        assert_eq!(summary.failures, 1);
+        assert_eq!(summary.transactions_count, 5);
+        assert_eq!(summary.committed_amount, 950);
+        assert_eq!(summary.commission_paid, 450);
+        assert_eq!(summary.stop_reason, StopReason::Limit(Rejection::AmountCap));
+        assert_eq!(
+            summary.to_string(),
+            "successes: 5, failures: 1 (83.33% success)\n\
+             committed: 5 transactions, 950 spent, 450 in commissions\n\
+             stopped: amount cap reached"
+        );
+
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let summary = run_transaction_process(1, vec![ok()], limiter, &Default::default());
+        assert_eq!(summary.stop_reason, StopReason::Exhausted);
+    }
+
+    #[test]
+    fn test_dry_run_admits_like_a_real_run_without_executing() {
+        let run = |dry_run| {
+            let params = Arc::new(EnvParams {
+                dry_run,
+                commission_change: 0,
+                // The simulation would fail every transaction.
+                failure_rate: Some(1.0),
+                ..test_params(1000, 10)
+            });
+            let executor = RecordingExecutor::default();
+            let submitted = executor.submitted.clone();
+            let limiter = Arc::new(LimitChecker::new(&params).with_executor(executor));
+            let results = Arc::new(Mutex::new(Vec::new()));
+            let mut generator = TransactionGenerator::new(params.clone());
+            generator.completed = Some(limiter.completed.clone());
+            let summary = run_transaction_process(1, generator, limiter, &results);
+            let submitted = submitted.lock().unwrap().len();
+            (summary, submitted, unwrap_results(results))
+        };
+
+        let (real, real_submitted, _) = run(false);
+        let (dry, dry_submitted, results) = run(true);
+
+        assert_eq!(real_submitted, 5);
+        assert_eq!(dry_submitted, 0);
+        assert_eq!(dry.transactions_count, real.transactions_count);
+        assert_eq!(dry.committed_amount, real.committed_amount);
+        assert_eq!(dry.stop_reason, real.stop_reason);
+        assert_eq!(
+            results[0],
+            States::Success {
+                signature: "dry-run-0".to_string(),
+                amount: 200
+            }
+        );
+    }
+
+    #[test]
+    fn test_slow_execution_times_out_and_is_rolled_back() {
+        let params = Arc::new(EnvParams {
+            tx_timeout_ms: Some(20),
+            ..test_params(1000, 10)
+        });
+        let limiter = LimitChecker::new(&params).with_executor(SlowExecutor {
+            delay: std::time::Duration::from_secs(5),
+        });
+
+        let started = std::time::Instant::now();
+        let result = limiter.process_transaction(&TestTransaction::new_stable_min(&params));
+
+        // The hung submission is left behind rather than joined.
+        assert!(started.elapsed() < std::time::Duration::from_millis(500));
+        assert_eq!(
+            result,
+            Ok(States::Failed {
+                error: "timeout".to_string(),
+                amount: 190
+            })
+        );
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 0);
+        assert_eq!(limiter.committed_amount(), 0);
+        assert_eq!(limiter.rollback_count.load(Ordering::SeqCst), 1);
+
+        // Within the timeout, the simulated execution runs as without one.
+        let limiter = LimitChecker::new(&params);
+        let tx = TestTransaction::new_stable_min(&params);
+        assert_eq!(
+            limiter.process_transaction(&tx),
+            Ok(States::Success {
+                signature: tx.info(),
+                amount: 190
+            })
+        );
+        assert!(matches!(
+            limiter.process_transaction(&TestTransaction::new_failing(&params)),
+            Ok(States::Failed { error, .. }) if error == "failed tx"
+        ));
+    }
+
+    #[test]
+    fn test_hung_executions_are_left_behind_on_the_submit_pool() {
+        let params = Arc::new(EnvParams {
+            tx_timeout_ms: Some(20),
+            max_threads: 2,
+            ..test_params(1000, 10)
+        });
+        let limiter = LimitChecker::new(&params);
+
+        // More hung executions than pool threads: the later ones wait for a free thread and
+        // time out as well, rather than blocking the caller.
+        let started = std::time::Instant::now();
+        for _ in 0..4 {
+            let tx = TestTransaction::new_stable_min(&params)
+                .with_delay(std::time::Duration::from_secs(5));
+            assert_eq!(
+                limiter.process_transaction(&tx),
+                Ok(States::Failed {
+                    error: "timeout".to_string(),
+                    amount: 190
+                })
+            );
+        }
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert_eq!(limiter.rollback_count.load(Ordering::SeqCst), 4);
+
+        // Without a copy to detach, the execution runs on the calling thread and its time is
+        // not charged to the submission.
+        let limiter = LimitChecker::new(&params);
+        let tx = FlakyTransaction {
+            inner: TestTransaction::new_stable_min(&params)
+                .with_delay(std::time::Duration::from_millis(50)),
+            ..FlakyTransaction::new(&params, 0)
+        };
+        assert!(matches!(
+            limiter.process_transaction(&tx),
+            Ok(States::Success { amount: 190, .. })
+        ));
+    }
+
+    #[test]
+    fn test_targets_keep_to_their_own_budgets() {
+        let params = EnvParams {
+            commission_change: 0,
+            failure_rate: Some(0.0),
+            max_threads: 2,
+            targets: vec![
+                Target::parse("W1:SOL:1k:100", 0).unwrap(),
+                Target::parse("W2:ETH:450:100", 0).unwrap(),
+            ],
+            ..test_params(0, 0)
+        };
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        assert_eq!(params.validate(), Ok(()));
+        let summaries = run_targets(2, &target_runs(&params), &results);
+
+        let committed: Vec<_> = summaries
+            .iter()
+            .map(|target| {
+                (
+                    target.target.wallet.as_str(),
+                    target.summary.committed_amount,
+                    target.summary.stop_reason,
+                )
+            })
+            .collect();
+        // Each transaction costs 200: 5 fit into the first budget, 2 into the second.
+        assert_eq!(
+            committed,
+            vec![
+                ("W1", 1_000, StopReason::Limit(Rejection::AmountCap)),
+                ("W2", 400, StopReason::Limit(Rejection::AmountCap)),
+            ]
+        );
+        assert_eq!(unwrap_results(results).len(), 7);
+        assert_eq!(summaries[1].stats.transactions_count, 2);
+        assert!(Target::parse("W1:SOL:1k", 0).is_err());
+
+        // The options of a single run are rejected rather than ignored.
+        let params = EnvParams {
+            replay_path: Some("replay.json".to_string()),
+            report_path: Some("report.json".to_string()),
+            initial_count: 3,
+            ..params
+        };
+        let Err(ConfigError::Invalid(errors)) = params.validate() else {
+            panic!("options unsupported with TARGETS passed the validation");
+        };
+        let fields: Vec<_> = errors.iter().map(|error| error.field).collect();
+        assert_eq!(fields, ["replay_path", "initial_count", "report_path"]);
+    }
+
+    #[test]
+    fn test_custom_executor_receives_the_admitted_transactions() {
+        let params = Arc::new(EnvParams {
+            commission_change: 0,
+            // The simulation would fail every transaction.
+            failure_rate: Some(1.0),
+            ..test_params(400, 10)
+        });
+        let executor = RecordingExecutor::default();
+        let submitted = executor.submitted.clone();
+        let limiter = Arc::new(LimitChecker::new(&params).with_executor(executor));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let mut generator = TransactionGenerator::new(params.clone());
+        generator.completed = Some(limiter.completed.clone());
+
+        run_transaction_process(1, generator, limiter, &results);
+
+        assert_eq!(*submitted.lock().unwrap(), vec![0, 1]);
+        assert_eq!(
+            unwrap_results(results),
+            vec![
+                States::Success {
+                    signature: "rpc-sig-0".to_string(),
+                    amount: 200
+                },
+                States::Success {
+                    signature: "rpc-sig-1".to_string(),
+                    amount: 200
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_submissions_are_paced_to_max_tps_across_threads() {
+        let params = Arc::new(EnvParams {
+            max_tps: Some(50.0),
+            max_threads: 4,
+            ..test_params(10_000, 100)
+        });
+        let transactions: Vec<_> = (0..11)
+            .map(|_| TestTransaction::new_stable_min(&params))
+            .collect();
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        let started = std::time::Instant::now();
+        run_transaction_process(4, transactions, limiter, &results);
+
+        assert_eq!(unwrap_results(results).len(), 11);
+        // The first submission is immediate, the next 10 are 20ms apart.
+        assert!(started.elapsed() >= std::time::Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_run_stops_admitting_transactions_at_the_time_limit() {
+        let params = Arc::new(EnvParams {
+            max_runtime_secs: Some(0.05),
+            max_threads: 2,
+            ..test_params(i64::MAX, usize::MAX)
+        });
+        let generator = (0..).map(|_| {
+            TestTransaction::new_stable_min(&params).with_delay(std::time::Duration::from_millis(5))
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        let started = std::time::Instant::now();
+        let summary = run_transaction_process(2, generator, limiter, &results);
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(!unwrap_results(results).is_empty());
+        assert_eq!(summary.stop_reason, StopReason::Limit(Rejection::TimeLimit));
+        assert_eq!(summary.stop_reason.to_string(), "time limit reached");
+    }
+
+    #[test]
+    fn test_failed_execution_is_retried_with_backoff() {
+        let params = Arc::new(EnvParams {
+            retry_attempts: 3,
+            retry_backoff_ms: 5,
+            ..test_params(1000, 10)
+        });
+        let limiter = LimitChecker::new(&params);
+        let recovering = FlakyTransaction::new(&params, 2);
+
+        let started = std::time::Instant::now();
+        let state = limiter.process_transaction(&recovering).unwrap();
+
+        assert!(matches!(state, States::Success { .. }));
+        // Backoffs of 5ms and 10ms.
+        assert!(started.elapsed() >= std::time::Duration::from_millis(15));
+        assert_eq!(recovering.attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(limiter.retries.load(Ordering::SeqCst), 2);
+        assert_eq!(limiter.committed_amount(), 190);
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 1);
+
+        let failing = FlakyTransaction::new(&params, 10);
+        let state = limiter.process_transaction(&failing).unwrap();
+
+        assert!(matches!(state, States::Failed { .. }));
+        assert_eq!(failing.attempts.load(Ordering::SeqCst), 4);
+        assert_eq!(limiter.retries.load(Ordering::SeqCst), 5);
+        assert_eq!(limiter.committed_amount(), 190);
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 1);
+        // One failure per failed transaction, however many attempts it took.
+        assert_eq!(limiter.metrics.fail.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_retry_backoff_ends_when_the_run_is_aborted() {
+        let params = Arc::new(EnvParams {
+            retry_attempts: 3,
+            retry_backoff_ms: 10_000,
+            ..test_params(1000, 10)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let failing = FlakyTransaction::new(&params, 10);
+
+        let abort = {
+            let limiter = limiter.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                limiter.abort();
+            })
+        };
+        let started = std::time::Instant::now();
+        let state = limiter.process_transaction(&failing).unwrap();
+        abort.join().unwrap();
+
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+        assert!(matches!(state, States::Failed { .. }));
+        assert_eq!(failing.attempts.load(Ordering::SeqCst), 1);
+        assert_eq!(limiter.committed_amount(), 0);
+        assert_eq!(limiter.metrics.fail.load(Ordering::SeqCst), 1);
+        // The retry that never ran is not counted, and the failure is classified once.
+        assert_eq!(limiter.retries.load(Ordering::SeqCst), 0);
+        assert_eq!(limiter.error_classifier.counts().values().sum::<usize>(), 1);
+    }
+
+    #[test]
+    fn test_abort_releases_the_transactions_waiting_for_the_window() {
+        let params = Arc::new(EnvParams {
+            window_amount_limit: Some(200),
+            window_secs: 60,
+            ..test_params(1000, 10)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let tx = TestTransaction::new_stable_min(&params);
+        assert!(matches!(
+            limiter.process_transaction(&tx),
+            Ok(States::Success { .. })
+        ));
+
+        let waiting = {
+            let (limiter, params) = (limiter.clone(), params.clone());
+            std::thread::spawn(move || {
+                limiter.process_transaction(&TestTransaction::new_stable_min(&params))
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        limiter.abort();
+
+        assert_eq!(waiting.join().unwrap(), Ok(States::Finish));
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_pending_retries_outlast_another_limit_finishing_the_process() {
+        let params = Arc::new(EnvParams {
+            retry_attempts: 3,
+            retry_backoff_ms: 50,
+            ..test_params(1000, 1)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let recovering = Arc::new(FlakyTransaction::new(&params, 2));
+
+        let retrying = {
+            let (limiter, recovering) = (limiter.clone(), recovering.clone());
+            std::thread::spawn(move || limiter.process_transaction(&*recovering))
+        };
+        // The count cap finishes the process while the first retry is pending.
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        assert_eq!(
+            limiter.process_transaction(&TestTransaction::new_stable_min(&params)),
+            Ok(States::Finish)
+        );
+
+        assert!(matches!(
+            retrying.join().unwrap(),
+            Ok(States::Success { amount: 190, .. })
+        ));
+        assert_eq!(recovering.attempts.load(Ordering::SeqCst), 3);
+        assert_eq!(limiter.retries.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_retries_stop_at_the_execute_call_cap() {
+        let params = Arc::new(EnvParams {
+            total_amount: 1000,
+            max_transactions: 100,
+            max_threads: 1,
+            deferred_retry_limit: 10,
+            max_execute_calls: Some(5),
+            ..Default::default()
+        });
+        let transactions: Vec<_> = (0..3).map(|_| FlakyTransaction::new(&params, 10)).collect();
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        run_transaction_process(1, transactions, limiter.clone(), &results);
+
+        // Three first attempts and two deferred retries before the cap ends the run.
+        assert_eq!(limiter.metrics.fail.load(Ordering::SeqCst), 5);
+        assert_eq!(limiter.execute_calls.load(Ordering::SeqCst), 5);
+        assert_eq!(limiter.rejections.counts().execute_cap, 1);
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 0);
+        assert_eq!(limiter.reserved_amount.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_concurrent_threads_never_exceed_the_caps() {
+        // Each cap alone admits exactly 10 transactions of 190.
+        for params in [test_params(1_000_000, 10), test_params(10 * 190, 1_000)] {
+            let params = Arc::new(EnvParams {
+                max_threads: 8,
+                ..params
+            });
+            let generator: Vec<_> = (0..400)
+                .map(|_| {
+                    TestTransaction::new_stable_min(&params)
+                        .with_delay(std::time::Duration::from_millis(1))
+                })
+                .collect();
+            let limiter = Arc::new(LimitChecker::new(&params));
+            let results = Arc::new(Mutex::new(Vec::new()));
+
+            run_transaction_process(8, generator, limiter.clone(), &results);
+
+            assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 10);
+            assert_eq!(limiter.committed_amount(), 10 * 190);
+            assert_eq!(limiter.metrics.success.load(Ordering::SeqCst), 10);
+            assert_eq!(unwrap_results(results).len(), 10);
+        }
+    }
+
+    #[test]
+    fn test_transactions_are_processed_on_several_threads() {
+        let params = Arc::new(EnvParams {
+            max_threads: 4,
+            ..test_params(1_000_000, 40)
+        });
+        let threads = Arc::new(Mutex::new(std::collections::HashSet::new()));
+        let generator = (0..).map(|_| {
+            TestTransaction::new_stable_min(&params)
+                .with_delay(std::time::Duration::from_millis(5))
+                .with_thread_record(&threads)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        // The infinite generator stops being pulled from once the count cap finishes the run.
+        run_transaction_process(4, generator, limiter.clone(), &results);
+
+        assert!(threads.lock().unwrap().len() > 1);
+        assert_eq!(unwrap_results(results).len(), 40);
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 40);
+    }
+
+    #[test]
+    fn test_inconsistent_amount_is_rejected() {
+        let params = Arc::new(EnvParams {
+            total_amount: 1000,
+            max_transactions: 100,
+            validate_amounts: true,
+            ..Default::default()
+        });
+        let limiter = LimitChecker::new(&params);
+
+        let result = limiter.process_transaction(&InconsistentTransaction);
+
+        assert!(matches!(result, Err(ProcessError::Invalid(_))));
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 0);
+        assert_eq!(limiter.committed_amount(), 0);
+
+        let unchecked = LimitChecker::new(&Arc::new(EnvParams {
+            validate_amounts: false,
+            ..(*params).clone()
+        }));
+        assert!(unchecked
+            .process_transaction(&InconsistentTransaction)
+            .is_ok());
+    }
+
+    fn run_with_malformed_third(on_error: OnError) -> Vec<States> {
+        let params = Arc::new(EnvParams {
+            validate_amounts: true,
+            on_error,
+            ..test_params(10_000, 100)
+        });
+        let transactions: Vec<_> = (0..5)
+            .map(|index| TestTransaction::new_stable_min(&params).with_malformed_amount(index == 2))
+            .collect();
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+
+        run_transaction_process(1, transactions, limiter, &results);
+        unwrap_results(results)
+    }
+
+    #[test]
+    fn test_processing_error_stops_the_run() {
+        let results = run_with_malformed_third(OnError::Stop);
+        assert_eq!(results.len(), 2);
+    }
+
+    #[test]
+    fn test_processing_error_is_skipped_on_continue() {
+        let results = run_with_malformed_third(OnError::Continue);
+        assert_eq!(results.len(), 4);
+        assert!(results
+            .iter()
+            .all(|state| matches!(state, States::Success { .. })));
+    }
+
+    #[test]
+    fn test_rejections_are_counted_by_reason() {
+        // Each transaction costs 190.
+        let insufficient = Arc::new(test_params(100, 10));
+        let limiter = LimitChecker::new(&insufficient);
+        let tx = TestTransaction::new_stable_min(&insufficient);
+        assert_eq!(limiter.process_transaction(&tx), Ok(States::Finish));
+        assert_eq!(limiter.stats().rejections.insufficient_funds, 1);
+
+        let count_cap = Arc::new(test_params(1000, 1));
+        let limiter = LimitChecker::new(&count_cap);
+        let tx = TestTransaction::new_stable_min(&count_cap);
+        limiter.process_transaction(&tx).unwrap();
+        assert_eq!(limiter.process_transaction(&tx), Ok(States::Finish));
+        assert_eq!(limiter.stats().rejections.count_cap, 1);
+
+        let amount_cap = Arc::new(test_params(300, 10));
+        let limiter = LimitChecker::new(&amount_cap);
+        let tx = TestTransaction::new_stable_min(&amount_cap);
+        limiter.process_transaction(&tx).unwrap();
+        assert_eq!(limiter.process_transaction(&tx), Ok(States::Finish));
+        assert_eq!(limiter.stats().rejections.amount_cap, 1);
+
+        let dust = Arc::new(EnvParams {
+            dust_threshold: 200,
+            ..test_params(1000, 10)
+        });
+        let limiter = LimitChecker::new(&dust);
+        let tx = TestTransaction::new_stable_min(&dust);
+        assert_eq!(
+            limiter.process_transaction(&tx),
+            Ok(States::Skipped(Rejection::Dust))
+        );
+        assert_eq!(
+            limiter.stats().rejections,
+            RejectionCounts {
+                dust: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(limiter.stats().transactions_count, 0);
+    }
+
+    #[test]
+    fn test_slow_transaction_violates_sla() {
+        let params = Arc::new(EnvParams {
+            tx_sla_ms: Some(10),
+            ..test_params(1000, 10)
+        });
+        let limiter = LimitChecker::new(&params);
+        let slow = TestTransaction::new_stable_min(&params)
+            .with_delay(std::time::Duration::from_millis(30));
+
+        limiter.process_transaction(&slow).unwrap();
+        limiter
+            .process_transaction(&TestTransaction::new_stable_min(&params))
+            .unwrap();
+
+        let stats = limiter.stats();
+        assert_eq!(stats.sla_violations, 1);
+        assert_eq!(stats.successes, 2);
+    }
+
+    #[test]
+    fn test_thread_utilization_single_thread() {
+        let params = Arc::new(test_params(1000, 4));
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let transactions: Vec<_> = (0..6)
+            .map(|_| TestTransaction::new_stable_min(&params))
+            .collect();
+
+        run_transaction_process(1, transactions, limiter.clone(), &Default::default());
+
+        let utilization = limiter.stats().thread_utilization;
+        assert_eq!(utilization.per_thread, vec![4]);
+        assert_eq!((utilization.min, utilization.max), (4, 4));
+        assert_eq!(utilization.avg, 4.0);
+    }
+
+    #[test]
+    fn test_window_amount_limit_delays_into_next_window() {
+        // Two 190 transactions fit into one window of 400.
+        let params = Arc::new(EnvParams {
+            window_amount_limit: Some(400),
+            window_secs: 1,
+            ..test_params(10_000, 100)
+        });
+        let limiter = LimitChecker::new(&params);
+        let tx = TestTransaction::new_stable_min(&params);
+
+        let started = std::time::Instant::now();
+        limiter.process_transaction(&tx).unwrap();
+        limiter.process_transaction(&tx).unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_millis(500));
+
+        limiter.process_transaction(&tx).unwrap();
+        limiter.process_transaction(&tx).unwrap();
+        let elapsed = started.elapsed();
+        assert!(elapsed >= std::time::Duration::from_secs(1));
+        assert!(elapsed < std::time::Duration::from_secs(2));
+        assert_eq!(limiter.stats().committed_amount, 4 * 190);
+
+        let too_large = Arc::new(EnvParams {
+            window_amount_limit: Some(100),
+            ..(*params).clone()
+        });
+        let limiter = LimitChecker::new(&too_large);
+        assert_eq!(
+            limiter.process_transaction(&tx),
+            Ok(States::Skipped(Rejection::WindowCap))
+        );
+    }
+
+    #[test]
+    fn test_streaming_yields_before_run_finishes() {
+        let params = Arc::new(test_params(10_000, 100));
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let transactions: Vec<_> = (0..5)
+            .map(|_| {
+                TestTransaction::new_stable_min(&params)
+                    .with_delay(std::time::Duration::from_millis(50))
+            })
+            .collect();
+
+        let mut stream = run_streaming(1, transactions, limiter.clone());
+
+        let first = stream.next().expect("no result streamed");
+        assert!(matches!(first, States::Success { .. }));
+        assert!(limiter.stats().successes < 5);
+
+        assert_eq!(stream.count(), 4);
+        assert_eq!(limiter.stats().successes, 5);
+    }
+
+    #[test]
+    fn test_cancelled_run_returns_partial_stats() {
+        let params = Arc::new(test_params(100_000, 100));
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let transactions: Vec<_> = (0..100)
+            .map(|_| {
+                TestTransaction::new_stable_min(&params)
+                    .with_delay(std::time::Duration::from_millis(10))
+            })
+            .collect();
+
+        let handle = spawn_transaction_process(1, transactions, limiter);
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+                handle.cancel();
+            });
+        });
+        let (results, stats) = handle.join();
+
+        assert!(stats.successes > 0 && stats.successes < 100, "{:?}", stats);
+        assert_eq!(results.len() as u64, stats.successes);
+        assert_eq!(stats.committed_amount, 190 * stats.successes as i64);
+    }
+
+    #[test]
+    fn test_commission_is_converted_into_budget_units() {
+        // Price 100, commission 90 tokens at 0.5 -> 145 budget units per transaction.
+        let params = Arc::new(EnvParams {
+            fee_to_budget_rate: Some(0.5),
+            ..test_params(300, 10)
+        });
+        let limiter = LimitChecker::new(&params);
+        let tx = TestTransaction::new_stable_min(&params);
+
+        assert_eq!(limiter.budget_amount(&tx), 145);
+        assert!(matches!(
+            limiter.process_transaction(&tx),
+            Ok(States::Success { amount: 145, .. })
+        ));
+        assert!(matches!(
+            limiter.process_transaction(&tx),
+            Ok(States::Success { amount: 145, .. })
+        ));
+        assert_eq!(limiter.committed_amount(), 290);
+
+        // A third transaction would bring the committed amount to 435 > 300.
+        assert_eq!(limiter.process_transaction(&tx), Ok(States::Finish));
+        assert_eq!(limiter.stats().rejections.amount_cap, 1);
+    }
+
+    #[test]
+    fn test_simulated_raw_signatures_are_unique() {
+        let params = Arc::new(EnvParams {
+            commission_change: 0,
+            failure_rate: Some(0.0),
+            ..test_params(100_000, 30)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let mut generator = TransactionGenerator::new(params.clone());
+        generator.completed = Some(limiter.completed.clone());
+
+        let summary = run_transaction_process(1, generator, limiter.clone(), &Default::default());
+
+        assert_eq!(summary.successes, 30);
+        assert_eq!(limiter.stats().duplicate_signatures, 0);
+    }
+
+    #[test]
+    fn test_duplicate_signatures_are_detected() {
+        // `TestTransaction` returns its info as the signature, identical for identical params.
+        let params = Arc::new(test_params(10_000, 10));
+        let limiter = LimitChecker::new(&params);
+        let tx = TestTransaction::new_stable_min(&params);
+
+        for _ in 0..3 {
+            assert!(matches!(
+                limiter.process_transaction(&tx),
+                Ok(States::Success { .. })
+            ));
+        }
+
+        let stats = limiter.stats();
+        assert_eq!(stats.successes, 3);
+        assert_eq!(stats.duplicate_signatures, 2);
+    }
+
+    #[test]
+    fn test_starts_from_initial_committed_amount() {
+        let params = Arc::new(EnvParams {
+            initial_committed: 500,
+            initial_count: 3,
+            ..test_params(900, 5)
+        });
+        let limiter = LimitChecker::new(&params);
+        let tx = TestTransaction::new_stable_min(&params);
+
+        assert_eq!(limiter.snapshot().remaining_budget, 400);
+        assert!(matches!(
+            limiter.process_transaction(&tx),
+            Ok(States::Success { .. })
+        ));
+        assert!(matches!(
+            limiter.process_transaction(&tx),
+            Ok(States::Success { .. })
+        ));
+        assert_eq!(limiter.committed_amount(), 880);
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 5);
+
+        // The count cap is reached including the initial offset.
+        assert_eq!(limiter.process_transaction(&tx), Ok(States::Finish));
+        assert_eq!(limiter.stats().rejections.count_cap, 1);
+    }
+
+    #[test]
+    fn test_estimate_matches_deterministic_run() {
+        // Without commission variation every transaction costs exactly the average amount.
+        let params = Arc::new(EnvParams {
+            commission_change: 0,
+            ..test_params(1_050, 100)
+        });
+        let estimate = params.estimate();
+
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = (0..).map(|_| TestTransaction::new_stable_min(&params));
+        run_transaction_process(1, generator, limiter.clone(), &results);
+
+        let stats = limiter.stats();
+        assert_eq!(estimate.transactions, stats.transactions_count);
+        assert!((estimate.spend - stats.committed_amount as f64).abs() < params.average_amount());
+        assert_eq!(estimate.binding, Rejection::AmountCap);
+        assert_eq!(stats.rejections.amount_cap, 1);
+    }
+
+    #[test]
+    fn test_fair_tokens_share_the_threads() {
+        let params = Arc::new(EnvParams {
+            max_threads: 4,
+            tokens: vec!["A".to_string(), "B".to_string()],
+            fair_tokens: true,
+            ..test_params(1_000_000, 100)
+        });
+        let limiter = LimitChecker::new(&params);
+        let probe = Arc::new(TokenProbe::default());
+        let tx = |token: &str| {
+            TestTransaction::new_stable_min(&params)
+                .with_delay(std::time::Duration::from_millis(20))
+                .with_token(token, Some(&probe))
+        };
+
+        // Three threads flood token A while a single one sends token B.
+        std::thread::scope(|scope| {
+            for token in ["A", "A", "A", "B"] {
+                let (limiter, tx) = (&limiter, &tx);
+                scope.spawn(move || {
+                    for _ in 0..5 {
+                        assert!(matches!(
+                            limiter.process_transaction(&tx(token)),
+                            Ok(States::Success { .. })
+                        ));
+                    }
+                });
+            }
+        });
+
+        let max_in_flight = probe.max_in_flight.lock().unwrap();
+        assert_eq!(limiter.token_slots.as_ref().unwrap().share(), 2);
+        assert!(max_in_flight["A"] <= 2);
+        assert!(max_in_flight["B"] <= 2);
+        assert!(probe.overlaps.load(Ordering::SeqCst) > 0);
+        assert_eq!(limiter.stats().successes, 20);
+    }
+
+    #[test]
+    fn test_pre_execute_hook_vetoes_by_amount() {
+        let params = Arc::new(test_params(10_000, 10));
+        let limiter =
+            LimitChecker::new(&params).with_pre_execute(|tx: &dyn Transaction| match tx.amount() {
+                amount if amount > 200 => Err(format!("amount {} moved above 200", amount)),
+                _ => Ok(()),
+            });
+        let cheap = TestTransaction::new_stable_min(&params);
+        let expensive = TestTransaction {
+            adjusted_commission: 150,
+            ..TestTransaction::new_stable_min(&params)
+        };
+
+        assert_eq!(
+            limiter.process_transaction(&expensive),
+            Ok(States::Skipped(Rejection::Vetoed))
+        );
+        assert_eq!(limiter.transactions_count.load(Ordering::SeqCst), 0);
+        assert_eq!(limiter.committed_amount(), 0);
+
+        assert!(matches!(
+            limiter.process_transaction(&cheap),
+            Ok(States::Success { amount: 190, .. })
+        ));
+        let stats = limiter.stats();
+        assert_eq!(stats.rejections.vetoed, 1);
+        assert_eq!(stats.committed_amount, 190);
+    }
+
+    #[test]
+    fn test_deterministic_mode_gives_identical_output() {
+        let params = Arc::new(EnvParams {
+            max_threads: 4,
+            deterministic: true,
+            ..test_params(2_000, 100)
+        });
+        let run = || {
+            let limiter = Arc::new(LimitChecker::new(&params));
+            let results = Arc::new(Mutex::new(Vec::new()));
+            let generator = (0..20).map(|i| TestTransaction {
+                adjusted_commission: 50 + i * 7 % 40,
+                fail: i % 5 == 3,
+                ..TestTransaction::new_stable_min(&params)
+            });
+            run_transaction_process(params.max_threads, generator, limiter.clone(), &results);
+            (
+                JsonFormatter.format(&unwrap_results(results)),
+                limiter.stats().thread_utilization.per_thread,
+            )
+        };
+
+        let (first, threads) = run();
+        let (second, _) = run();
+        assert_eq!(first, second);
+        assert!(!first.is_empty());
+        // Everything ran on the single worker thread.
+        assert_eq!(threads.iter().filter(|count| **count > 0).count(), 1);
+    }
+
+    #[test]
+    fn test_dependent_transaction_waits_for_its_predecessor() {
+        let params = Arc::new(test_params(10_000, 10));
+        let tx = |id, depends_on, fail| {
+            let tx = if fail {
+                TestTransaction::new_failing(&params)
+            } else {
+                TestTransaction::new_stable_min(&params)
+            };
+            tx.with_id(id, depends_on)
+        };
+
+        // The swap (2) is generated before the approve (1) it depends on.
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = vec![tx(2, Some(1), false), tx(1, None, false)];
+        run_transaction_process(1, generator, limiter.clone(), &results);
+        let signatures: Vec<_> = unwrap_results(results)
+            .into_iter()
+            .map(|state| match state {
+                States::Success { signature, .. } => signature,
+                other => panic!("unexpected state {:?}", other),
+            })
+            .collect();
+        assert_eq!(signatures, vec!["sig-1", "sig-2"]);
+
+        // A failed approve leaves the swap unexecuted.
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = vec![tx(2, Some(1), false), tx(1, None, true)];
+        run_transaction_process(1, generator, limiter.clone(), &results);
+        let results = unwrap_results(results);
+        assert_eq!(results[1], States::Skipped(Rejection::UnmetDependency));
+        assert_eq!(limiter.stats().rejections.unmet_dependency, 1);
+        assert_eq!(limiter.stats().successes, 0);
+    }
+
+    #[test]
+    fn test_results_are_written_to_the_configured_file() {
+        let params = Arc::new(test_params(400, 10));
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = (0..).map(|_| TestTransaction::new_stable_min(&params));
+        run_transaction_process(1, generator, limiter, &results);
+        let results = unwrap_results(results);
+
+        let path = std::env::temp_dir().join(format!("results-{}.csv", std::process::id()));
+        let stream = OutputStream::File(path.to_string_lossy().into_owned());
+        display_results(&results, &CsvFormatter, &stream);
+        let written = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(written, CsvFormatter.format(&results));
+        let written = String::from_utf8(written).unwrap();
+        assert!(written.starts_with("index,status,amount,message\n1,success,190,"));
+        assert!(written.contains("\n2,success,190,"));
+    }
+
+    #[test]
+    fn test_results_reach_every_configured_sink() {
+        let csv_path = std::env::temp_dir().join(format!("sinks-{}.csv", std::process::id()));
+        let json_path = std::env::temp_dir().join(format!("sinks-{}.json", std::process::id()));
+        let params = Arc::new(EnvParams {
+            output_sinks: vec![
+                format!("csv:{}", csv_path.display()).parse().unwrap(),
+                format!("json:{}", json_path.display()).parse().unwrap(),
+            ],
+            ..test_params(400, 10)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = (0..).map(|_| TestTransaction::new_stable_min(&params));
+        run_transaction_process(1, generator, limiter, &results);
+        let results = unwrap_results(results);
+
+        display_results_to_sinks(&results, &params.output_sinks());
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        let json = std::fs::read_to_string(&json_path).unwrap();
+        std::fs::remove_file(&csv_path).unwrap();
+        std::fs::remove_file(&json_path).unwrap();
+
+        assert!(csv.contains("\n1,success,190,"));
+        assert!(json.starts_with(r#"[{"index":1,"status":"success","amount":190,"#));
+    }
+
+    #[test]
+    fn test_json_output_holds_the_results_and_the_summary() {
+        let path = std::env::temp_dir().join(format!("run-{}.json", std::process::id()));
+        let params = Arc::new(EnvParams {
+            output_format: OutputFormat::Json,
+            output_stream: OutputStream::File(path.to_string_lossy().into_owned()),
+            ..test_params(400, 10)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let transactions = vec![
+            TestTransaction::new_failing(&params),
+            TestTransaction::new_stable_min(&params),
+        ];
+        let summary = run_transaction_process(1, transactions, limiter, &results);
+        let results = unwrap_results(results);
+
+        display_run(&results, &summary, &params.output_sinks());
+        let json = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["results"][0]["status"], "failed");
+        assert_eq!(document["results"][0]["message"], "failed tx");
+        assert_eq!(document["results"][1]["status"], "success");
+        assert_eq!(document["results"][1]["amount"], 190);
+        let parsed: RunSummary = serde_json::from_value(document["summary"].clone()).unwrap();
+        assert_eq!(parsed, summary);
+        assert_eq!(parsed.successes, 1);
+        assert_eq!(parsed.failures, 1);
+    }
+
+    #[test]
+    fn test_reservations_prevent_over_commit() {
+        // Room for exactly 10 transactions of 190.
+        let params = Arc::new(test_params(1_900, 1_000));
+        let limiter = LimitChecker::new(&params);
+        let tx = TestTransaction::new_stable_min(&params)
+            .with_delay(std::time::Duration::from_millis(5));
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..5 {
+                        let _ = limiter.process_transaction(&tx);
+                        assert!(limiter.committed_amount() <= 1_900);
+                    }
+                });
+            }
+        });
+
+        let stats = limiter.stats();
+        assert_eq!(stats.successes, 10);
+        assert_eq!(limiter.settled_amount.load(Ordering::SeqCst), 1_900);
+        assert_eq!(limiter.reserved_amount.load(Ordering::SeqCst), 0);
+        assert_eq!(stats.rejections.amount_cap, 30);
+    }
+
+    #[test]
+    fn test_token_count_cap_binds_before_the_amount_cap() {
+        // The budget covers 10 bundles, but only 3 bundles of 4 tokens fit under 12 tokens.
+        let params = Arc::new(EnvParams {
+            max_tokens: Some(12),
+            ..test_params(1_900, 100)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = (0..).map(|_| TestTransaction::new_stable_min(&params).with_tokens(4));
+        run_transaction_process(1, generator, limiter.clone(), &results);
+
+        let stats = limiter.stats();
+        assert_eq!(stats.successes, 3);
+        assert_eq!(limiter.tokens_count.load(Ordering::SeqCst), 12);
+        assert_eq!(stats.committed_amount, 570);
+        assert_eq!(stats.rejections.token_cap, 1);
+        assert_eq!(stats.rejections.amount_cap, 0);
+        assert_eq!(unwrap_results(results).len(), 3);
+    }
+
+    #[test]
+    fn test_rollbacks_are_totalled() {
+        let params = Arc::new(test_params(10_000, 100));
+        let limiter = LimitChecker::new(&params);
+
+        for _ in 0..3 {
+            limiter
+                .process_transaction(&TestTransaction::new_failing(&params))
+                .unwrap();
+        }
+        limiter
+            .process_transaction(&TestTransaction::new_stable_min(&params))
+            .unwrap();
+
+        let stats = limiter.stats();
+        assert_eq!(stats.rollback_count, 3);
+        assert_eq!(stats.rolled_back_amount, 3 * 190);
+        assert_eq!(stats.transactions_count, 1);
+        assert_eq!(stats.committed_amount, 190);
+    }
+
+    #[test]
+    fn test_run_lasts_at_least_the_minimum_duration() {
+        // The budget is exhausted after 2 transactions.
+        let params = Arc::new(EnvParams {
+            min_duration_secs: Some(1),
+            ..test_params(400, 10)
+        });
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = (0..).map(|_| TestTransaction::new_stable_min(&params));
+
+        let started = std::time::Instant::now();
+        run_transaction_process(1, generator, limiter.clone(), &results);
+
+        assert!(started.elapsed() >= std::time::Duration::from_secs(1));
+        assert_eq!(limiter.stats().successes, 2);
+    }
+
+    #[test]
+    fn test_errors_are_counted_per_category() {
+        let params = Arc::new(EnvParams {
+            error_categories: vec![
+                "funds:insufficient funds".parse().unwrap(),
+                "timeout:timed out".parse().unwrap(),
+                "nonce:nonce".parse().unwrap(),
+            ],
+            ..test_params(10_000, 100)
+        });
+        let limiter = LimitChecker::new(&params);
+
+        for error in [
+            "Insufficient funds for fee",
+            "request timed out after 30s",
+            "insufficient funds",
+            "nonce too low",
+            "blockhash not found",
+        ] {
+            let tx = TestTransaction::new_stable_min(&params).with_error(error);
+            limiter.process_transaction(&tx).unwrap();
+        }
+
+        let categories = limiter.stats().error_categories;
+        assert_eq!(
+            categories.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("funds".to_string(), 2),
+                ("nonce".to_string(), 1),
+                ("other".to_string(), 1),
+                ("timeout".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_capped_wallet_is_skipped_while_others_continue() {
+        // Each wallet can afford 3 transactions of 190, the global budget many more.
+        let params = Arc::new(EnvParams {
+            wallets: vec!["A".to_string(), "B".to_string()],
+            per_wallet_amount_limit: Some(570),
+            ..test_params(10_000, 100)
+        });
+        let limiter = LimitChecker::new(&params);
+        let mut process = |wallet| {
+            let tx = TestTransaction::new_stable_min(&params).with_wallet(wallet);
+            limiter.process_transaction(&tx).unwrap()
+        };
+
+        let states: Vec<_> = ["A", "A", "A", "A", "B", "B", "B"]
+            .into_iter()
+            .map(&mut process)
+            .collect();
+        assert_eq!(states[3], States::Skipped(Rejection::WalletCap));
+        assert!(states[4..]
+            .iter()
+            .all(|state| matches!(state, States::Success { .. })));
+
+        // Once every wallet is capped, the run finishes.
+        assert_eq!(process("B"), States::Finish);
+
+        let stats = limiter.stats();
+        assert_eq!(stats.successes, 6);
+        assert_eq!(stats.rejections.wallet_cap, 2);
+        assert_eq!(limiter.wallet_amounts.lock().unwrap()["A"], 570);
+        assert_eq!(limiter.wallet_amounts.lock().unwrap()["B"], 570);
+    }
+
+    #[test]
+    fn test_results_keep_the_first_success_per_token() {
+        let params = Arc::new(EnvParams {
+            dedupe_by_token: true,
+            ..test_params(10_000, 5)
+        });
+        let probe = Arc::new(TokenProbe::default());
+        let tx =
+            |token: &str| TestTransaction::new_stable_min(&params).with_token(token, Some(&probe));
+        let limiter = Arc::new(LimitChecker::new(&params));
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let generator = vec![tx("A"), tx("A"), tx("B"), tx("A"), tx("B")];
+        run_transaction_process(1, generator, limiter.clone(), &results);
+
+        assert_eq!(unwrap_results(results).len(), 2);
+        assert_eq!(limiter.stats().successes, 5);
+        assert_eq!(limiter.stats().transactions_count, 5);
     }
 }